@@ -27,6 +27,8 @@ pub(crate) fn valid(src: &str) -> usize {
 ///
 /// Each value is paired together with an [`AttributeKind`] in order to form an element.
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct AttributeValue<'s> {
     raw: CowStr<'s>,
 }
@@ -38,6 +40,15 @@ impl<'s> AttributeValue<'s> {
         Self::default()
     }
 
+    /// Convert into an attribute value that owns its content and no longer borrows from the
+    /// input.
+    #[must_use]
+    pub fn into_owned(self) -> AttributeValue<'static> {
+        AttributeValue {
+            raw: self.raw.into_owned().into(),
+        }
+    }
+
     /// Processes the attribute value escapes and returns an iterator of the parts of the value
     /// that should be displayed.
     pub fn parts(&'s self) -> AttributeValueParts<'s> {
@@ -131,7 +142,8 @@ impl<'s> Iterator for AttributeValueParts<'s> {
 /// The kind of an element within an attribute set.
 ///
 /// Each kind is paired together with an [`AttributeValue`] to form an element.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AttributeKind<'s> {
     /// A class element, e.g. `.a`.
     ///
@@ -166,15 +178,15 @@ pub enum AttributeKind<'s> {
     ///     .into_iter();
     /// assert_eq!(
     ///     a.next(),
-    ///     Some((AttributeKind::Pair { key: "key" }, "value".into())),
+    ///     Some((AttributeKind::Pair { key: "key".into() }, "value".into())),
     /// );
     /// assert_eq!(
     ///     a.next(),
-    ///     Some((AttributeKind::Pair { key: "id" }, "a".into())),
+    ///     Some((AttributeKind::Pair { key: "id".into() }, "a".into())),
     /// );
     /// assert_eq!(a.next(), None);
     /// ```
-    Pair { key: &'s str },
+    Pair { key: CowStr<'s> },
     /// A comment element, e.g. `%cmt%`.
     ///
     /// # Examples
@@ -192,14 +204,27 @@ pub enum AttributeKind<'s> {
 impl<'s> AttributeKind<'s> {
     /// Returns the element's key, if applicable.
     #[must_use]
-    pub fn key(&self) -> Option<&'s str> {
+    pub fn key(&self) -> Option<&str> {
         match self {
             AttributeKind::Class => Some("class"),
             AttributeKind::Id => Some("id"),
-            AttributeKind::Pair { key } => Some(key),
+            AttributeKind::Pair { key } => Some(key.as_ref()),
             AttributeKind::Comment => None,
         }
     }
+
+    /// Convert into an element kind that owns its key and no longer borrows from the input.
+    #[must_use]
+    pub fn into_owned(self) -> AttributeKind<'static> {
+        match self {
+            AttributeKind::Class => AttributeKind::Class,
+            AttributeKind::Id => AttributeKind::Id,
+            AttributeKind::Pair { key } => AttributeKind::Pair {
+                key: key.into_owned().into(),
+            },
+            AttributeKind::Comment => AttributeKind::Comment,
+        }
+    }
 }
 
 /// A set of attributes, with order, duplicates and comments preserved.
@@ -232,9 +257,9 @@ impl<'s> AttributeKind<'s> {
 ///     vec![
 ///         (AttributeKind::Id, "a".into()),
 ///         (AttributeKind::Class, "b".into()),
-///         (AttributeKind::Pair { key: "id" }, "c".into()),
-///         (AttributeKind::Pair { key: "class" }, "d".into()),
-///         (AttributeKind::Pair { key: "key" }, "val".into()),
+///         (AttributeKind::Pair { key: "id".into() }, "c".into()),
+///         (AttributeKind::Pair { key: "class".into() }, "d".into()),
+///         (AttributeKind::Pair { key: "key".into() }, "val".into()),
 ///         (AttributeKind::Comment, "comment".into()),
 ///     ],
 /// );
@@ -255,8 +280,8 @@ impl<'s> AttributeKind<'s> {
 /// assert_eq!(
 ///     attrs.as_slice(),
 ///     &[
-///         (AttributeKind::Pair { key: "key1" }, "val1".into()),
-///         (AttributeKind::Pair { key: "key2" }, "new_val".into()),
+///         (AttributeKind::Pair { key: "key1".into() }, "val1".into()),
+///         (AttributeKind::Pair { key: "key2".into() }, "new_val".into()),
 ///     ]
 /// );
 /// ```
@@ -274,6 +299,8 @@ impl<'s> AttributeKind<'s> {
 /// assert_eq!(a, b);
 /// ```
 #[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Attributes<'s>(Vec<AttributeElem<'s>>);
 
 type AttributeElem<'s> = (AttributeKind<'s>, AttributeValue<'s>);
@@ -285,6 +312,29 @@ impl<'s> Attributes<'s> {
         Self::default()
     }
 
+    /// Convert into a set of attributes that owns all of its elements and no longer borrows
+    /// from the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let a: Attributes<'static> = {
+    ///     let src = String::from("{.a key=val}");
+    ///     Attributes::try_from(src.as_str()).unwrap().into_owned()
+    /// };
+    /// assert_eq!(a.get_value("key"), Some("val".into()));
+    /// ```
+    #[must_use]
+    pub fn into_owned(self) -> Attributes<'static> {
+        Attributes(
+            self.0
+                .into_iter()
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect(),
+        )
+    }
+
     #[must_use]
     pub(crate) fn take(&mut self) -> Self {
         std::mem::take(self)
@@ -344,7 +394,7 @@ impl<'s> Attributes<'s> {
     /// );
     /// ```
     #[must_use]
-    pub fn get_value(&self, key: &str) -> Option<AttributeValue> {
+    pub fn get_value(&self, key: &str) -> Option<AttributeValue<'_>> {
         if key == "class"
             && self
                 .0
@@ -564,14 +614,14 @@ impl<'s> IntoIterator for Attributes<'s> {
     /// assert_eq!(
     ///     elems.next(),
     ///     Some((
-    ///         AttributeKind::Pair { key: "key1" },
+    ///         AttributeKind::Pair { key: "key1".into() },
     ///         AttributeValue::from("val1"),
     ///     )),
     /// );
     /// assert_eq!(
     ///     elems.next(),
     ///     Some((
-    ///         AttributeKind::Pair { key: "key2" },
+    ///         AttributeKind::Pair { key: "key2".into() },
     ///         AttributeValue::from("val2"),
     ///     )),
     /// );
@@ -598,14 +648,14 @@ impl<'i, 's> IntoIterator for &'i Attributes<'s> {
     /// assert_eq!(
     ///     elems.next(),
     ///     Some(&(
-    ///         AttributeKind::Pair { key: "key1" },
+    ///         AttributeKind::Pair { key: "key1".into() },
     ///         AttributeValue::from("val1"),
     ///     )),
     /// );
     /// assert_eq!(
     ///     elems.next(),
     ///     Some(&(
-    ///         AttributeKind::Pair { key: "key2" },
+    ///         AttributeKind::Pair { key: "key2".into() },
     ///         AttributeValue::from("val2"),
     ///     )),
     /// );
@@ -632,14 +682,14 @@ impl<'i, 's> IntoIterator for &'i mut Attributes<'s> {
     /// assert_eq!(
     ///     elems.next(),
     ///     Some(&mut (
-    ///         AttributeKind::Pair { key: "key1" },
+    ///         AttributeKind::Pair { key: "key1".into() },
     ///         AttributeValue::from("val1"),
     ///     )),
     /// );
     /// assert_eq!(
     ///     elems.next(),
     ///     Some(&mut (
-    ///         AttributeKind::Pair { key: "key2" },
+    ///         AttributeKind::Pair { key: "key2".into() },
     ///         AttributeValue::from("val2"),
     ///     )),
     /// );
@@ -768,9 +818,12 @@ impl<'s> Parser<'s> {
                 match st {
                     Class => self.attrs.push((AttributeKind::Class, content.into())),
                     Identifier => self.attrs.push((AttributeKind::Id, content.into())),
-                    Key => self
-                        .attrs
-                        .push((AttributeKind::Pair { key: content }, "".into())),
+                    Key => self.attrs.push((
+                        AttributeKind::Pair {
+                            key: content.into(),
+                        },
+                        "".into(),
+                    )),
                     Value | ValueQuoted | ValueContinued => {
                         let last = self.attrs.len() - 1;
                         self.attrs.0[last]
@@ -920,8 +973,18 @@ mod test {
         test_attr!(
             "{attr0=val0 attr1=val1}",
             [
-                (Pair { key: "attr0" }, "val0"),
-                (Pair { key: "attr1" }, "val1"),
+                (
+                    Pair {
+                        key: "attr0".into()
+                    },
+                    "val0"
+                ),
+                (
+                    Pair {
+                        key: "attr1".into()
+                    },
+                    "val1"
+                ),
             ],
             [("attr0", "val0"), ("attr1", "val1")],
         );
@@ -932,8 +995,18 @@ mod test {
         test_attr!(
             r#"{attr0="val0" attr1="val1"}"#,
             [
-                (Pair { key: "attr0" }, "val0"),
-                (Pair { key: "attr1" }, "val1"),
+                (
+                    Pair {
+                        key: "attr0".into()
+                    },
+                    "val0"
+                ),
+                (
+                    Pair {
+                        key: "attr1".into()
+                    },
+                    "val1"
+                ),
             ],
             [("attr0", "val0"), ("attr1", "val1")],
         );
@@ -942,7 +1015,12 @@ mod test {
             [
                 (Id, "id"),
                 (Class, "class"),
-                (Pair { key: "style" }, "color:red"),
+                (
+                    Pair {
+                        key: "style".into()
+                    },
+                    "color:red"
+                ),
             ],
             [("id", "id"), ("class", "class"), ("style", "color:red")]
         );
@@ -952,7 +1030,12 @@ mod test {
     fn value_newline() {
         test_attr!(
             "{attr0=\"abc\ndef\"}",
-            [(Pair { key: "attr0" }, "abc def")],
+            [(
+                Pair {
+                    key: "attr0".into()
+                },
+                "abc def"
+            )],
             [("attr0", "abc def")]
         );
     }
@@ -987,12 +1070,12 @@ mod test {
     fn escape() {
         test_attr!(
             r#"{attr="with escaped \~ char"}"#,
-            [(Pair { key: "attr" }, "with escaped ~ char")],
+            [(Pair { key: "attr".into() }, "with escaped ~ char")],
             [("attr", "with escaped ~ char")]
         );
         test_attr!(
             r#"{key="quotes \" should be escaped"}"#,
-            [(Pair { key: "key" }, r#"quotes " should be escaped"#)],
+            [(Pair { key: "key".into() }, r#"quotes " should be escaped"#)],
             [("key", r#"quotes " should be escaped"#)]
         );
     }
@@ -1001,17 +1084,17 @@ mod test {
     fn escape_backslash() {
         test_attr!(
             r#"{attr="with\\backslash"}"#,
-            [(Pair { key: "attr" }, r"with\backslash")],
+            [(Pair { key: "attr".into() }, r"with\backslash")],
             [("attr", r"with\backslash")]
         );
         test_attr!(
             r#"{attr="with many backslashes\\\\"}"#,
-            [(Pair { key: "attr" }, r"with many backslashes\\")],
+            [(Pair { key: "attr".into() }, r"with many backslashes\\")],
             [("attr", r"with many backslashes\\")]
         );
         test_attr!(
             r#"{attr="\\escaped backslash at start"}"#,
-            [(Pair { key: "attr" }, r"\escaped backslash at start")],
+            [(Pair { key: "attr".into() }, r"\escaped backslash at start")],
             [("attr", r"\escaped backslash at start")]
         );
     }
@@ -1020,12 +1103,12 @@ mod test {
     fn only_escape_punctuation() {
         test_attr!(
             r#"{attr="do not \escape"}"#,
-            [(Pair { key: "attr" }, r"do not \escape")],
+            [(Pair { key: "attr".into() }, r"do not \escape")],
             [("attr", r"do not \escape")]
         );
         test_attr!(
             r#"{attr="\backslash at the beginning"}"#,
-            [(Pair { key: "attr" }, r"\backslash at the beginning")],
+            [(Pair { key: "attr".into() }, r"\backslash at the beginning")],
             [("attr", r"\backslash at the beginning")]
         );
     }
@@ -0,0 +1,345 @@
+//! A minimal JSON value with a parser and a serializer, for the messages of the protocol.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Members of an object, in the order they appear.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Get a member of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        if let Value::Object(members) = self {
+            members.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+        } else {
+            None
+        }
+    }
+
+    /// Get a member of nested objects, e.g. `["textDocument", "uri"]`.
+    pub fn pointer(&self, keys: &[&str]) -> Option<&Value> {
+        keys.iter().try_fold(self, |v, k| v.get(k))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Value::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        if let Value::Number(n) = self {
+            if *n >= 0.0 && n.fract() == 0.0 {
+                return Some(*n as usize);
+            }
+        }
+        None
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        if let Value::Array(a) = self {
+            Some(a)
+        } else {
+            None
+        }
+    }
+
+    /// Parse a JSON document.
+    pub fn parse(src: &str) -> Result<Value, String> {
+        let mut p = Parser { src, pos: 0 };
+        let v = p.value()?;
+        p.whitespace();
+        if p.pos < src.len() {
+            return Err(p.error("trailing characters"));
+        }
+        Ok(v)
+    }
+}
+
+/// Create an object from its members.
+pub fn object<I>(members: I) -> Value
+where
+    I: IntoIterator<Item = (&'static str, Value)>,
+{
+    Value::Object(
+        members
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(a: Vec<T>) -> Self {
+        Value::Array(a.into_iter().map(Into::into).collect())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => {
+                if n.fract() == 0.0 && n.abs() < 1e15 {
+                    write!(f, "{}", *n as i64)
+                } else {
+                    write!(f, "{}", n)
+                }
+            }
+            Value::String(s) => write_string(s, f),
+            Value::Array(a) => {
+                f.write_str("[")?;
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_str("]")
+            }
+            Value::Object(members) => {
+                f.write_str("{")?;
+                for (i, (k, v)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(k, f)?;
+                    write!(f, ":{}", v)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn error(&self, msg: &str) -> String {
+        format!("{} at byte {}", msg, self.pos)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.src[self.pos..].starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                self.whitespace();
+                if self.eat("}") {
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.whitespace();
+                    if !self.eat(":") {
+                        return Err(self.error("expected ':'"));
+                    }
+                    members.push((key, self.value()?));
+                    self.whitespace();
+                    if self.eat("}") {
+                        return Ok(Value::Object(members));
+                    } else if !self.eat(",") {
+                        return Err(self.error("expected ',' or '}'"));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                self.whitespace();
+                if self.eat("]") {
+                    return Ok(Value::Array(elements));
+                }
+                loop {
+                    elements.push(self.value()?);
+                    self.whitespace();
+                    if self.eat("]") {
+                        return Ok(Value::Array(elements));
+                    } else if !self.eat(",") {
+                        return Err(self.error("expected ',' or ']'"));
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while matches!(
+                    self.peek(),
+                    Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                ) {
+                    self.pos += 1;
+                }
+                self.src[start..self.pos]
+                    .parse()
+                    .map(Value::Number)
+                    .map_err(|_| self.error("invalid number"))
+            }
+            _ if self.eat("null") => Ok(Value::Null),
+            _ if self.eat("true") => Ok(Value::Bool(true)),
+            _ if self.eat("false") => Ok(Value::Bool(false)),
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.eat("\"") {
+            return Err(self.error("expected string"));
+        }
+        let mut s = String::new();
+        loop {
+            let rest = &self.src[self.pos..];
+            let end = rest
+                .find(|c| c == '"' || c == '\\')
+                .ok_or_else(|| self.error("unterminated string"))?;
+            s.push_str(&rest[..end]);
+            self.pos += end;
+            if self.eat("\"") {
+                return Ok(s);
+            }
+            self.pos += 1;
+            let c = match self.peek() {
+                Some(b'"') => '"',
+                Some(b'\\') => '\\',
+                Some(b'/') => '/',
+                Some(b'b') => '\u{8}',
+                Some(b'f') => '\u{c}',
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                Some(b't') => '\t',
+                Some(b'u') => {
+                    self.pos += 1;
+                    let hi = self.hex()?;
+                    let c = if (0xd800..0xdc00).contains(&hi) && self.eat("\\u") {
+                        let lo = self.hex()?;
+                        char::from_u32(0x10000 + ((hi - 0xd800) << 10) + (lo.wrapping_sub(0xdc00)))
+                    } else {
+                        char::from_u32(hi)
+                    };
+                    s.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                    continue;
+                }
+                _ => return Err(self.error("invalid escape")),
+            };
+            s.push(c);
+            self.pos += 1;
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let n =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Value;
+
+    #[test]
+    fn roundtrip() {
+        let src = r#"{"a":[1,-2.5,true,null],"b":{"c":"d\"\né"},"e":[]}"#;
+        let v = Value::parse(src).unwrap();
+        assert_eq!(
+            v.pointer(&["b", "c"]).and_then(Value::as_str),
+            Some("d\"\né")
+        );
+        assert_eq!(
+            v.to_string(),
+            r#"{"a":[1,-2.5,true,null],"b":{"c":"d\"\né"},"e":[]}"#
+        );
+    }
+
+    #[test]
+    fn surrogates() {
+        let v = Value::parse(r#" "\ud83d\ude00" "#).unwrap();
+        assert_eq!(v.as_str(), Some("😀"));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Value::parse("{").is_err());
+        assert!(Value::parse("[1,]").is_err());
+        assert!(Value::parse("\"a").is_err());
+        assert!(Value::parse("1 2").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! A language server for djot documents that communicates over stdin and stdout.
+
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::process::exit;
+
+mod json;
+mod server;
+
+use json::Value;
+use server::Server;
+
+/// Read a message, framed by its headers, or `None` at the end of the input.
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                len = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let len = len.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut content = vec![0; len];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message<W: Write>(output: &mut W, msg: &Value) -> io::Result<()> {
+    let content = msg.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+/// Handle messages until the exit notification or the end of the input.
+fn run<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<Server> {
+    let mut server = Server::default();
+    while !server.exit {
+        let content = match read_message(&mut input)? {
+            Some(content) => content,
+            None => break,
+        };
+        match Value::parse(&content) {
+            Ok(msg) => {
+                for response in server.handle(&msg) {
+                    write_message(&mut output, &response)?;
+                }
+            }
+            Err(e) => eprintln!("invalid message: {}", e),
+        }
+    }
+    Ok(server)
+}
+
+fn main() {
+    if std::env::args().skip(1).any(|a| a == "-h" || a == "--help") {
+        eprintln!("usage: jotdown-lsp\n\nlanguage server for djot, over stdin and stdout");
+        exit(0);
+    }
+    match run(io::stdin().lock(), io::stdout().lock()) {
+        Ok(server) => exit(if server.shutdown { 0 } else { 1 }),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::json::Value;
+
+    fn frame(msgs: &[&str]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for msg in msgs {
+            input.extend(format!("Content-Length: {}\r\n\r\n{}", msg.len(), msg).bytes());
+        }
+        input
+    }
+
+    /// Run the server with the messages as input and return the messages it sends back.
+    fn run(msgs: &[&str]) -> Vec<Value> {
+        let input = frame(msgs);
+        let mut output = Vec::new();
+        let server = super::run(&input[..], &mut output).unwrap();
+        assert!(server.shutdown && server.exit);
+        let mut output = &output[..];
+        let mut responses = Vec::new();
+        while let Some(msg) = super::read_message(&mut output).unwrap() {
+            responses.push(Value::parse(&msg).unwrap());
+        }
+        responses
+    }
+
+    fn result(responses: &[Value], id: usize) -> &Value {
+        responses
+            .iter()
+            .find(|r| r.get("id").and_then(Value::as_usize) == Some(id))
+            .and_then(|r| r.get("result"))
+            .unwrap()
+    }
+
+    const DOC: &str = concat!(
+        "# Heading\\n",
+        "\\n",
+        "See [text][a], [](#Sub) and[^n].\\n",
+        "\\n",
+        "## Sub\\n",
+        "\\n",
+        "::: note\\n",
+        "_b_\\n",
+        ":::\\n",
+        "\\n",
+        "[a]: /url\\n",
+        "\\n",
+        "[^n]: note\\n",
+        "\\n",
+        "[unused]: /x\\n",
+    );
+
+    fn session(requests: &[&str]) -> Vec<Value> {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"file:///a.dj","languageId":"djot","version":1,"text":"{}"}}}}}}"#,
+            DOC
+        );
+        let mut msgs = vec![
+            r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+            &open,
+        ];
+        msgs.extend(requests);
+        msgs.push(r#"{"jsonrpc":"2.0","id":99,"method":"shutdown"}"#);
+        msgs.push(r#"{"jsonrpc":"2.0","method":"exit"}"#);
+        run(&msgs)
+    }
+
+    #[test]
+    fn initialize() {
+        let responses = session(&[]);
+        let caps = result(&responses, 0).get("capabilities").unwrap();
+        assert_eq!(
+            caps.pointer(&["textDocumentSync", "change"])
+                .and_then(Value::as_usize),
+            Some(2)
+        );
+        assert_eq!(result(&responses, 99), &Value::Null);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let responses = session(&[]);
+        let diagnostics = responses
+            .iter()
+            .find(|r| {
+                r.get("method").and_then(Value::as_str) == Some("textDocument/publishDiagnostics")
+            })
+            .and_then(|r| r.pointer(&["params", "diagnostics"]))
+            .and_then(Value::as_array)
+            .unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].get("message").and_then(Value::as_str),
+            Some("unused link definition: unused")
+        );
+        assert_eq!(
+            diagnostics[0].pointer(&["range", "start", "line"]),
+            Some(&Value::Number(14.0))
+        );
+    }
+
+    #[test]
+    fn document_symbol() {
+        let responses = session(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/documentSymbol","params":{"textDocument":{"uri":"file:///a.dj"}}}"#,
+        ]);
+        let symbols = result(&responses, 1).as_array().unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(
+            symbols[0].get("name").and_then(Value::as_str),
+            Some("Heading")
+        );
+        let children = symbols[0]
+            .get("children")
+            .and_then(Value::as_array)
+            .unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].get("name").and_then(Value::as_str), Some("Sub"));
+        assert_eq!(
+            children[0].pointer(&["selectionRange", "start", "line"]),
+            Some(&Value::Number(4.0))
+        );
+    }
+
+    #[test]
+    fn definition() {
+        let responses = session(&[
+            // [text][a]
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.dj"},"position":{"line":2,"character":6}}}"#,
+            // [](#Sub)
+            r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.dj"},"position":{"line":2,"character":20}}}"#,
+            // [^n]
+            r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.dj"},"position":{"line":2,"character":28}}}"#,
+            // plain text
+            r#"{"jsonrpc":"2.0","id":4,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.dj"},"position":{"line":2,"character":1}}}"#,
+        ]);
+        let line = |id| {
+            result(&responses, id)
+                .pointer(&["range", "start", "line"])
+                .and_then(Value::as_usize)
+        };
+        assert_eq!(line(1), Some(10));
+        assert_eq!(line(2), Some(4));
+        assert_eq!(line(3), Some(12));
+        assert_eq!(result(&responses, 4), &Value::Null);
+    }
+
+    #[test]
+    fn completion() {
+        let responses = session(&[
+            r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.dj","version":2},"contentChanges":[{"range":{"start":{"line":2,"character":0},"end":{"line":2,"character":0}},"text":"[x](#"}]}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/completion","params":{"textDocument":{"uri":"file:///a.dj"},"position":{"line":2,"character":5}}}"#,
+        ]);
+        let labels: Vec<_> = result(&responses, 1)
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|i| i.get("label").and_then(Value::as_str))
+            .collect();
+        assert_eq!(labels, &["Heading", "Sub"]);
+    }
+
+    #[test]
+    fn folding_range() {
+        let responses = session(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/foldingRange","params":{"textDocument":{"uri":"file:///a.dj"}}}"#,
+        ]);
+        let ranges: Vec<_> = result(&responses, 1)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                (
+                    r.get("startLine").and_then(Value::as_usize).unwrap(),
+                    r.get("endLine").and_then(Value::as_usize).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(ranges, &[(0, 14), (4, 14), (6, 8)]);
+    }
+
+    #[test]
+    fn semantic_tokens() {
+        let responses = session(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/semanticTokens/full","params":{"textDocument":{"uri":"file:///a.dj"}}}"#,
+        ]);
+        let data = result(&responses, 1).get("data").unwrap();
+        // last tokens are the markers and content of the emphasis `_b_` on line 7
+        let data: Vec<_> = data
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_usize)
+            .collect();
+        assert_eq!(
+            data[data.len() - 15..],
+            [5, 0, 1, 11, 0, 0, 1, 1, 11, 0, 0, 1, 1, 11, 0]
+        );
+    }
+
+    #[test]
+    fn unknown_method() {
+        let responses = session(&[r#"{"jsonrpc":"2.0","id":1,"method":"foo/bar"}"#]);
+        let error = responses
+            .iter()
+            .find(|r| r.get("id").and_then(Value::as_usize) == Some(1))
+            .and_then(|r| r.get("error"))
+            .unwrap();
+        assert_eq!(error.get("code"), Some(&Value::Number(-32601.0)));
+    }
+}
@@ -0,0 +1,625 @@
+//! Handlers for the requests and notifications of the Language Server Protocol.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use jotdown::Container;
+use jotdown::DiagnosticKind;
+use jotdown::Event;
+use jotdown::IncrementalParser;
+use jotdown::LineIndex;
+use jotdown::LinkType;
+use jotdown::Position;
+use jotdown::PositionEncoding;
+use jotdown::SpanLinkType;
+
+use crate::json::object;
+use crate::json::Value;
+
+/// Types of the semantic tokens, one for each kind of inline container.
+const TOKEN_TYPES: &[&str] = &[
+    "span",
+    "link",
+    "image",
+    "verbatim",
+    "math",
+    "rawInline",
+    "subscript",
+    "superscript",
+    "insert",
+    "delete",
+    "strong",
+    "emphasis",
+    "mark",
+];
+
+/// `SymbolKind.String`, used for headings.
+const SYMBOL_KIND_HEADING: usize = 15;
+/// `CompletionItemKind.Reference`.
+const COMPLETION_KIND_REFERENCE: usize = 18;
+
+/// Error code of requests with an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Default)]
+pub struct Server {
+    /// Open documents by URI.
+    documents: HashMap<String, IncrementalParser>,
+    /// A shutdown request has been received.
+    pub shutdown: bool,
+    /// An exit notification has been received.
+    pub exit: bool,
+}
+
+impl Server {
+    /// Handle a message from the client and return the messages to send back.
+    pub fn handle(&mut self, msg: &Value) -> Vec<Value> {
+        let method = msg.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = msg.get("params").unwrap_or(&Value::Null);
+        let uri = params
+            .pointer(&["textDocument", "uri"])
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        let result = match method {
+            "initialize" => Some(initialize()),
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Value::Null)
+            }
+            "exit" => {
+                self.exit = true;
+                None
+            }
+            "textDocument/didOpen" => {
+                let text = params
+                    .pointer(&["textDocument", "text"])
+                    .and_then(Value::as_str)
+                    .unwrap_or("");
+                self.documents
+                    .insert(uri.clone(), IncrementalParser::new(text.to_string()));
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didChange" => {
+                if let Some(doc) = self.documents.get_mut(&uri) {
+                    let changes = params
+                        .get("contentChanges")
+                        .and_then(Value::as_array)
+                        .unwrap_or(&[]);
+                    for change in changes {
+                        apply_change(doc, change);
+                    }
+                }
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![notification(
+                    "textDocument/publishDiagnostics",
+                    object(vec![
+                        ("uri", uri.into()),
+                        ("diagnostics", Value::Array(Vec::new())),
+                    ]),
+                )];
+            }
+            "textDocument/documentSymbol" => self.documents.get(&uri).map(document_symbols),
+            "textDocument/definition" => self.documents.get(&uri).map(|doc| {
+                let index = LineIndex::new(doc.src());
+                let offset = offset(&index, params.get("position"));
+                definition(doc, offset).map_or(Value::Null, |r| {
+                    object(vec![
+                        ("uri", uri.clone().into()),
+                        ("range", range(&index, r)),
+                    ])
+                })
+            }),
+            "textDocument/completion" => self.documents.get(&uri).map(|doc| {
+                let offset = offset(&LineIndex::new(doc.src()), params.get("position"));
+                completion(doc, offset)
+            }),
+            "textDocument/foldingRange" => self.documents.get(&uri).map(folding_ranges),
+            "textDocument/semanticTokens/full" => self.documents.get(&uri).map(semantic_tokens),
+            _ => None,
+        };
+
+        match (msg.get("id"), result) {
+            (Some(id), Some(result)) => vec![object(vec![
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("result", result),
+            ])],
+            (Some(id), None) if method != "exit" => {
+                let message = if self.documents.contains_key(&uri) || uri.is_empty() {
+                    format!("unknown method: {}", method)
+                } else {
+                    format!("unknown document: {}", uri)
+                };
+                vec![object(vec![
+                    ("jsonrpc", "2.0".into()),
+                    ("id", id.clone()),
+                    (
+                        "error",
+                        object(vec![
+                            ("code", Value::Number(METHOD_NOT_FOUND as f64)),
+                            ("message", message.into()),
+                        ]),
+                    ),
+                ])]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let diagnostics = self.documents.get(uri).map_or_else(Vec::new, |doc| {
+            let index = LineIndex::new(doc.src());
+            doc.diagnostics()
+                .into_iter()
+                .map(|d| {
+                    let (severity, message) = match &d.kind {
+                        DiagnosticKind::UnresolvedReference { label } => {
+                            (2usize, format!("unresolved reference: {}", label))
+                        }
+                        DiagnosticKind::UnresolvedFootnote { label } => {
+                            (2usize, format!("unresolved footnote: {}", label))
+                        }
+                        DiagnosticKind::DuplicateDefinition { label } => {
+                            (2usize, format!("duplicate link definition: {}", label))
+                        }
+                        DiagnosticKind::DuplicateFootnote { label } => {
+                            (2usize, format!("duplicate footnote: {}", label))
+                        }
+                        DiagnosticKind::UnusedDefinition { label } => {
+                            (3usize, format!("unused link definition: {}", label))
+                        }
+                        DiagnosticKind::UnusedFootnote { label } => {
+                            (3usize, format!("unused footnote: {}", label))
+                        }
+                        DiagnosticKind::DuplicateId { id, .. } => {
+                            (2usize, format!("duplicate id: {}", id))
+                        }
+                    };
+                    object(vec![
+                        ("range", range(&index, d.range)),
+                        ("severity", severity.into()),
+                        ("source", "jotdown".into()),
+                        ("message", message.into()),
+                    ])
+                })
+                .collect()
+        });
+        notification(
+            "textDocument/publishDiagnostics",
+            object(vec![
+                ("uri", uri.into()),
+                ("diagnostics", Value::Array(diagnostics)),
+            ]),
+        )
+    }
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn initialize() -> Value {
+    object(vec![
+        (
+            "capabilities",
+            object(vec![
+                ("positionEncoding", "utf-16".into()),
+                (
+                    "textDocumentSync",
+                    object(vec![
+                        ("openClose", true.into()),
+                        // incremental
+                        ("change", 2usize.into()),
+                    ]),
+                ),
+                ("documentSymbolProvider", true.into()),
+                ("definitionProvider", true.into()),
+                (
+                    "completionProvider",
+                    object(vec![("triggerCharacters", vec!["[", "^", "#"].into())]),
+                ),
+                ("foldingRangeProvider", true.into()),
+                (
+                    "semanticTokensProvider",
+                    object(vec![
+                        (
+                            "legend",
+                            object(vec![
+                                ("tokenTypes", TOKEN_TYPES.to_vec().into()),
+                                ("tokenModifiers", Value::Array(Vec::new())),
+                            ]),
+                        ),
+                        ("full", true.into()),
+                    ]),
+                ),
+            ]),
+        ),
+        (
+            "serverInfo",
+            object(vec![
+                ("name", "jotdown-lsp".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+/// Convert an LSP position, with a line and character counted from 0, to a byte offset.
+fn offset(index: &LineIndex, position: Option<&Value>) -> usize {
+    let line = position
+        .and_then(|p| p.get("line"))
+        .and_then(Value::as_usize);
+    let character = position
+        .and_then(|p| p.get("character"))
+        .and_then(Value::as_usize);
+    index.offset(
+        Position {
+            line: line.unwrap_or(0) + 1,
+            column: character.unwrap_or(0) + 1,
+        },
+        PositionEncoding::Utf16,
+    )
+}
+
+fn position(index: &LineIndex, offset: usize) -> Value {
+    let p = index.position(offset, PositionEncoding::Utf16);
+    object(vec![
+        ("line", (p.line - 1).into()),
+        ("character", (p.column - 1).into()),
+    ])
+}
+
+/// Convert a byte range to an LSP range.
+fn range(index: &LineIndex, r: Range<usize>) -> Value {
+    object(vec![
+        ("start", position(index, r.start)),
+        ("end", position(index, r.end)),
+    ])
+}
+
+fn apply_change(doc: &mut IncrementalParser, change: &Value) {
+    let text = change.get("text").and_then(Value::as_str).unwrap_or("");
+    if let Some(r) = change.get("range") {
+        let index = LineIndex::new(doc.src());
+        let start = offset(&index, r.get("start"));
+        let end = offset(&index, r.get("end")).max(start);
+        doc.edit(start..end, text);
+    } else {
+        *doc = IncrementalParser::new(text.to_string());
+    }
+}
+
+/// A document symbol that has not yet been closed.
+struct Symbol {
+    name: String,
+    start: usize,
+    selection: Range<usize>,
+    children: Vec<Value>,
+}
+
+impl Symbol {
+    fn into_value(self, index: &LineIndex, end: usize) -> Value {
+        object(vec![
+            ("name", self.name.into()),
+            ("kind", SYMBOL_KIND_HEADING.into()),
+            ("range", range(index, self.start..end)),
+            ("selectionRange", range(index, self.selection)),
+            ("children", Value::Array(self.children)),
+        ])
+    }
+}
+
+/// An outline of the headings, nested by their sections.
+fn document_symbols(doc: &IncrementalParser) -> Value {
+    let index = LineIndex::new(doc.src());
+    let mut root = Vec::new();
+    let mut sections: Vec<Symbol> = Vec::new();
+    let mut heading: Option<Symbol> = None;
+    for (e, r) in doc.events() {
+        match e {
+            Event::Start(Container::Section { .. }, _) => sections.push(Symbol {
+                name: String::new(),
+                start: r.start,
+                selection: r.clone(),
+                children: Vec::new(),
+            }),
+            Event::Start(Container::Heading { has_section, .. }, _) => {
+                let start = if *has_section {
+                    sections.last().map_or(r.start, |s| s.start)
+                } else {
+                    r.start
+                };
+                heading = Some(Symbol {
+                    name: String::new(),
+                    start,
+                    selection: r.clone(),
+                    children: Vec::new(),
+                });
+            }
+            Event::End(Container::Heading { has_section, .. }) => {
+                if let Some(mut h) = heading.take() {
+                    h.selection.end = r.end;
+                    if *has_section {
+                        if let Some(s) = sections.last_mut() {
+                            s.name = h.name;
+                            s.selection = h.selection;
+                        }
+                    } else {
+                        let symbol = h.into_value(&index, r.end);
+                        sections
+                            .last_mut()
+                            .map_or(&mut root, |s| &mut s.children)
+                            .push(symbol);
+                    }
+                }
+            }
+            Event::End(Container::Section { .. }) => {
+                if let Some(s) = sections.pop() {
+                    let symbol = s.into_value(&index, r.end);
+                    sections
+                        .last_mut()
+                        .map_or(&mut root, |s| &mut s.children)
+                        .push(symbol);
+                }
+            }
+            Event::Str(s) => {
+                if let Some(h) = &mut heading {
+                    h.name.push_str(s);
+                }
+            }
+            Event::Softbreak | Event::Hardbreak => {
+                if let Some(h) = &mut heading {
+                    h.name.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    Value::Array(root)
+}
+
+/// The label of the reference link or image that ends with the event at `end`, whose text starts
+/// at `text_start`.
+fn reference_label<'s>(src: &'s str, text_start: usize, end: &Range<usize>) -> &'s str {
+    let tag = src[end.clone()].trim_start_matches(']');
+    let label = tag.trim_start_matches('[').trim_end_matches(']');
+    if label.is_empty() {
+        &src[text_start..end.start]
+    } else {
+        label
+    }
+}
+
+/// Find the range of the link definition, heading or footnote that the element at the offset
+/// refers to.
+fn definition(doc: &IncrementalParser, offset: usize) -> Option<Range<usize>> {
+    let src = doc.src();
+    let events = doc.events();
+    let mut definitions = HashMap::new();
+    let mut footnotes = HashMap::new();
+    let mut headings = HashMap::new();
+    for (e, r) in events {
+        match e {
+            Event::Start(Container::LinkDefinition { label }, _) => {
+                definitions.insert(label.to_string(), r.clone());
+            }
+            Event::Start(Container::Footnote { label }, _) => {
+                footnotes
+                    .entry(label.to_string())
+                    .or_insert_with(|| r.clone());
+            }
+            Event::Start(Container::Heading { id, .. }, _) => {
+                headings.entry(id.to_string()).or_insert_with(|| r.clone());
+            }
+            _ => {}
+        }
+    }
+
+    let mut links = Vec::new();
+    for (e, r) in events {
+        match e {
+            Event::FootnoteReference(label) if r.contains(&offset) => {
+                return footnotes.get(label.as_ref()).cloned();
+            }
+            Event::Start(Container::Link(..) | Container::Image(..), _) => links.push(r),
+            Event::End(c @ (Container::Link(..) | Container::Image(..))) => {
+                let start = links.pop()?;
+                if !(start.start..r.end).contains(&offset) {
+                    continue;
+                }
+                let (url, reference) = match c {
+                    Container::Link(url, ty) => {
+                        (url, *ty == LinkType::Span(SpanLinkType::Reference))
+                    }
+                    Container::Image(url, ty) => (url, *ty == SpanLinkType::Reference),
+                    _ => unreachable!(),
+                };
+                if let Some(r) = url.strip_prefix('#').and_then(|id| headings.get(id)) {
+                    return Some(r.clone());
+                } else if reference {
+                    return definitions.get(reference_label(src, start.end, r)).cloned();
+                }
+                return None;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Complete footnote labels after `[^`, heading ids after `](#` and link labels after `][`.
+fn completion(doc: &IncrementalParser, offset: usize) -> Value {
+    let src = doc.src();
+    let line = &src[src[..offset].rfind('\n').map_or(0, |i| i + 1)..offset];
+    let after = |pat: &str, close: char| {
+        line.rfind(pat)
+            .map_or(false, |i| !line[i + pat.len()..].contains(close))
+    };
+
+    let mut items = Vec::new();
+    let mut item = |label: &str, detail: &str| {
+        if !items.iter().any(|i: &(String, String)| i.0 == label) {
+            items.push((label.to_string(), detail.to_string()));
+        }
+    };
+    let mut heading: Option<(String, String)> = None;
+    for (e, _) in doc.events() {
+        match e {
+            Event::Start(Container::Footnote { label }, _) if after("[^", ']') => {
+                item(label, "footnote");
+            }
+            Event::Start(Container::LinkDefinition { label }, _)
+                if after("][", ']') && !after("[^", ']') =>
+            {
+                item(label, "link definition");
+            }
+            Event::Start(Container::Heading { id, .. }, _) => {
+                heading = Some((id.to_string(), String::new()));
+            }
+            Event::Str(s) => {
+                if let Some((_, text)) = &mut heading {
+                    text.push_str(s);
+                }
+            }
+            Event::End(Container::Heading { .. }) => {
+                if let Some((id, text)) = heading.take() {
+                    if after("](#", ')') {
+                        item(&id, &text);
+                    } else if after("][", ']') && !after("[^", ']') {
+                        item(&text, &format!("#{}", id));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Value::Array(
+        items
+            .into_iter()
+            .map(|(label, detail)| {
+                object(vec![
+                    ("label", label.into()),
+                    ("kind", COMPLETION_KIND_REFERENCE.into()),
+                    ("detail", detail.into()),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Folding ranges of divs, code blocks, raw blocks and sections.
+fn folding_ranges(doc: &IncrementalParser) -> Value {
+    let index = LineIndex::new(doc.src());
+    let line = |offset: usize| index.position(offset, PositionEncoding::Utf8).line - 1;
+    let mut starts = Vec::new();
+    let mut ranges = Vec::new();
+    for (e, r) in doc.events() {
+        match e {
+            Event::Start(
+                Container::Div { .. }
+                | Container::CodeBlock { .. }
+                | Container::RawBlock { .. }
+                | Container::Section { .. },
+                _,
+            ) => starts.push(r.start),
+            Event::End(
+                Container::Div { .. }
+                | Container::CodeBlock { .. }
+                | Container::RawBlock { .. }
+                | Container::Section { .. },
+            ) => {
+                if let Some(start) = starts.pop() {
+                    // exclude the newline at the end of the last line
+                    let (start, end) = (line(start), line(r.end.saturating_sub(1).max(start)));
+                    if end > start {
+                        ranges.push((start, end));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    ranges.sort_unstable();
+    Value::Array(
+        ranges
+            .into_iter()
+            .map(|(start, end)| object(vec![("startLine", start.into()), ("endLine", end.into())]))
+            .collect(),
+    )
+}
+
+fn token_type(c: &Container) -> Option<usize> {
+    let ty = match c {
+        Container::Span => "span",
+        Container::Link(..) => "link",
+        Container::Image(..) => "image",
+        Container::Verbatim => "verbatim",
+        Container::Math { .. } => "math",
+        Container::RawInline { .. } => "rawInline",
+        Container::Subscript => "subscript",
+        Container::Superscript => "superscript",
+        Container::Insert => "insert",
+        Container::Delete => "delete",
+        Container::Strong => "strong",
+        Container::Emphasis => "emphasis",
+        Container::Mark => "mark",
+        _ => return None,
+    };
+    TOKEN_TYPES.iter().position(|t| *t == ty)
+}
+
+/// Semantic tokens for the markers and content of inline containers, typed by the innermost
+/// container.
+fn semantic_tokens(doc: &IncrementalParser) -> Value {
+    let src = doc.src();
+    let mut tokens = Vec::new();
+    let mut open = Vec::new();
+    for (e, r) in doc.events() {
+        let ty = match e {
+            Event::Start(c, _) => token_type(c).map(|ty| {
+                open.push(ty);
+                ty
+            }),
+            Event::End(c) => token_type(c).and_then(|_| open.pop()),
+            _ => open.last().copied(),
+        };
+        if let Some(ty) = ty {
+            // tokens may not span multiple lines
+            let mut start = r.start;
+            for line in src[r.clone()].split_inclusive('\n') {
+                let end = start + line.trim_end_matches(&['\r', '\n'][..]).len();
+                if end > start {
+                    tokens.push((start..end, ty));
+                }
+                start += line.len();
+            }
+        }
+    }
+    tokens.sort_by_key(|(r, _)| r.start);
+
+    let index = LineIndex::new(src);
+    let mut data = Vec::new();
+    let mut prev = Position { line: 1, column: 1 };
+    for (r, ty) in tokens {
+        let Range { start, end } = index.range(r, PositionEncoding::Utf16);
+        let delta_line = start.line - prev.line;
+        let delta_start = if delta_line == 0 {
+            start.column - prev.column
+        } else {
+            start.column - 1
+        };
+        data.extend([delta_line, delta_start, end.column - start.column, ty, 0]);
+        prev = start;
+    }
+    object(vec![("data", data.into())])
+}
@@ -3,6 +3,7 @@ use std::ops::Range;
 use crate::Alignment;
 use crate::OrderedListNumbering::*;
 use crate::OrderedListStyle::*;
+use crate::Syntax;
 
 use crate::attr;
 use crate::lex;
@@ -34,8 +35,8 @@ pub enum Node<'s> {
 }
 
 #[must_use]
-pub fn parse(src: &str) -> Vec<Event> {
-    TreeParser::new(src).parse()
+pub(crate) fn parse(src: &str, syntax: Syntax) -> Vec<Event<'_>> {
+    TreeParser::new(src, syntax).parse()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -190,6 +191,8 @@ struct OpenList {
 /// Parser for block-level tree structure of entire document.
 struct TreeParser<'s> {
     src: &'s str,
+    /// Syntax that is enabled.
+    syntax: Syntax,
     /// The previous block element was a blank line.
     prev_blankline: bool,
     prev_loose: bool,
@@ -209,9 +212,10 @@ struct TreeParser<'s> {
 
 impl<'s> TreeParser<'s> {
     #[must_use]
-    fn new(src: &'s str) -> Self {
+    fn new(src: &'s str, syntax: Syntax) -> Self {
         Self {
             src,
+            syntax,
             prev_blankline: false,
             prev_loose: false,
             attr_start: None,
@@ -241,7 +245,7 @@ impl<'s> TreeParser<'s> {
         for _ in std::mem::take(&mut self.open_sections).drain(..) {
             self.exit(self.src.len()..self.src.len());
         }
-        debug_assert_eq!(self.open, &[]);
+        debug_assert!(self.open.is_empty());
         self.events
     }
 
@@ -282,7 +286,7 @@ impl<'s> TreeParser<'s> {
             kind,
             span: span_start,
             line_count,
-        }) = MeteredBlock::new(lines.iter().map(|sp| &self.src[sp.clone()]))
+        }) = MeteredBlock::new(lines.iter().map(|sp| &self.src[sp.clone()]), self.syntax)
         {
             let lines = &mut lines[..line_count];
             let span_start = (span_start.start + lines[0].start)..(span_start.end + lines[0].start);
@@ -327,7 +331,7 @@ impl<'s> TreeParser<'s> {
             }) = self.open_lists.last_mut()
             {
                 debug_assert!(usize::from(*depth) <= self.open.len());
-                if self.open.len() == (*depth).into() {
+                if self.open.len() == usize::from(*depth) {
                     let continues = if let Kind::ListItem { ty: ty_new, .. } = kind {
                         if let Some((ty_prev_res, ty_new_res)) = ty_prev.continues(&ty_new) {
                             if ty_start == ty_prev {
@@ -377,7 +381,7 @@ impl<'s> TreeParser<'s> {
                 Kind::Paragraph => Block::Leaf(Paragraph),
                 Kind::Heading { level } => Block::Leaf(Heading {
                     level: level.try_into().unwrap(),
-                    has_section: top_level,
+                    has_section: top_level && self.syntax.sections,
                     pos: span_start.start as u32,
                 }),
                 Kind::Fenced {
@@ -453,7 +457,7 @@ impl<'s> TreeParser<'s> {
             }
 
             // trim ending whitespace of raw block
-            if spec.starts_with('=') {
+            if self.syntax.raw && spec.starts_with('=') {
                 let l = lines.len();
                 if l > 0 {
                     lines[l - 1] = self.trim_end(lines[l - 1].clone());
@@ -667,7 +671,7 @@ impl<'s> TreeParser<'s> {
 
         if let Some(OpenList { depth, .. }) = self.open_lists.last() {
             debug_assert!(usize::from(*depth) <= self.open.len());
-            if self.open.len() == (*depth).into() {
+            if self.open.len() == usize::from(*depth) {
                 self.prev_blankline = false;
                 self.prev_loose = false;
                 let l = self.open_lists.pop().unwrap();
@@ -877,10 +881,10 @@ struct MeteredBlock<'s> {
 
 impl<'s> MeteredBlock<'s> {
     /// Identify and measure the line length of a single block.
-    fn new<I: Iterator<Item = &'s str>>(mut lines: I) -> Option<Self> {
+    fn new<I: Iterator<Item = &'s str>>(mut lines: I, syntax: Syntax) -> Option<Self> {
         lines.next().map(|l| {
-            let IdentifiedBlock { mut kind, span } = IdentifiedBlock::new(l);
-            let line_count = 1 + lines.take_while(|l| kind.continues(l)).count();
+            let IdentifiedBlock { mut kind, span } = IdentifiedBlock::new(l, syntax);
+            let line_count = 1 + lines.take_while(|l| kind.continues(l, syntax)).count();
             Self {
                 kind,
                 span,
@@ -934,7 +938,7 @@ struct IdentifiedBlock<'s> {
 }
 
 impl<'s> IdentifiedBlock<'s> {
-    fn new(line: &'s str) -> Self {
+    fn new(line: &'s str, syntax: Syntax) -> Self {
         let l = line.len();
 
         let line = line.trim_start_matches(|c: char| c.is_ascii_whitespace() && c != '\n');
@@ -970,10 +974,10 @@ impl<'s> IdentifiedBlock<'s> {
                     None
                 }
             }
-            '{' => {
+            '{' if syntax.attributes => {
                 (attr::valid(line) == lt).then(|| (Kind::Atom(Attributes), indent..(indent + l)))
             }
-            '|' => {
+            '|' if syntax.tables => {
                 if lt >= 2 && line_t.ends_with('|') && !line_t.ends_with("\\|") {
                     Some((Kind::Table { caption: false }, indent..indent))
                 } else {
@@ -981,9 +985,9 @@ impl<'s> IdentifiedBlock<'s> {
                 }
             }
             '[' => chars.as_str().find("]").and_then(|l| {
-                if chars.clone().nth(l + 1) == Some(':') {
-                    let label = &chars.as_str()[0..l];
-                    let footnote = label.starts_with('^');
+                let label = &chars.as_str()[0..l];
+                let footnote = label.starts_with('^');
+                if chars.clone().nth(l + 1) == Some(':') && (syntax.footnotes || !footnote) {
                     Some((
                         Kind::Definition {
                             indent,
@@ -1001,7 +1005,8 @@ impl<'s> IdentifiedBlock<'s> {
                 Some((Kind::Atom(ThematicBreak), indent..(indent + lt)))
             }
             b @ ('-' | '*' | '+') => chars.next().map_or(true, |c| c == ' ').then(|| {
-                let task_list = chars.next() == Some('[')
+                let task_list = syntax.task_lists
+                    && chars.next() == Some('[')
                     && matches!(chars.next(), Some('x' | 'X' | ' '))
                     && chars.next() == Some(']')
                     && chars.next().map_or(true, |c| c.is_ascii_whitespace());
@@ -1025,10 +1030,11 @@ impl<'s> IdentifiedBlock<'s> {
                     )
                 }
             }),
-            ':' if chars
-                .clone()
-                .next()
-                .map_or(true, |c| c.is_ascii_whitespace()) =>
+            ':' if syntax.description_lists
+                && chars
+                    .clone()
+                    .next()
+                    .map_or(true, |c| c.is_ascii_whitespace()) =>
             {
                 Some((
                     Kind::ListItem {
@@ -1180,7 +1186,7 @@ impl<'s> IdentifiedBlock<'s> {
 
 impl<'s> Kind<'s> {
     /// Determine if a line continues the block.
-    fn continues(&mut self, line: &'s str) -> bool {
+    fn continues(&mut self, line: &'s str, syntax: Syntax) -> bool {
         match self {
             Self::Atom(..)
             | Self::Fenced {
@@ -1188,11 +1194,11 @@ impl<'s> Kind<'s> {
                 ..
             } => false,
             Self::Blockquote => matches!(
-                IdentifiedBlock::new(line).kind,
+                IdentifiedBlock::new(line, syntax).kind,
                 Self::Blockquote | Self::Paragraph
             ),
             Self::Heading { level } => {
-                let next = IdentifiedBlock::new(line).kind;
+                let next = IdentifiedBlock::new(line, syntax).kind;
                 matches!(next, Self::Paragraph)
                     || matches!(next, Self::Heading { level: l } if l == *level )
             }
@@ -1206,7 +1212,7 @@ impl<'s> Kind<'s> {
             } => {
                 let line_t = line.trim_start_matches(|c: char| c.is_ascii_whitespace());
                 let whitespace = line.len() - line_t.len();
-                let next = IdentifiedBlock::new(line).kind;
+                let next = IdentifiedBlock::new(line, syntax).kind;
                 let para = !*last_blankline && matches!(next, Self::Paragraph);
                 *last_blankline = matches!(next, Self::Atom(Blankline));
                 *last_blankline || whitespace > *indent || para
@@ -1217,7 +1223,7 @@ impl<'s> Kind<'s> {
                 last_blankline,
                 ..
             } => {
-                let next = IdentifiedBlock::new(line).kind;
+                let next = IdentifiedBlock::new(line, syntax).kind;
                 let line_t = line.trim_start_matches(|c: char| c.is_ascii_whitespace());
                 let whitespace = line.len() - line_t.len();
                 let cont_para = !*last_blankline && matches!(next, Self::Paragraph);
@@ -1241,7 +1247,7 @@ impl<'s> Kind<'s> {
                     fence_length: l,
                     spec,
                     ..
-                } = IdentifiedBlock::new(line).kind
+                } = IdentifiedBlock::new(line, syntax).kind
                 {
                     if spec.is_empty() {
                         *has_closing_fence = k == *kind
@@ -1294,6 +1300,7 @@ mod test {
     use crate::Alignment;
     use crate::OrderedListNumbering::*;
     use crate::OrderedListStyle::*;
+    use crate::Syntax;
 
     use super::Atom::*;
     use super::Container::*;
@@ -1308,7 +1315,7 @@ mod test {
 
     macro_rules! test_parse {
         ($src:expr $(,$($event:expr),* $(,)?)?) => {
-            let t = super::TreeParser::new($src).parse();
+            let t = super::TreeParser::new($src, Syntax::default()).parse();
             let actual = t.into_iter().map(|ev| (ev.kind, &$src[ev.span])).collect::<Vec<_>>();
             let expected = &[$($($event),*,)?];
             assert_eq!(
@@ -2780,7 +2787,7 @@ mod test {
     macro_rules! test_block {
         ($src:expr, $kind:expr, $str:expr, $len:expr $(,)?) => {
             let lines = super::lines($src).map(|sp| &$src[sp]);
-            let mb = super::MeteredBlock::new(lines).unwrap();
+            let mb = super::MeteredBlock::new(lines, Syntax::default()).unwrap();
             assert_eq!(
                 (mb.kind, &$src[mb.span], mb.line_count),
                 ($kind, $str, $len),
@@ -0,0 +1,332 @@
+use std::ops::Range;
+
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+use crate::LinkType;
+use crate::Map;
+use crate::Set;
+use crate::SpanLinkType;
+
+/// A problem found in the input by [`crate::Parser::diagnostics`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic<'s> {
+    /// The kind of problem.
+    pub kind: DiagnosticKind<'s>,
+    /// Byte range of the element in the input that the problem refers to.
+    pub range: Range<usize>,
+}
+
+/// The kind of a [`Diagnostic`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind<'s> {
+    /// A reference link or image with a label that matches neither a link definition nor a
+    /// heading, and is not resolved by a [`crate::LinkResolver`]. The link is emitted with
+    /// [`SpanLinkType::Unresolved`].
+    UnresolvedReference {
+        /// Label of the reference.
+        label: CowStr<'s>,
+    },
+    /// A footnote reference with a label that does not match any footnote.
+    UnresolvedFootnote {
+        /// Label of the reference.
+        label: CowStr<'s>,
+    },
+    /// A link definition with the same label as a previous link definition, which it replaces.
+    DuplicateDefinition {
+        /// Label of the link definition.
+        label: CowStr<'s>,
+    },
+    /// A footnote with the same label as a previous footnote.
+    DuplicateFootnote {
+        /// Label of the footnote.
+        label: CowStr<'s>,
+    },
+    /// A link definition that is not used by any reference link or image.
+    UnusedDefinition {
+        /// Label of the link definition.
+        label: CowStr<'s>,
+    },
+    /// A footnote that is not referenced.
+    UnusedFootnote {
+        /// Label of the footnote.
+        label: CowStr<'s>,
+    },
+    /// An element with the same id as a previous element, either from an explicit `{#id}`
+    /// attribute or an id generated for a heading.
+    DuplicateId {
+        /// The duplicated id.
+        id: CowStr<'s>,
+        /// Byte range of the first element with the id.
+        first: Range<usize>,
+    },
+}
+
+/// Collects diagnostics from the events emitted by the parser.
+#[derive(Default)]
+pub(crate) struct Diagnostics<'s> {
+    diagnostics: Vec<Diagnostic<'s>>,
+    /// Start offsets of open links and images.
+    links: Vec<usize>,
+    /// Link definitions and the range of the first definition with each label.
+    definitions: Map<CowStr<'s>, Range<usize>>,
+    /// Footnotes and the range of the first footnote with each label.
+    footnotes: Map<CowStr<'s>, Range<usize>>,
+    /// Footnote references, in the order they were encountered.
+    references: Vec<(CowStr<'s>, Range<usize>)>,
+    /// Ids and the range of the first element with each id.
+    ids: Map<String, Range<usize>>,
+    /// Open containers, with their id, start offset and whether the id is a duplicate.
+    open: Vec<Option<(String, usize, bool)>>,
+}
+
+impl<'s> Diagnostics<'s> {
+    pub(crate) fn event(&mut self, e: &Event<'s>, range: Range<usize>) {
+        match e {
+            Event::Start(c, attrs) => {
+                // the id of a section is reported for its heading
+                let id = match c {
+                    Container::Section { .. } => None,
+                    Container::Heading { id, .. } => Some(id.to_string()),
+                    _ => attrs.get_value("id").map(|id| id.to_string()),
+                };
+                let open = id.map(|id| {
+                    let duplicate = self.ids.contains_key(&id);
+                    if !duplicate {
+                        self.ids.insert(id.clone(), range.clone());
+                    }
+                    (id, range.start, duplicate)
+                });
+                self.open.push(open);
+                match c {
+                    Container::Link(..) | Container::Image(..) => self.links.push(range.start),
+                    Container::LinkDefinition { label } => {
+                        self.definition(label, range, true);
+                    }
+                    Container::Footnote { label } => self.definition(label, range, false),
+                    _ => {}
+                }
+            }
+            Event::End(c) => {
+                if let Some(Some((id, start, duplicate))) = self.open.pop() {
+                    self.id(id, start..range.end, duplicate);
+                }
+                if let Container::Link(..) | Container::Image(..) = c {
+                    let start = self.links.pop().unwrap_or(range.start);
+                    if let Container::Link(label, LinkType::Span(SpanLinkType::Unresolved))
+                    | Container::Image(label, SpanLinkType::Unresolved) = c
+                    {
+                        self.diagnostics.push(Diagnostic {
+                            kind: DiagnosticKind::UnresolvedReference {
+                                label: label.clone(),
+                            },
+                            range: start..range.end,
+                        });
+                    }
+                }
+            }
+            Event::FootnoteReference(label) => self.references.push((label.clone(), range)),
+            Event::ThematicBreak(attrs) => {
+                if let Some(id) = attrs.get_value("id") {
+                    let id = id.to_string();
+                    let duplicate = self.ids.contains_key(&id);
+                    self.id(id, range, duplicate);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Record the full range of an element with an id, once it has ended. Whether the id is a
+    /// duplicate is determined when the element starts, as elements may be nested.
+    fn id(&mut self, id: String, range: Range<usize>, duplicate: bool) {
+        if duplicate {
+            // the range of the first element is set when finished, as it may not have ended yet
+            self.diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::DuplicateId {
+                    id: id.into(),
+                    first: 0..0,
+                },
+                range,
+            });
+        } else {
+            self.ids.insert(id, range);
+        }
+    }
+
+    fn definition(&mut self, label: &CowStr<'s>, range: Range<usize>, link: bool) {
+        let definitions = if link {
+            &mut self.definitions
+        } else {
+            &mut self.footnotes
+        };
+        if definitions.contains_key(label) {
+            let label = label.clone();
+            self.diagnostics.push(Diagnostic {
+                kind: if link {
+                    DiagnosticKind::DuplicateDefinition { label }
+                } else {
+                    DiagnosticKind::DuplicateFootnote { label }
+                },
+                range,
+            });
+        } else {
+            definitions.insert(label.clone(), range);
+        }
+    }
+
+    /// Finish the collection, given the labels of the link definitions that were used. The
+    /// diagnostics are returned in the order of their location in the input.
+    pub(crate) fn finish(mut self, used_definitions: &Set<&str>) -> Vec<Diagnostic<'s>> {
+        let mut referenced = Set::new();
+        for (label, range) in self.references {
+            if !self.footnotes.contains_key(&label) {
+                self.diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::UnresolvedFootnote {
+                        label: label.clone(),
+                    },
+                    range,
+                });
+            }
+            referenced.insert(label);
+        }
+        for (label, range) in self.footnotes {
+            if !referenced.contains(&label) {
+                self.diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::UnusedFootnote { label },
+                    range,
+                });
+            }
+        }
+        for (label, range) in self.definitions {
+            if !used_definitions.contains(label.as_ref()) {
+                self.diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::UnusedDefinition { label },
+                    range,
+                });
+            }
+        }
+        for d in &mut self.diagnostics {
+            if let DiagnosticKind::DuplicateId { id, first } = &mut d.kind {
+                if let Some(r) = self.ids.get(id.as_ref()) {
+                    *first = r.clone();
+                }
+            }
+        }
+        self.diagnostics.sort_by_key(|d| d.range.start);
+        self.diagnostics
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DiagnosticKind::*;
+
+    macro_rules! test_diagnostics {
+        ($src:expr $(,$($kind:expr => $range:expr),* $(,)?)?) => {
+            #[allow(unused)]
+            let actual = crate::Parser::new($src)
+                .diagnostics()
+                .into_iter()
+                .map(|d| (d.kind, &$src[d.range]))
+                .collect::<Vec<_>>();
+            let expected = &[$($(($kind, $range)),*,)?];
+            assert_eq!(actual, expected, "\n\n{}\n\n", $src);
+        };
+    }
+
+    #[test]
+    fn none() {
+        test_diagnostics!(concat!(
+            "# Heading\n",        //
+            "\n",                 //
+            "[a][] [b][] [c]\n",  //
+            "[Heading][] [^f]\n", //
+            "\n",                 //
+            "[a]: url\n",         //
+            "[b]: url\n",         //
+            "\n",                 //
+            "[^f]: note\n",
+        ));
+    }
+
+    #[test]
+    fn unresolved() {
+        test_diagnostics!(
+            "[a][] ![b](url) ![c][d] [^e]",
+            UnresolvedReference { label: "a".into() } => "[a][]",
+            UnresolvedReference { label: "d".into() } => "![c][d]",
+            UnresolvedFootnote { label: "e".into() } => "[^e]",
+        );
+    }
+
+    #[test]
+    fn duplicate_definition() {
+        test_diagnostics!(
+            concat!(
+                "[a][]\n",  //
+                "\n",       //
+                "[a]: u\n", //
+                "[a]: v\n",
+            ),
+            DuplicateDefinition { label: "a".into() } => "[a]:",
+        );
+    }
+
+    #[test]
+    fn footnotes() {
+        test_diagnostics!(
+            concat!(
+                "[^a]\n",    //
+                "\n",        //
+                "[^a]: x\n", //
+                "\n",        //
+                "[^a]: y\n", //
+                "\n",        //
+                "[^b]: z\n",
+            ),
+            DuplicateFootnote { label: "a".into() } => "[^a]:",
+            UnusedFootnote { label: "b".into() } => "[^b]:",
+        );
+    }
+
+    #[test]
+    fn unused_definition() {
+        test_diagnostics!(
+            concat!(
+                "[a]: u\n", //
+                "[b]: v\n", //
+                "\n",       //
+                "[c][b]\n",
+            ),
+            UnusedDefinition { label: "a".into() } => "[a]:",
+        );
+    }
+
+    #[test]
+    fn duplicate_id() {
+        test_diagnostics!(
+            concat!(
+                "# a\n",     //
+                "\n",        //
+                "> # b\n",   //
+                "\n",        //
+                "{#b}\n",    //
+                "***\n",     //
+                "\n",        //
+                "[x]{#a}\n", //
+                "\n",        //
+                "# a\n",     //
+                "\n",        //
+                "{#c}\n",    //
+                ":::\n",     //
+                "{#c}\n",    //
+                "d\n",       //
+                ":::\n",
+            ),
+            DuplicateId { id: "b".into(), first: 7..11 } => "{#b}\n***",
+            DuplicateId { id: "a".into(), first: 0..4 } => "[x]{#a}",
+            DuplicateId { id: "c".into(), first: 36..56 } => "{#c}\nd\n",
+        );
+    }
+}
@@ -0,0 +1,2077 @@
+//! A Djot renderer that takes an iterator of [`Event`]s and emits Djot.
+//!
+//! The output is not necessarily identical to the input the events were parsed from, but parsing
+//! the output again produces an equivalent sequence of events. Events that only describe the
+//! source, i.e. [`Event::Escape`] and [`Event::Blankline`], are not preserved, and neither is the
+//! way text is split into [`Event::Str`]s. Links and images that were resolved via a link
+//! definition are written with their destination inline.
+//!
+//! To reprint a Djot document in canonical form while keeping its references, use [`format()`].
+
+use std::collections::VecDeque;
+
+use crate::block;
+use crate::lines;
+use crate::lines::Prefix;
+use crate::lines::Prefixes;
+use crate::lines::Row;
+use crate::lines::Table;
+use crate::Alignment;
+use crate::AttributeKind;
+use crate::AttributeValue;
+use crate::Attributes;
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+use crate::LinkType;
+use crate::ListBulletType;
+use crate::ListKind;
+use crate::OrderedListNumbering;
+use crate::OrderedListStyle;
+use crate::Parser;
+use crate::Reference;
+use crate::Render;
+use crate::RenderRef;
+use crate::SpanLinkType;
+use crate::Syntax;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("hello _world_");
+/// assert_eq!(jotdown::djot::render_to_string(events), "hello _world_\n");
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer::default().push(events, &mut s).unwrap();
+    s
+}
+
+/// Options for [`format()`].
+#[derive(Clone, Debug, Default)]
+pub struct FormatOptions {
+    /// Maximum width of the lines of paragraphs, including the indentation of their containers.
+    ///
+    /// Lines are only broken at spaces, so words that are longer than the width exceed it. If
+    /// `None`, the line breaks of the input are kept.
+    pub width: Option<usize>,
+}
+
+/// Reprint a Djot document in canonical form.
+///
+/// In addition to what the [`Renderer`] does, i.e. separating blocks with blank lines, using
+/// the shortest fences for code blocks and divs, writing a `#` marker on each line of headings and
+/// numbering ordered lists sequentially, the formatter
+///
+/// - pads the columns of tables to equal widths,
+/// - writes attributes in the order id, classes, key-value pairs and comments, with a single
+///   element for each key,
+/// - uses `-` as list bullet and `.` as ordered list delimiter, alternating only where adjacent
+///   lists would otherwise be joined,
+/// - wraps the lines of paragraphs, if a width is set.
+///
+/// Unlike the [`Renderer`], references to link definitions are kept. Parsing the output produces
+/// the same events as the input, apart from the bullets and delimiters of lists, the line breaks
+/// of paragraphs, the order and duplicates of attributes, and the events that only describe the
+/// source, i.e. [`Event::Escape`] and [`Event::Blankline`].
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::djot::*;
+/// let src = concat!(
+///     "|a|bcd|\n",
+///     "|-:|--|\n",
+///     "\n",
+///     "{.x #y}\n",
+///     "see [the\n",
+///     "docs][docs], and more and more text\n",
+///     "\n",
+///     "[docs]: https://example.com\n",
+/// );
+/// let options = FormatOptions { width: Some(20) };
+/// let expected = concat!(
+///     "|   a | bcd |\n",
+///     "| --: | --- |\n",
+///     "\n",
+///     "{#y .x}\n",
+///     "see [the\n",
+///     "docs][docs], and\n",
+///     "more and more text\n",
+///     "\n",
+///     "[docs]: https://example.com\n",
+/// );
+/// assert_eq!(format(src, &options), expected);
+/// ```
+#[must_use]
+pub fn format(src: &str, options: &FormatOptions) -> String {
+    let mut w = Writer {
+        canonical: true,
+        width: options.width,
+        ..Writer::default()
+    };
+    let mut parser = Parser::new(src).with_references();
+    let mut out = String::new();
+    while let Some(e) = parser.next() {
+        w.references.extend(parser.take_references());
+        w.render_event(&e, &mut out).unwrap();
+    }
+    out
+}
+
+/// [`Render`] implementor that writes Djot output.
+///
+/// Blocks are separated by blank lines, except for items of tight lists. Block attributes are
+/// placed on their own line before the block they belong to.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::djot::*;
+/// let src = concat!(
+///     "{#quote}\n",
+///     "> a\n",
+///     "b\n",
+///     "* c\n",
+///     "* d\n",
+/// );
+/// let mut actual = String::new();
+/// Renderer::default()
+///     .push(Parser::new(src), &mut actual)
+///     .unwrap();
+/// let expected = concat!(
+///     "{#quote}\n",
+///     "> a\n",
+///     "> b\n",
+///     "\n",
+///     "* c\n",
+///     "* d\n",
+/// );
+/// assert_eq!(actual, expected);
+/// ```
+#[derive(Clone, Default)]
+pub struct Renderer {}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::default();
+        events.try_for_each(|e| w.render_event(&e, &mut out))
+    }
+}
+
+impl RenderRef for Renderer {
+    fn push_ref<'s, E, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        E: AsRef<Event<'s>>,
+        I: Iterator<Item = E>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::default();
+        events.try_for_each(|e| w.render_event(e.as_ref(), &mut out))
+    }
+}
+
+/// Separation state of blocks within a block container, with the kind of the list that is the
+/// last block, if any.
+type Level = lines::Level<Option<ListKind>>;
+
+enum List {
+    Items { kind: ListKind, n: u64 },
+    Description { item: bool },
+}
+
+/// A block that contains inline elements.
+enum Leaf<'s> {
+    Paragraph,
+    Heading { level: u16 },
+    Term,
+    Cell,
+    Caption,
+    LinkDefinition { label: CowStr<'s> },
+    Code { spec: String },
+}
+
+/// An open inline container, with its content buffered.
+type Inline<'s> = lines::Inline<'s, InlineState<'s>>;
+
+struct InlineState<'s> {
+    /// Positions of breakable spaces and soft breaks in the text.
+    breaks: Vec<usize>,
+    /// Positions of characters in the text that may have to be escaped, see [`escape_context`].
+    escapes: Vec<usize>,
+    /// Tag of a reference link or image, if kept.
+    tag: Option<CowStr<'s>>,
+}
+
+#[derive(Default)]
+struct Writer<'s> {
+    prefixes: Prefixes,
+    levels: Vec<Level>,
+    lists: Vec<List>,
+    /// Attributes to be written before the next block.
+    attrs: Attributes<'s>,
+    leaf: Option<Leaf<'s>>,
+    /// Buffered content of the current leaf block.
+    text: String,
+    /// Positions of spaces and soft breaks in the buffered content that may be replaced by line
+    /// breaks or spaces, respectively, while wrapping lines.
+    breaks: Vec<usize>,
+    /// Positions of characters in the buffered content that may have to be escaped, see
+    /// [`escape_context`].
+    escapes: Vec<usize>,
+    inlines: Vec<Inline<'s>>,
+    /// Position of a left quote that should be braced if followed by whitespace.
+    quote: Option<usize>,
+    /// Inline depth and buffer length after the last smart punctuation atom.
+    last_smart: Option<(usize, usize)>,
+    table: Option<Table>,
+    /// Events of the outermost open div, buffered in order to determine fence lengths.
+    div_events: Vec<Event<'s>>,
+    div_depth: usize,
+    /// Fence lengths of buffered divs that have not yet been started, in reverse order.
+    fences: Vec<usize>,
+    /// Fence lengths of open divs.
+    open_fences: Vec<usize>,
+    /// Pad table columns, write attributes in canonical order and keep references, as done by
+    /// [`format()`].
+    canonical: bool,
+    /// Maximum width of paragraph lines.
+    width: Option<usize>,
+    /// Upcoming resolved reference links and images.
+    references: VecDeque<Reference<'s>>,
+}
+
+impl<'s> Writer<'s> {
+    fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.div_depth > 0 || matches!(e, Event::Start(Container::Div { .. }, ..)) {
+            match e {
+                Event::Start(Container::Div { .. }, ..) => self.div_depth += 1,
+                Event::End(Container::Div { .. }) => self.div_depth -= 1,
+                _ => {}
+            }
+            self.div_events.push(e.clone());
+            if self.div_depth == 0 {
+                let events = std::mem::take(&mut self.div_events);
+                self.fences = fence_lengths(&events);
+                self.fences.reverse();
+                for e in &events {
+                    self.render(e, &mut out)?;
+                }
+            }
+            return Ok(());
+        }
+
+        self.render(e, out)
+    }
+
+    fn render<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match e {
+            Event::Start(c, attrs) => self.enter(c, attrs, &mut out)?,
+            Event::End(c) => self.exit(c, &mut out)?,
+            Event::Str(s) => {
+                let raw = matches!(
+                    self.leaf,
+                    Some(Leaf::Code { .. } | Leaf::LinkDefinition { .. })
+                ) || self.inlines.last().map_or(false, |i| {
+                    matches!(
+                        i.container,
+                        Container::Verbatim | Container::Math { .. } | Container::RawInline { .. }
+                    )
+                });
+                if raw {
+                    self.buf().push_str(s);
+                } else {
+                    self.brace_quote(s);
+                    let cell = matches!(self.leaf, Some(Leaf::Cell));
+                    let wrap = self.wrapping();
+                    let (buf, breaks, escapes) = self.buf_positions();
+                    write_text(s, cell, buf, wrap.then(|| breaks), escapes);
+                }
+            }
+            Event::FootnoteReference(label) => self.push("[^", label, "]"),
+            Event::Symbol(sym) => self.push(":", sym, ":"),
+            Event::LeftSingleQuote => self.left_quote('\''),
+            Event::LeftDoubleQuote => self.left_quote('"'),
+            Event::RightSingleQuote => self.right_quote('\''),
+            Event::RightDoubleQuote => self.right_quote('"'),
+            Event::Ellipsis => self.smart("...", '.'),
+            Event::EnDash => self.smart("--", '-'),
+            Event::EmDash => self.smart("---", '-'),
+            Event::NonBreakingSpace => self.push("", "\\ ", ""),
+            Event::Hardbreak => self.push("", "\\\n", ""),
+            Event::Softbreak => {
+                self.brace_quote("\n");
+                let wrap = self.wrapping();
+                let (buf, breaks, _) = self.buf_positions();
+                if wrap {
+                    breaks.push(buf.len());
+                }
+                buf.push('\n');
+            }
+            Event::Escape | Event::Blankline => {}
+            Event::Attributes(attrs) => {
+                let canonical = self.canonical;
+                if self.leaf.is_some() {
+                    write_attributes(attrs, canonical, self.buf());
+                } else {
+                    self.block_start(&Attributes::new(), &mut out)?;
+                    let mut line = String::new();
+                    write_attributes(attrs, canonical, &mut line);
+                    self.prefixes.line(&line, &mut out)?;
+                }
+            }
+            Event::ThematicBreak(attrs) => {
+                self.block_start(attrs, &mut out)?;
+                self.prefixes.line("* * *", &mut out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn enter<W>(
+        &mut self,
+        c: &Container<'s>,
+        attrs: &Attributes<'s>,
+        mut out: W,
+    ) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Section { .. } => self.attrs.extend(attrs.iter().cloned()),
+            Container::ListItem | Container::TaskListItem { .. } => {
+                let marker = if let Some(List::Items { kind, n }) = self.lists.last_mut() {
+                    *n += 1;
+                    list_marker(*kind, *n - 1, c)
+                } else {
+                    "-".to_string()
+                };
+                self.item_start(marker, &mut out)?;
+            }
+            Container::DescriptionTerm => {
+                if let Some(List::Description { item }) = self.lists.last_mut() {
+                    if *item {
+                        self.prefixes.pop();
+                        self.levels.pop();
+                    }
+                    *item = true;
+                }
+                self.item_start(":".to_string(), &mut out)?;
+                if let Some(level) = self.levels.last_mut() {
+                    level.blocks = true;
+                }
+                self.leaf_start(Leaf::Term);
+            }
+            Container::DescriptionDetails => {}
+            Container::TableRow { head } => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Row {
+                        head: *head,
+                        ..Row::default()
+                    });
+                }
+            }
+            Container::TableCell { alignment, .. } => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.alignments.push(*alignment);
+                }
+                self.leaf_start(Leaf::Cell);
+            }
+            Container::Caption => self.leaf_start(Leaf::Caption),
+            Container::Blockquote => {
+                self.block_start(attrs, &mut out)?;
+                self.container_start(Some("> "), "> ");
+            }
+            Container::List { kind, tight } => {
+                let kind = if self.canonical {
+                    self.canonical_list_kind(*kind)
+                } else {
+                    *kind
+                };
+                self.block_start(attrs, &mut out)?;
+                self.lists.push(List::Items {
+                    kind,
+                    n: if let ListKind::Ordered { start, .. } = kind {
+                        start
+                    } else {
+                        1
+                    },
+                });
+                self.levels.push(Level {
+                    tight: *tight,
+                    ..Level::default()
+                });
+            }
+            Container::DescriptionList => {
+                self.block_start(attrs, &mut out)?;
+                self.lists.push(List::Description { item: false });
+                self.levels.push(Level::default());
+            }
+            Container::Footnote { label } => {
+                self.block_start(attrs, &mut out)?;
+                self.container_start(Some(&format!("[^{}]: ", label)), "  ");
+            }
+            Container::Table => {
+                self.block_start(attrs, &mut out)?;
+                self.table = Some(Table::default());
+            }
+            Container::Div { class } => {
+                self.block_start(attrs, &mut out)?;
+                let n = self.fences.pop().unwrap_or(3);
+                let mut fence = ":".repeat(n);
+                if !class.is_empty() {
+                    fence.push(' ');
+                    fence.push_str(class);
+                }
+                self.prefixes.line(&fence, &mut out)?;
+                self.open_fences.push(n);
+                self.levels.push(Level::default());
+            }
+            Container::Paragraph => {
+                self.block_start(attrs, &mut out)?;
+                self.leaf_start(Leaf::Paragraph);
+            }
+            Container::Heading { level, .. } => {
+                self.block_start(attrs, &mut out)?;
+                self.leaf_start(Leaf::Heading { level: *level });
+            }
+            Container::LinkDefinition { label } => {
+                self.block_start(attrs, &mut out)?;
+                self.leaf_start(Leaf::LinkDefinition {
+                    label: label.clone(),
+                });
+            }
+            Container::CodeBlock { language } => {
+                self.block_start(attrs, &mut out)?;
+                self.leaf_start(Leaf::Code {
+                    spec: language.to_string(),
+                });
+            }
+            Container::RawBlock { format } => {
+                self.block_start(attrs, &mut out)?;
+                self.leaf_start(Leaf::Code {
+                    spec: format!("={}", format),
+                });
+            }
+            Container::Verbatim | Container::Math { .. } | Container::RawInline { .. } => {
+                self.inline_start(c, attrs, '$');
+            }
+            Container::Span | Container::Link(..) => self.inline_start(c, attrs, '!'),
+            Container::Image(..)
+            | Container::Subscript
+            | Container::Superscript
+            | Container::Insert
+            | Container::Delete
+            | Container::Strong
+            | Container::Emphasis
+            | Container::Mark => self.inline_start(c, attrs, '\0'),
+        }
+        Ok(())
+    }
+
+    fn exit<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Section { .. } | Container::TableRow { .. } => {}
+            Container::Blockquote
+            | Container::Footnote { .. }
+            | Container::ListItem
+            | Container::TaskListItem { .. } => self.container_end(&mut out)?,
+            Container::List { .. } => {
+                let list = self.lists.pop();
+                self.levels.pop();
+                if let (Some(List::Items { kind, .. }), Some(level)) =
+                    (list, self.levels.last_mut())
+                {
+                    level.last = Some(kind);
+                }
+            }
+            Container::DescriptionList => {
+                if let Some(List::Description { item: true }) = self.lists.pop() {
+                    self.container_end(&mut out)?;
+                }
+                self.levels.pop();
+            }
+            Container::DescriptionDetails => {
+                if let Some(List::Description { item }) = self.lists.last_mut() {
+                    if *item {
+                        *item = false;
+                        self.container_end(&mut out)?;
+                    }
+                }
+            }
+            Container::Div { .. } => {
+                self.levels.pop();
+                let n = self.open_fences.pop().unwrap_or(3);
+                self.prefixes.line(&":".repeat(n), &mut out)?;
+            }
+            Container::Table => self.table_end(&mut out)?,
+            Container::Paragraph
+            | Container::Heading { .. }
+            | Container::DescriptionTerm
+            | Container::TableCell { .. }
+            | Container::Caption
+            | Container::LinkDefinition { .. }
+            | Container::CodeBlock { .. }
+            | Container::RawBlock { .. } => self.leaf_end(&mut out)?,
+            _ => self.inline_end(),
+        }
+
+        Ok(())
+    }
+
+    /// Separate a new block from its preceding siblings and write its attributes.
+    fn block_start<W>(&mut self, attrs: &Attributes<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.levels.is_empty() {
+            self.levels.push(Level::default());
+        }
+        let level = self.levels.last_mut().unwrap();
+        let blank = level.blocks;
+        level.blocks = true;
+        level.last = None;
+        if blank {
+            self.prefixes.blankline(&mut out)?;
+        }
+
+        let mut attrs_all = std::mem::take(&mut self.attrs);
+        attrs_all.extend(attrs.iter().cloned());
+        if !attrs_all.is_empty() {
+            let mut line = String::new();
+            write_attributes(&attrs_all, self.canonical, &mut line);
+            self.prefixes.line(&line, &mut out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Normalize the bullets of a list to `-` and the delimiters of an ordered list to `.`.
+    ///
+    /// Another bullet or delimiter is used if the list would otherwise continue the preceding
+    /// list, or if the bullet is used by an enclosing list item on the same line, which could form
+    /// a thematic break.
+    fn canonical_list_kind(&self, kind: ListKind) -> ListKind {
+        let prev = self.levels.last().and_then(|l| l.last);
+        match kind {
+            ListKind::Unordered(..) | ListKind::Task(..) => {
+                let task = matches!(kind, ListKind::Task(..));
+                let list = |b| {
+                    if task {
+                        ListKind::Task(b)
+                    } else {
+                        ListKind::Unordered(b)
+                    }
+                };
+                let pending = |b: ListBulletType| {
+                    self.prefixes
+                        .iter()
+                        .filter_map(|p| p.first.as_ref())
+                        .any(|first| first.starts_with(char::from(b)))
+                };
+                let bullet = [
+                    ListBulletType::Dash,
+                    ListBulletType::Star,
+                    ListBulletType::Plus,
+                ]
+                .iter()
+                .copied()
+                .find(|b| prev != Some(list(*b)) && !pending(*b))
+                .unwrap_or(ListBulletType::Dash);
+                list(bullet)
+            }
+            ListKind::Ordered {
+                numbering, start, ..
+            } => {
+                // alphabetic and roman numbering may be ambiguous
+                let continues = |style| {
+                    matches!(
+                        prev,
+                        Some(ListKind::Ordered { numbering: n, style: s, .. })
+                            if s == style
+                                && (n == numbering
+                                    || (n != OrderedListNumbering::Decimal
+                                        && numbering != OrderedListNumbering::Decimal))
+                    )
+                };
+                let style = [
+                    OrderedListStyle::Period,
+                    OrderedListStyle::Paren,
+                    OrderedListStyle::ParenParen,
+                ]
+                .iter()
+                .copied()
+                .find(|s| !continues(*s))
+                .unwrap_or(OrderedListStyle::Period);
+                ListKind::Ordered {
+                    numbering,
+                    style,
+                    start,
+                }
+            }
+        }
+    }
+
+    fn item_start<W>(&mut self, marker: String, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if let Some(level) = self.levels.last_mut() {
+            let blank = level.blocks && !level.tight;
+            level.blocks = true;
+            if blank {
+                self.prefixes.blankline(&mut out)?;
+            }
+        }
+        let rest = " ".repeat(marker.len() + 1);
+        self.push_prefix(Prefix {
+            first: Some(marker + " "),
+            rest,
+        });
+        self.levels.push(Level::default());
+        Ok(())
+    }
+
+    fn container_start(&mut self, first: Option<&str>, rest: &str) {
+        self.push_prefix(Prefix {
+            first: first.map(ToString::to_string),
+            rest: rest.to_string(),
+        });
+        self.levels.push(Level::default());
+    }
+
+    fn push_prefix(&mut self, prefix: Prefix) {
+        // a container that starts on the first line of a footnote must be indented past the
+        // footnote label on subsequent lines
+        for p in self.prefixes.iter_mut() {
+            if let Some(first) = &p.first {
+                let width = first.chars().count();
+                if width > p.rest.len() {
+                    p.rest = " ".repeat(width);
+                }
+            }
+        }
+        self.prefixes.push(prefix);
+    }
+
+    fn container_end<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.prefixes.last().map_or(false, |p| p.first.is_some()) {
+            // empty container, marker must still be written, including its trailing space
+            for p in self.prefixes.iter_mut() {
+                out.write_str(&p.first.take().unwrap_or_else(|| p.rest.clone()))?;
+            }
+            out.write_char('\n')?;
+        }
+        self.prefixes.pop();
+        self.levels.pop();
+        Ok(())
+    }
+
+    fn leaf_start(&mut self, leaf: Leaf<'s>) {
+        self.leaf = Some(leaf);
+        self.text.clear();
+        self.breaks.clear();
+        self.escapes.clear();
+        self.quote = None;
+        self.last_smart = None;
+    }
+
+    fn leaf_end<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        self.brace_quote(" ");
+        let mut text = std::mem::take(&mut self.text);
+        let mut breaks = std::mem::take(&mut self.breaks);
+        escape_context(&mut text, &mut breaks, &std::mem::take(&mut self.escapes));
+        match self.leaf.take() {
+            Some(leaf @ (Leaf::Paragraph | Leaf::Term)) => {
+                if let Some(pos) = escape_block_start(&mut text, &breaks) {
+                    for b in breaks.iter_mut().filter(|b| **b >= pos) {
+                        *b += 1;
+                    }
+                }
+                if let (Leaf::Paragraph, Some(width)) = (leaf, self.width) {
+                    let indent = |first: bool| {
+                        self.prefixes
+                            .iter()
+                            .map(|p| match &p.first {
+                                Some(s) if first => s.chars().count(),
+                                _ => p.rest.chars().count(),
+                            })
+                            .sum()
+                    };
+                    text = wrap(&text, &breaks, width, indent(true), indent(false));
+                }
+                self.prefixes.line(&text, &mut out)?;
+            }
+            Some(Leaf::Heading { level }) => {
+                let hashes = "#".repeat(level.into());
+                for l in text.split('\n') {
+                    let escape = if l.starts_with('#') { "\\" } else { "" };
+                    self.prefixes
+                        .line(&format!("{} {}{}", hashes, escape, l), &mut out)?;
+                }
+            }
+            Some(Leaf::Cell) => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.cells.push(text.replace('\n', " "));
+                }
+            }
+            Some(Leaf::Caption) => {
+                if let Some(table) = &mut self.table {
+                    table.caption = Some(text);
+                }
+            }
+            Some(Leaf::LinkDefinition { label }) => {
+                self.prefixes
+                    .line(&format!("[{}]: {}", label, text), &mut out)?;
+            }
+            Some(Leaf::Code { spec }) => {
+                let content = text.strip_suffix('\n').unwrap_or(&text);
+                let fence = lines::code_fence(content);
+                self.prefixes
+                    .line(&format!("{}{}", fence, spec), &mut out)?;
+                if !text.is_empty() {
+                    self.prefixes.line(content, &mut out)?;
+                }
+                self.prefixes.line(&fence, &mut out)?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn table_end<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let Table { rows, caption } = if let Some(table) = self.table.take() {
+            table
+        } else {
+            return Ok(());
+        };
+
+        // separators are at least three characters wide
+        let mut widths = Vec::new();
+        for Row { cells, .. } in &rows {
+            if widths.len() < cells.len() {
+                widths.resize(cells.len(), 3);
+            }
+            if self.canonical {
+                for (w, cell) in widths.iter_mut().zip(cells) {
+                    *w = (*w).max(cell.chars().count());
+                }
+            }
+        }
+
+        for (
+            i,
+            Row {
+                head,
+                cells,
+                alignments,
+            },
+        ) in rows.iter().enumerate()
+        {
+            let separator = alignments
+                .iter()
+                .zip(&widths)
+                .map(|(a, w)| match a {
+                    Alignment::Unspecified => "-".repeat(*w),
+                    Alignment::Left => format!(":{}", "-".repeat(w - 1)),
+                    Alignment::Center => format!(":{}:", "-".repeat(w - 2)),
+                    Alignment::Right => format!("{}:", "-".repeat(w - 1)),
+                })
+                .collect::<Vec<_>>()
+                .join(" | ");
+            let aligned = alignments
+                .iter()
+                .any(|a| !matches!(a, Alignment::Unspecified));
+            if !head && i == 0 && aligned {
+                self.prefixes
+                    .line(&format!("| {} |", separator), &mut out)?;
+            }
+            let cells = cells
+                .iter()
+                .zip(alignments)
+                .zip(&widths)
+                .map(|((cell, a), w)| {
+                    if !self.canonical {
+                        return cell.clone();
+                    }
+                    let pad = w - cell.chars().count();
+                    let left = match a {
+                        Alignment::Right => pad,
+                        Alignment::Center => pad / 2,
+                        Alignment::Unspecified | Alignment::Left => 0,
+                    };
+                    format!("{}{}{}", " ".repeat(left), cell, " ".repeat(pad - left))
+                })
+                .collect::<Vec<_>>();
+            self.prefixes
+                .line(&format!("| {} |", cells.join(" | ")), &mut out)?;
+            if *head {
+                self.prefixes
+                    .line(&format!("| {} |", separator), &mut out)?;
+            }
+        }
+
+        if let Some(caption) = caption {
+            self.prefixes.line(&format!("^ {}", caption), &mut out)?;
+        }
+        Ok(())
+    }
+
+    /// Spaces and soft breaks of the current block may be replaced by line breaks.
+    fn wrapping(&self) -> bool {
+        self.width.is_some() && matches!(self.leaf, Some(Leaf::Paragraph))
+    }
+
+    /// Buffer of the innermost open inline element.
+    fn buf(&mut self) -> &mut String {
+        self.buf_positions().0
+    }
+
+    /// Buffer of the innermost open inline element, and the positions of its breaks and of the
+    /// characters that may have to be escaped.
+    fn buf_positions(&mut self) -> (&mut String, &mut Vec<usize>, &mut Vec<usize>) {
+        if let Some(i) = self.inlines.last_mut() {
+            (&mut i.text, &mut i.state.breaks, &mut i.state.escapes)
+        } else {
+            (&mut self.text, &mut self.breaks, &mut self.escapes)
+        }
+    }
+
+    fn push(&mut self, open: &str, s: &str, close: &str) {
+        self.brace_quote(open);
+        let buf = self.buf();
+        buf.push_str(open);
+        buf.push_str(s);
+        buf.push_str(close);
+    }
+
+    fn inline_start(&mut self, c: &Container<'s>, attrs: &Attributes<'s>, escape: char) {
+        self.brace_quote("{");
+        if escape != '\0' {
+            let buf = self.buf();
+            let n = buf.len();
+            if buf.ends_with(escape) && !buf[..n - 1].ends_with('\\') {
+                self.insert(n - 1, '\\');
+            }
+        }
+        let reference = matches!(
+            c,
+            Container::Link(_, LinkType::Span(SpanLinkType::Reference))
+                | Container::Image(_, SpanLinkType::Reference)
+        );
+        let reference = if reference {
+            self.references.pop_front()
+        } else {
+            None
+        };
+        let (tag, attrs) = match reference {
+            // attributes of the definition are added again by the parser
+            Some(Reference {
+                tag,
+                definition_attributes,
+            }) => (
+                Some(tag),
+                attrs.iter().skip(definition_attributes).cloned().collect(),
+            ),
+            None => (None, attrs.clone()),
+        };
+        self.inlines.push(Inline {
+            container: c.clone(),
+            attrs,
+            text: String::new(),
+            state: InlineState {
+                breaks: Vec::new(),
+                escapes: Vec::new(),
+                tag,
+            },
+        });
+    }
+
+    fn inline_end(&mut self) {
+        self.brace_quote(" ");
+        let Inline {
+            container,
+            attrs,
+            text,
+            state:
+                InlineState {
+                    breaks,
+                    escapes,
+                    tag,
+                },
+        } = if let Some(i) = self.inlines.pop() {
+            i
+        } else {
+            return;
+        };
+
+        // `[^` would start a footnote reference
+        let after_bracket = self.inlines.last().map_or(false, |i| {
+            i.text.is_empty()
+                && matches!(
+                    i.container,
+                    Container::Span | Container::Link(..) | Container::Image(..)
+                )
+        });
+
+        let mut s = String::new();
+        // position of the text within the output, if kept
+        let mut text_pos = None;
+        match &container {
+            Container::Span if attrs.is_empty() => {
+                text_pos = Some(0);
+                s.push_str(&text);
+            }
+            Container::Span => {
+                s.push('[');
+                text_pos = Some(s.len());
+                s.push_str(&text);
+                s.push(']');
+            }
+            Container::Link(dst, LinkType::AutoLink | LinkType::Email) => {
+                s.push('<');
+                s.push_str(dst);
+                s.push('>');
+            }
+            Container::Link(dst, LinkType::Span(ty)) | Container::Image(dst, ty) => {
+                if matches!(container, Container::Image(..)) {
+                    s.push('!');
+                }
+                s.push('[');
+                text_pos = Some(s.len());
+                s.push_str(&text);
+                s.push(']');
+                let tag = if matches!(ty, SpanLinkType::Unresolved) {
+                    Some(dst)
+                } else {
+                    tag.as_ref()
+                };
+                if let Some(tag) = tag {
+                    s.push('[');
+                    // collapsed reference, the tag of which is the text as written within the
+                    // brackets
+                    let collapsed = self.canonical && {
+                        let mut unwrapped = format!("[{}]", text);
+                        for b in &breaks {
+                            unwrapped.replace_range(b + 1..=b + 1, " ");
+                        }
+                        let escapes: Vec<usize> = escapes.iter().map(|e| e + 1).collect();
+                        escape_context(&mut unwrapped, &mut [], &escapes);
+                        tag.as_ref() == &unwrapped[1..unwrapped.len() - 1]
+                    };
+                    if !collapsed {
+                        s.push_str(tag);
+                    }
+                    s.push(']');
+                } else {
+                    s.push('(');
+                    s.push_str(dst);
+                    s.push(')');
+                }
+            }
+            Container::Verbatim | Container::Math { .. } | Container::RawInline { .. } => {
+                if let Container::Math { display } = container {
+                    s.push_str(if display { "$$" } else { "$" });
+                }
+                // the shortest fence that does not occur in the text, as a fence of three or more
+                // backticks at the start of a paragraph would start a code block instead
+                let runs: Vec<usize> = text.split(|c| c != '`').map(str::len).collect();
+                let n = (1..).find(|n| !runs.contains(n)).unwrap();
+                let fence = "`".repeat(n);
+                // the parser only strips a space next to a backtick of the text
+                s.push_str(&fence);
+                if text.starts_with('`') {
+                    s.push(' ');
+                }
+                s.push_str(&text);
+                if text.ends_with('`') {
+                    s.push(' ');
+                }
+                s.push_str(&fence);
+                if let Container::RawInline { format } = container {
+                    s.push_str("{=");
+                    s.push_str(format.as_ref());
+                    s.push('}');
+                }
+            }
+            _ => {
+                let (c, open, close) = match container {
+                    Container::Subscript => ('~', "{~", "~}"),
+                    Container::Superscript => ('^', "{^", "^}"),
+                    Container::Insert => ('+', "{+", "+}"),
+                    Container::Delete => ('-', "{-", "-}"),
+                    Container::Strong => ('*', "{*", "*}"),
+                    Container::Emphasis => ('_', "{_", "_}"),
+                    Container::Mark => ('=', "{=", "=}"),
+                    _ => unreachable!(),
+                };
+                let braced = !matches!(c, '~' | '^' | '*' | '_')
+                    || (c == '^' && after_bracket)
+                    || text.starts_with(char::is_whitespace)
+                    || text.ends_with(char::is_whitespace);
+                if text.is_empty() {
+                    // empty containers are not recognized by the parser
+                } else if braced {
+                    s.push_str(open);
+                    text_pos = Some(s.len());
+                    s.push_str(&text);
+                    s.push_str(close);
+                } else {
+                    s.push(c);
+                    text_pos = Some(s.len());
+                    s.push_str(&text);
+                    s.push(c);
+                }
+            }
+        }
+        write_attributes(&attrs, self.canonical, &mut s);
+        let (buf, buf_breaks, buf_escapes) = self.buf_positions();
+        if let Some(pos) = text_pos {
+            let offset = buf.len() + pos;
+            buf_breaks.extend(breaks.iter().map(|b| offset + b));
+            buf_escapes.extend(escapes.iter().map(|e| offset + e));
+        }
+        buf.push_str(&s);
+    }
+
+    fn left_quote(&mut self, q: char) {
+        self.brace_quote("{");
+        let buf = self.buf();
+        if buf.is_empty() || buf.ends_with(char::is_whitespace) {
+            buf.push(q);
+            let pos = buf.len() - 1;
+            self.quote = Some(pos);
+        } else {
+            buf.push('{');
+            buf.push(q);
+        }
+    }
+
+    fn right_quote(&mut self, q: char) {
+        self.brace_quote("}");
+        let buf = self.buf();
+        if buf.ends_with(|c: char| c.is_alphanumeric() || matches!(c, '.' | ',' | ';' | '!' | '?'))
+        {
+            buf.push(q);
+        } else {
+            buf.push(q);
+            buf.push('}');
+        }
+    }
+
+    /// Write a smart punctuation atom, keeping it separate from any adjacent equal characters of
+    /// text.
+    fn smart(&mut self, s: &str, c: char) {
+        self.brace_quote(s);
+        let depth = self.inlines.len();
+        let last_smart = self.last_smart;
+        let buf = self.buf();
+        let n = buf.len();
+        if last_smart != Some((depth, n)) && buf.ends_with(c) && !buf[..n - 1].ends_with('\\') {
+            self.insert(n - 1, '\\');
+        }
+        let buf = self.buf();
+        buf.push_str(s);
+        self.last_smart = Some((depth, buf.len()));
+    }
+
+    /// Brace a preceding left quote that would otherwise not be recognized as opening, i.e. if it
+    /// is followed by whitespace.
+    fn brace_quote(&mut self, next: &str) {
+        if let Some(pos) = self.quote.take() {
+            if next.is_empty() {
+                self.quote = Some(pos);
+            } else if next.starts_with(char::is_whitespace) {
+                self.insert(pos, '{');
+            }
+        }
+    }
+
+    /// Insert a character into the buffer of the innermost open inline element, before any
+    /// breaks at or after the position.
+    ///
+    /// A backslash escapes the character at the position, which then no longer has to be escaped.
+    fn insert(&mut self, pos: usize, c: char) {
+        let (buf, breaks, escapes) = self.buf_positions();
+        buf.insert(pos, c);
+        if c == '\\' {
+            escapes.retain(|e| *e != pos);
+        }
+        for b in breaks.iter_mut().chain(escapes).filter(|b| **b >= pos) {
+            *b += c.len_utf8();
+        }
+    }
+}
+
+/// Determine the fence lengths of all divs, in the order they are started.
+///
+/// An outer div requires a longer fence than any of the divs it contains.
+fn fence_lengths(events: &[Event]) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for e in events {
+        match e {
+            Event::Start(Container::Div { .. }, ..) => {
+                stack.push((lengths.len(), 0));
+                lengths.push(3);
+            }
+            Event::End(Container::Div { .. }) => {
+                if let Some((i, inner)) = stack.pop() {
+                    lengths[i] = 3 + inner;
+                    if let Some((_, h)) = stack.last_mut() {
+                        *h = (*h).max(inner + 1);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    lengths
+}
+
+fn list_marker(kind: ListKind, n: u64, c: &Container) -> String {
+    match kind {
+        ListKind::Unordered(b) => char::from(b).to_string(),
+        ListKind::Task(b) => {
+            let checked = matches!(c, Container::TaskListItem { checked: true });
+            format!("{} [{}]", char::from(b), if checked { 'x' } else { ' ' })
+        }
+        ListKind::Ordered {
+            numbering, style, ..
+        } => style.marker(&numbering.format_number(n)),
+    }
+}
+
+/// Write escaped text, and the positions of its spaces to `breaks` if given.
+///
+/// The positions of characters that may have to be escaped depending on the surrounding text are
+/// added to `escapes`, see [`escape_context`].
+fn write_text(
+    s: &str,
+    cell: bool,
+    out: &mut String,
+    mut breaks: Option<&mut Vec<usize>>,
+    escapes: &mut Vec<usize>,
+) {
+    for c in s.chars() {
+        if let (' ', Some(breaks)) = (c, &mut breaks) {
+            breaks.push(out.len());
+        }
+        let escape = match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '{' | '}' | '~' | '^' | '\'' | '"' => true,
+            '<' | '-' | '.' | ':' => {
+                escapes.push(out.len());
+                false
+            }
+            '|' => cell,
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// Escape the characters at the given positions of the text of a block that would otherwise be
+/// parsed together with the surrounding text, e.g. as smart punctuation, a symbol or an autolink,
+/// and move the breaks after them.
+///
+/// This is decided on the whole text of the block rather than on each string, as the surrounding
+/// text may be split into multiple events, which would be merged when the output is parsed again.
+fn escape_context(text: &mut String, breaks: &mut [usize], positions: &[usize]) {
+    let mut out = String::with_capacity(text.len());
+    let mut escaped = Vec::new();
+    let mut last = 0;
+    for &pos in positions {
+        out.push_str(&text[last..pos]);
+        last = pos;
+        let c = text[pos..].chars().next().unwrap();
+        let next = &text[pos + c.len_utf8()..];
+        let escape = match c {
+            '<' => !next.starts_with(char::is_whitespace) && !next.is_empty(),
+            '-' => out.ends_with('-') && escaped.last() != Some(&(pos - 1)),
+            '.' => out.ends_with(".."),
+            ':' => {
+                let name = next
+                    .bytes()
+                    .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'+' | b'_'))
+                    .count();
+                name > 0 && next[name..].starts_with(':')
+            }
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+            escaped.push(pos);
+        }
+    }
+    out.push_str(&text[last..]);
+    for b in breaks {
+        *b += escaped.iter().filter(|e| **e < *b).count();
+    }
+    *text = out;
+}
+
+/// Fill the lines of a paragraph up to the width, by replacing each breakable space or soft break,
+/// at the given positions, with either a space or a line break.
+fn wrap(text: &str, breaks: &[usize], width: usize, first_indent: usize, indent: usize) -> String {
+    let wrapped = fill(text, breaks, width, first_indent, indent);
+    let first_line = wrapped.split('\n').next().unwrap_or("");
+    let paragraph = block::parse(first_line, Syntax::default())
+        .first()
+        .map_or(true, |e| {
+            matches!(
+                e.kind,
+                block::EventKind::Enter(block::Node::Leaf(block::Leaf::Paragraph))
+            )
+        });
+    if paragraph {
+        wrapped
+    } else {
+        // might be parsed as e.g. a link definition or thematic break, keep the line breaks of
+        // the input instead
+        text.to_string()
+    }
+}
+
+fn fill(text: &str, breaks: &[usize], width: usize, first_indent: usize, indent: usize) -> String {
+    // split into lines at the line breaks that must be kept, and into words at the breaks
+    let mut lines = vec![vec![String::new()]];
+    let mut breaks = breaks.iter().peekable();
+    let mut chars = text.char_indices().peekable();
+    let mut space = true;
+    while let Some((i, c)) = chars.next() {
+        let words = lines.last_mut().unwrap();
+        if breaks.peek() == Some(&&i) {
+            breaks.next();
+            // whitespace adjacent to a line break would be removed by the parser
+            if space || chars.peek().map_or(true, |(_, c)| c.is_whitespace()) {
+                words.last_mut().unwrap().push(' ');
+            } else {
+                words.push(String::new());
+            }
+        } else if c == '\n' {
+            lines.push(vec![String::new()]);
+        } else {
+            words.last_mut().unwrap().push(c);
+        }
+        space = c.is_whitespace();
+    }
+
+    let mut wrapped = String::with_capacity(text.len());
+    let mut col = first_indent;
+    for (i, words) in lines.iter().enumerate() {
+        if i > 0 {
+            wrapped.push('\n');
+            col = indent;
+        }
+
+        for (j, word) in words.iter().enumerate() {
+            let len = word.chars().count();
+            if j > 0 {
+                // the first line alone must not be e.g. attributes or a thematic break
+                let first = i == 0 && !wrapped.contains('\n');
+                let valid = !first
+                    || (wrapped.contains(char::is_alphanumeric)
+                        && !(j == 1 && words[0].starts_with('{')));
+                if col + 1 + len > width && valid && !starts_block(word) {
+                    wrapped.push('\n');
+                    col = indent;
+                } else {
+                    wrapped.push(' ');
+                    col += 1;
+                }
+            }
+            wrapped.push_str(word);
+            col += len;
+        }
+    }
+    wrapped
+}
+
+/// Whether a line that starts with the word might start a block in some container.
+fn starts_block(word: &str) -> bool {
+    let marker = word
+        .split(' ')
+        .next()
+        .unwrap_or(word)
+        .strip_suffix(|c| matches!(c, '.' | ')'))
+        .map_or(false, |n| {
+            n.trim_start_matches('(')
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric())
+        });
+    marker
+        || word.starts_with(|c| {
+            matches!(
+                c,
+                '#' | '>' | '-' | '+' | '*' | ':' | '|' | '[' | '{' | '(' | '`' | '~' | '^'
+            )
+        })
+}
+
+fn write_attributes(attrs: &Attributes, canonical: bool, out: &mut String) {
+    if attrs.is_empty() {
+        return;
+    }
+    if canonical {
+        write_attributes(&canonical_attributes(attrs), false, out);
+        return;
+    }
+    out.push('{');
+    for (i, (k, v)) in attrs.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let v = v.to_string();
+        let name = !v.is_empty() && v.bytes().all(crate::attr::is_name);
+        match k {
+            AttributeKind::Class if name => {
+                out.push('.');
+                out.push_str(&v);
+            }
+            AttributeKind::Id if name => {
+                out.push('#');
+                out.push_str(&v);
+            }
+            AttributeKind::Comment => {
+                out.push('%');
+                out.push_str(&v);
+                out.push('%');
+            }
+            _ => {
+                out.push_str(k.key().unwrap());
+                out.push_str("=\"");
+                for c in v.chars() {
+                    if matches!(c, '"' | '\\') {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                out.push('"');
+            }
+        }
+    }
+    out.push('}');
+}
+
+/// Order attributes as id, classes, key-value pairs and comments, with a single element per key.
+fn canonical_attributes<'a>(attrs: &'a Attributes) -> Attributes<'a> {
+    let mut canonical = Attributes::new();
+    if let Some(id) = attrs.get_value("id") {
+        canonical.push((AttributeKind::Id, id));
+    }
+    if let Some(class) = attrs.get_value("class") {
+        for c in class.to_string().split_ascii_whitespace() {
+            canonical.push((AttributeKind::Class, AttributeValue::from(c.to_string())));
+        }
+    }
+    for (key, value) in attrs.unique_pairs() {
+        if !matches!(key, "id" | "class") {
+            canonical.push((AttributeKind::Pair { key: key.into() }, value));
+        }
+    }
+    for (kind, value) in attrs.iter() {
+        if matches!(kind, AttributeKind::Comment) {
+            canonical.push((AttributeKind::Comment, value.clone()));
+        }
+    }
+    canonical
+}
+
+/// Escape the first character of a paragraph if it would otherwise start another kind of block,
+/// and return the position of the inserted character.
+///
+/// Soft breaks at the positions in `breaks` are not considered to end the first line, as they
+/// may still be replaced by spaces when wrapping lines.
+fn escape_block_start(text: &mut String, breaks: &[usize]) -> Option<usize> {
+    // inline attributes alone on the first line would be block attributes, break the line within
+    // them instead
+    let line = text.split('\n').next().unwrap_or("");
+    let n = crate::attr::valid(line);
+    if n > 0 && n == line.trim_end().len() && !breaks.contains(&line.len()) {
+        text.insert(n - 1, '\n');
+        return Some(n - 1);
+    }
+
+    let mut chars = text.chars();
+    let first = chars.next()?;
+    let ends_marker = |s: &str| s.is_empty() || s.starts_with(char::is_whitespace);
+    let rest = chars.as_str();
+    let escape = match first {
+        '#' => ends_marker(rest.trim_start_matches('#')),
+        '>' | '-' | '+' => ends_marker(rest),
+        ':' => ends_marker(rest) || rest.starts_with("::"),
+        '|' => true,
+        _ => false,
+    };
+    if escape {
+        text.insert(0, '\\');
+        return Some(0);
+    }
+
+    // ordered list item markers, e.g. `1.`, `a)` or `(iv)`
+    let paren = first == '(';
+    let number = text[usize::from(paren)..]
+        .bytes()
+        .take_while(u8::is_ascii_alphanumeric)
+        .count();
+    if number > 0 {
+        let i = usize::from(paren) + number;
+        let delim = text[i..].chars().next();
+        let valid = if paren {
+            delim == Some(')')
+        } else {
+            matches!(delim, Some('.' | ')'))
+        };
+        if valid && ends_marker(&text[i + 1..]) {
+            let pos = if paren { 0 } else { i };
+            text.insert(pos, '\\');
+            return Some(pos);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Render;
+
+    macro_rules! test_djot {
+        ($src:expr, $expected:expr $(,)?) => {
+            let mut actual = String::new();
+            super::Renderer::default()
+                .push(crate::Parser::new($src), &mut actual)
+                .unwrap();
+            assert_eq!(actual, $expected);
+            let mut again = String::new();
+            super::Renderer::default()
+                .push(crate::Parser::new(&actual), &mut again)
+                .unwrap();
+            assert_eq!(again, actual, "not idempotent");
+        };
+    }
+
+    macro_rules! test_format {
+        ($src:expr, $width:expr, $expected:expr $(,)?) => {
+            let options = super::FormatOptions { width: $width };
+            let actual = super::format($src, &options);
+            assert_eq!(actual, $expected);
+            let again = super::format(&actual, &options);
+            assert_eq!(again, actual, "not idempotent");
+        };
+    }
+
+    #[test]
+    fn para() {
+        test_djot!(
+            concat!(
+                "a\n", //
+                "b\n", "\n", "\n", "c\n",
+            ),
+            concat!(
+                "a\n", //
+                "b\n", "\n", "c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn heading() {
+        test_djot!(
+            concat!(
+                "{#a}\n", //
+                "## b\n", "c\n", "\n", "# d\n",
+            ),
+            concat!(
+                "{#a}\n", //
+                "## b\n", "## c\n", "\n", "# d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_tight() {
+        test_djot!(
+            concat!(
+                "- a\n", //
+                "\n", "  + b\n", "  + c\n", "- d\n",
+            ),
+            concat!(
+                "- a\n", //
+                "\n", "  + b\n", "  + c\n", "- d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_loose() {
+        test_djot!(
+            concat!(
+                "3) a\n", //
+                "\n", "   b\n", "4) c\n",
+            ),
+            concat!(
+                "3) a\n", //
+                "\n", "   b\n", "\n", "4) c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_numbering() {
+        test_djot!(
+            concat!(
+                "(iv) a\n", //
+                "(v) b\n", "\n", "Y. c\n", "Z. d\n",
+            ),
+            concat!(
+                "(iv) a\n", //
+                "(v) b\n", "\n", "Y. c\n", "Z. d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_task() {
+        test_djot!(
+            concat!(
+                "* [ ] a\n", //
+                "* [x] b\n",
+            ),
+            concat!(
+                "* [ ] a\n", //
+                "* [x] b\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_empty_item() {
+        test_djot!(
+            concat!(
+                "- \n", //
+                "- a\n",
+            ),
+            concat!(
+                "- \n", //
+                "\n", "- a\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn description_list() {
+        test_djot!(
+            concat!(
+                ": a\n", //
+                "\n", "  b\n", ": c\n",
+            ),
+            concat!(
+                ": a\n", //
+                "\n", "  b\n", "\n", ": c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn blockquote() {
+        test_djot!(
+            concat!(
+                "> a\n", //
+                ">\n", "> > b\n",
+            ),
+            concat!(
+                "> a\n", //
+                ">\n", "> > b\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn div_nested() {
+        test_djot!(
+            concat!(
+                "::::: a\n", //
+                "b\n",
+                "\n",
+                "::: c\n",
+                "d\n",
+                ":::\n",
+                ":::::\n",
+            ),
+            concat!(
+                ":::: a\n", //
+                "b\n", "\n", "::: c\n", "d\n", ":::\n", "::::\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn code_block() {
+        test_djot!(
+            concat!(
+                "````rust\n", //
+                "```\n",
+                "````\n",
+                "\n",
+                "``` =html\n",
+                "<br>\n",
+                "```\n",
+            ),
+            concat!(
+                "````rust\n", //
+                "```\n",
+                "````\n",
+                "\n",
+                "```=html\n",
+                "<br>\n",
+                "```\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn table() {
+        test_djot!(
+            concat!(
+                "|a|b|\n", //
+                "|:-|-:|\n",
+                "|c|d\\|e|\n",
+                "^ caption\n",
+            ),
+            concat!(
+                "| a | b |\n", //
+                "| :-- | --: |\n",
+                "| c | d\\|e |\n",
+                "^ caption\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn footnote() {
+        test_djot!(
+            concat!(
+                "a[^b]\n", //
+                "\n",
+                "[^b]: c\n",
+                "\n",
+                "    d\n",
+            ),
+            concat!(
+                "a[^b]\n", //
+                "\n",
+                "[^b]: c\n",
+                "\n",
+                "  d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn footnote_nested() {
+        test_djot!(
+            concat!(
+                "[^a]\n", //
+                "\n",
+                "[^a]: - > b\n",
+                "        > c\n",
+            ),
+            concat!(
+                "[^a]\n", //
+                "\n",
+                "[^a]: - > b\n",
+                "        > c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn link_definition() {
+        test_djot!(
+            concat!(
+                "[a][b] [c][]\n", //
+                "\n",
+                "{.d}\n",
+                "[b]: url\n",
+            ),
+            concat!(
+                "[a](url){.d} [c][c]\n", //
+                "\n",
+                "{.d}\n",
+                "[b]: url\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn inline_containers() {
+        test_djot!(
+            "*a* _b_ {* c *} ^d^ ~e~ {=f=} {+g+} {-h-} [i]{.j} [k]\n",
+            "*a* _b_ {* c *} ^d^ ~e~ {=f=} {+g+} {-h-} [i]{.j} \\[k\\]\n",
+        );
+    }
+
+    #[test]
+    fn verbatim() {
+        test_djot!(
+            "`a` `` `b` `` $`c` $$`d` `e`{=html} \\$`f`\n",
+            "`a` `` `b` `` $`c` $$`d` `e`{=html} \\$`f`\n",
+        );
+    }
+
+    #[test]
+    fn verbatim_round_trip() {
+        for src in [
+            "run ``echo `date` `` now\n",
+            "`` `a``\n",
+            "`(\n~...``\n",
+            "`a`` ``` b`\n",
+        ] {
+            let mut actual = String::new();
+            super::Renderer::default()
+                .push(crate::Parser::new(src), &mut actual)
+                .unwrap();
+            let expected: Vec<_> = crate::Parser::new(src).collect();
+            let events: Vec<_> = crate::Parser::new(&actual).collect();
+            assert_eq!(events, expected, "\n{:?}\n{:?}", src, actual);
+        }
+    }
+
+    #[test]
+    fn links() {
+        test_djot!(
+            "[a](b){#c} ![d *e*](f) <https://g> <h@i.j> \\![k](l) \\<m>\n",
+            "[a](b){#c} ![d *e*](f) <https://g> <h@i.j> \\![k](l) \\<m>\n",
+        );
+    }
+
+    #[test]
+    fn smart_punctuation() {
+        test_djot!(
+            "'a' \"b\" don't x {\"y\"} ... -- --- \\...\n",
+            "'a' \"b\" don't x \"y\" ... -- --- ..\\.\n",
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        test_djot!(
+            concat!(
+                "\\- a\\*\\_ \\<b \\:c: \\-\\-\n", //
+                "\\> d\n",
+                "\n",
+                "1\\. e\n",
+                "\n",
+                "\\# f\n",
+            ),
+            concat!(
+                "\\- a\\*\\_ \\<b \\:c: -\\-\n", //
+                "> d\n",
+                "\n",
+                "1\\. e\n",
+                "\n",
+                "\\# f\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn smart_punctuation_blocks() {
+        test_djot!(
+            concat!(
+                "a--\n", //
+                "\n",
+                "bc\\---\n",
+            ),
+            concat!(
+                "a--\n", //
+                "\n",
+                "bc\\---\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        test_djot!(
+            concat!(
+                "{#a .b c=\"d e\" %f%}\n", //
+                "g\n",
+                "\n",
+                "{.dangling}\n",
+            ),
+            concat!(
+                "{#a .b c=\"d e\" %f%}\n", //
+                "g\n",
+                "\n",
+                "{.dangling}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn attributes_paragraph_start() {
+        test_djot!(
+            concat!(
+                "{\n", //
+                ".a\n", "}\n", "para\n", "\n", "{\n", ".b}\n",
+            ),
+            concat!(
+                "{.a\n", //
+                "}\n", "para\n", "\n", "{.b\n", "}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn format_table() {
+        test_format!(
+            concat!(
+                "|a|bcd|e|\n", //
+                "|:-|:-:|-:|\n",
+                "|fghij|k|l|\n",
+            ),
+            None,
+            concat!(
+                "| a     | bcd |   e |\n", //
+                "| :---- | :-: | --: |\n",
+                "| fghij |  k  |   l |\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn format_attributes() {
+        test_format!(
+            concat!(
+                "{k=v .a %c% #x k=w}\n", //
+                "{.b #y}\n",
+                "para [span]{class=\"c d\" #z}\n",
+            ),
+            None,
+            concat!(
+                "{#y .a .b k=\"w\" %c%}\n", //
+                "para [span]{#z .c .d}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn format_references() {
+        test_format!(
+            concat!(
+                "[a][b]{.x} [c][] ![d][b] [e][f] [Heading][]\n", //
+                "\n",
+                "{.y}\n",
+                "[b]: url\n",
+                "\n",
+                "[c]: url\n",
+                "\n",
+                "# Heading\n",
+            ),
+            None,
+            concat!(
+                "[a][b]{.x} [c][] ![d][b] [e][f] [Heading][]\n", //
+                "\n",
+                "{.y}\n",
+                "[b]: url\n",
+                "\n",
+                "[c]: url\n",
+                "\n",
+                "# Heading\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn format_wrap() {
+        test_format!(
+            concat!(
+                "a b c d e f `g h i`\n", //
+                "j k\\\n",
+                "l m\n",
+                "\n",
+                "- n o p q r s t u v w\n",
+                "\n",
+                "> x  y z\n",
+            ),
+            Some(10),
+            concat!(
+                "a b c d e\n", //
+                "f `g h i`\n",
+                "j k\\\n",
+                "l m\n",
+                "\n",
+                "- n o p q\n",
+                "  r s t u\n",
+                "  v w\n",
+                "\n",
+                "> x  y z\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn format_wrap_separators() {
+        test_format!(
+            "a\u{2028}b c\u{2029}d e f\n",
+            Some(5),
+            "a\u{2028}b\nc\u{2029}d e\nf\n",
+        );
+    }
+
+    #[test]
+    fn format_wrap_block_start() {
+        test_format!(
+            concat!(
+                "{.a} b\n", //
+                "\n",
+                "c - d 1. e\n",
+                "\n",
+                "[f\n",
+                "\\[g\\]: h](i)\n",
+            ),
+            Some(1),
+            concat!(
+                "{.a} b\n", //
+                "\n",
+                "c -\n",
+                "d 1.\n",
+                "e\n",
+                "\n",
+                "[f\n",
+                "\\[g\\]:\n",
+                "h](i)\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn format_wrap_link_definition() {
+        test_format!(
+            concat!(
+                "[a\n", //
+                "\\[b\\]: c](d)\n",
+            ),
+            Some(80),
+            concat!(
+                "[a\n", //
+                "\\[b\\]: c](d)\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn format_lists() {
+        test_format!(
+            concat!(
+                "* a\n", //
+                "+ b\n",
+                "\n",
+                "1) c\n",
+                "2) d\n",
+                "\n",
+                "1. e\n",
+                "\n",
+                "+ - - f\n",
+            ),
+            None,
+            concat!(
+                "- a\n", //
+                "\n",
+                "* b\n",
+                "\n",
+                "1. c\n",
+                "2. d\n",
+                "\n",
+                "1) e\n",
+                "\n",
+                "- * + f\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn escapes_split() {
+        test_djot!("<*\n", "\\<\\*\n");
+        test_djot!("& --}\n", "& -\\-\\}\n");
+        test_djot!("a\\:smile:\n", "a\\:smile:\n");
+        test_djot!("\\--\\-\n", "-\\--\n");
+    }
+
+    /// Inputs of the unit tests, i.e. the lines before the `.` within each fence.
+    fn corpus() -> Vec<String> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/html-ut/ut");
+        let mut inputs = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(false, |e| e == "test") {
+                let content = std::fs::read_to_string(path).unwrap();
+                let mut input = None;
+                for line in content.lines() {
+                    if !line.is_empty() && line.bytes().all(|c| c == b'`') {
+                        input = if input.is_some() {
+                            None
+                        } else {
+                            Some(String::new())
+                        };
+                    } else if line == "." {
+                        inputs.extend(input.take());
+                    } else if let Some(input) = &mut input {
+                        input.push_str(line);
+                        input.push('\n');
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    #[test]
+    fn format_corpus() {
+        let inputs = corpus();
+        assert!(!inputs.is_empty());
+        for width in [None, Some(20)] {
+            let options = super::FormatOptions { width };
+            for src in &inputs {
+                let once = super::format(src, &options);
+                let twice = super::format(&once, &options);
+                assert_eq!(twice, once, "not idempotent\n{:?}", src);
+            }
+        }
+    }
+}
@@ -0,0 +1,560 @@
+use crate::html::write_text;
+use crate::html::CodeHighlighter;
+
+/// A built-in [`CodeHighlighter`] for a few common languages.
+///
+/// Comments, strings, numbers and keywords are wrapped in `<span>` elements with the classes
+/// `hl-comment`, `hl-string`, `hl-number` and `hl-keyword`, respectively, which can be styled
+/// with CSS. The highlighting is lexical only, it does not parse the code.
+///
+/// Supported languages are C (`c`, `h`), C++ (`cpp`, `c++`, `cc`, `hpp`), Go (`go`), Java
+/// (`java`), JavaScript (`javascript`, `js`), JSON (`json`), Python (`python`, `py`), Rust
+/// (`rust`, `rs`), shell (`sh`, `bash`, `shell`), TOML (`toml`) and TypeScript (`typescript`,
+/// `ts`). The language is matched case-insensitively, code blocks with other languages are
+/// written as plain text.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// let src = "``` rust\nlet x = 1; // one\n```\n";
+/// let mut actual = String::new();
+/// let renderer = Renderer::minified().with_highlighter(Highlighter);
+/// renderer.push(Parser::new(src), &mut actual).unwrap();
+/// assert_eq!(
+///     actual,
+///     concat!(
+///         "<pre><code class=\"language-rust\">",
+///         "<span class=\"hl-keyword\">let</span> x = <span class=\"hl-number\">1</span>; ",
+///         "<span class=\"hl-comment\">// one</span>\n",
+///         "</code></pre>",
+///     ),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Highlighter;
+
+impl CodeHighlighter for Highlighter {
+    fn highlight(&self, language: &str, code: &str) -> Option<String> {
+        let lang = LANGUAGES
+            .iter()
+            .find(|l| l.names.iter().any(|n| n.eq_ignore_ascii_case(language)))?;
+        let mut out = String::with_capacity(code.len());
+        let mut rest = code;
+        while let Some(c) = rest.chars().next() {
+            let (class, len) = lang.token(rest, c);
+            let (token, r) = rest.split_at(len);
+            if let Some(class) = class {
+                out.push_str("<span class=\"hl-");
+                out.push_str(class);
+                out.push_str("\">");
+                write_text(token, &mut out).unwrap();
+                out.push_str("</span>");
+            } else {
+                write_text(token, &mut out).unwrap();
+            }
+            rest = r;
+        }
+        Some(out)
+    }
+}
+
+struct Language {
+    /// Names of the language, used as code block languages.
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Characters that delimit strings.
+    quotes: &'static [char],
+    /// Delimiters of strings that may span multiple lines, e.g. triple quotes, matched before
+    /// single quotes.
+    long_quotes: &'static [&'static str],
+    /// Raw strings are delimited by `r"` or `r#"` up to `"` or `"#`, as in Rust.
+    raw_strings: bool,
+}
+
+impl Language {
+    /// Determine the class and length of the token at the start of the input, which begins with
+    /// the character `c`.
+    fn token(&self, s: &str, c: char) -> (Option<&'static str>, usize) {
+        if self.line_comments.iter().any(|l| s.starts_with(l)) {
+            (Some("comment"), s.find('\n').unwrap_or(s.len()))
+        } else if let Some((open, close)) =
+            self.block_comment.filter(|(open, _)| s.starts_with(open))
+        {
+            let len = s[open.len()..]
+                .find(close)
+                .map_or(s.len(), |i| open.len() + i + close.len());
+            (Some("comment"), len)
+        } else if let Some(quote) = self.long_quotes.iter().find(|q| s.starts_with(*q)) {
+            (Some("string"), string_len(s, quote.len(), quote, true))
+        } else if let Some((open, close)) = self.raw_string(s) {
+            (Some("string"), string_len(s, open, &close, false))
+        } else if self.quotes.contains(&c) {
+            let mut close = [0; 4];
+            (
+                Some("string"),
+                string_len(s, 1, c.encode_utf8(&mut close), true),
+            )
+        } else if c == '\\' {
+            // an escaped character outside of a string, e.g. a quote in shell
+            let len = s[1..].chars().next().map_or(0, char::len_utf8);
+            (None, 1 + len)
+        } else if c.is_ascii_digit() {
+            let len = s
+                .find(|d: char| !(d.is_ascii_alphanumeric() || d == '.' || d == '_'))
+                .unwrap_or(s.len());
+            (Some("number"), len)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = s
+                .find(|d: char| !(d.is_alphanumeric() || d == '_'))
+                .unwrap_or(s.len());
+            let class = if self.keywords.contains(&&s[..len]) {
+                Some("keyword")
+            } else {
+                None
+            };
+            (class, len)
+        } else {
+            (None, c.len_utf8())
+        }
+    }
+
+    /// Determine the length of the opening delimiter and the closing delimiter of a raw string at
+    /// the start of the input, if any.
+    fn raw_string(&self, s: &str) -> Option<(usize, String)> {
+        if !self.raw_strings {
+            return None;
+        }
+        let rest = s.strip_prefix('b').unwrap_or(s).strip_prefix('r')?;
+        let hashes = rest.bytes().take_while(|b| *b == b'#').count();
+        if rest[hashes..].starts_with('"') {
+            let open = s.len() - rest.len() + hashes + 1;
+            Some((open, format!("\"{}", "#".repeat(hashes))))
+        } else {
+            None
+        }
+    }
+}
+
+/// Determine the length of a string that starts with an opening delimiter of length `open` and
+/// ends with `close`, or at the end of the input if it is unclosed.
+fn string_len(s: &str, open: usize, close: &str, escapes: bool) -> usize {
+    let mut escaped = false;
+    s[open..]
+        .char_indices()
+        .find(|(i, d)| {
+            let end = !escaped && s[open + i..].starts_with(close);
+            escaped = escapes && !escaped && *d == '\\';
+            end
+        })
+        .map_or(s.len(), |(i, _)| open + i + close.len())
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+    "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long",
+    "register", "return", "short", "signed", "sizeof", "static", "struct", "switch", "true",
+    "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+const CPP_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "explicit",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "noexcept",
+    "nullptr",
+    "operator",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const GO_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "false",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "nil",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "true",
+    "type",
+    "var",
+];
+
+const JAVA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "true",
+    "try",
+    "var",
+    "void",
+    "while",
+];
+
+const JS_KEYWORDS: &[&str] = &[
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "from",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "of",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+    "local", "return", "then", "until", "while",
+];
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        names: &["c", "h"],
+        keywords: C_KEYWORDS,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        long_quotes: &[],
+        raw_strings: false,
+    },
+    Language {
+        names: &["cpp", "c++", "cc", "hpp"],
+        keywords: CPP_KEYWORDS,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        long_quotes: &[],
+        raw_strings: false,
+    },
+    Language {
+        names: &["go"],
+        keywords: GO_KEYWORDS,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        long_quotes: &[],
+        raw_strings: false,
+    },
+    Language {
+        names: &["java"],
+        keywords: JAVA_KEYWORDS,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        long_quotes: &[],
+        raw_strings: false,
+    },
+    Language {
+        names: &["javascript", "js", "typescript", "ts"],
+        keywords: JS_KEYWORDS,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        long_quotes: &[],
+        raw_strings: false,
+    },
+    Language {
+        names: &["json"],
+        keywords: &["false", "null", "true"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        long_quotes: &[],
+        raw_strings: false,
+    },
+    Language {
+        names: &["python", "py"],
+        keywords: PYTHON_KEYWORDS,
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        long_quotes: &["\"\"\"", "'''"],
+        raw_strings: false,
+    },
+    Language {
+        names: &["rust", "rs"],
+        keywords: RUST_KEYWORDS,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        // single quotes are ambiguous with lifetimes
+        quotes: &['"'],
+        long_quotes: &[],
+        raw_strings: true,
+    },
+    Language {
+        names: &["sh", "bash", "shell"],
+        keywords: SHELL_KEYWORDS,
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        long_quotes: &[],
+        raw_strings: false,
+    },
+    Language {
+        names: &["toml"],
+        keywords: &["false", "true"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        long_quotes: &["\"\"\"", "'''"],
+        raw_strings: false,
+    },
+];
+
+#[cfg(test)]
+mod test {
+    use super::Highlighter;
+    use crate::html::CodeHighlighter;
+
+    macro_rules! test_highlight {
+        ($language:expr, $src:expr, $expected:expr $(,)?) => {
+            let actual = Highlighter.highlight($language, $src);
+            assert_eq!(actual.as_deref(), $expected, "\n\n{}\n\n", $src);
+        };
+    }
+
+    #[test]
+    fn unsupported() {
+        test_highlight!("", "a", None);
+        test_highlight!("brainfuck", "+[-]", None);
+    }
+
+    #[test]
+    fn tokens() {
+        test_highlight!(
+            "rust",
+            "fn f<'a>(x: &'a str) -> u8 { 0x1f }",
+            Some(concat!(
+                r#"<span class="hl-keyword">fn</span> f&lt;'a&gt;(x: &amp;'a str) -&gt; u8 "#,
+                r#"{ <span class="hl-number">0x1f</span> }"#,
+            )),
+        );
+        test_highlight!(
+            "Python",
+            "if x1 == 'a\\'b': # c\n    pass",
+            Some(concat!(
+                r#"<span class="hl-keyword">if</span> x1 == "#,
+                r#"<span class="hl-string">'a\'b'</span>: "#,
+                r#"<span class="hl-comment"># c</span>"#,
+                "\n    ",
+                r#"<span class="hl-keyword">pass</span>"#,
+            )),
+        );
+    }
+
+    #[test]
+    fn triple_quotes() {
+        test_highlight!(
+            "python",
+            "\"\"\"a \"b\"\n\"\"\" + ''''c'''",
+            Some(concat!(
+                r#"<span class="hl-string">"""a "b""#,
+                "\n",
+                r#""""</span> + <span class="hl-string">''''c'''</span>"#,
+            )),
+        );
+    }
+
+    #[test]
+    fn escape_outside_string() {
+        test_highlight!(
+            "sh",
+            "echo it\\'s 'a'",
+            Some(r#"echo it\'s <span class="hl-string">'a'</span>"#),
+        );
+        test_highlight!(
+            "python",
+            "x = \\'\ny = 1",
+            Some(concat!(
+                "x = \\'\n",
+                r#"y = <span class="hl-number">1</span>"#
+            )),
+        );
+    }
+
+    #[test]
+    fn raw_strings() {
+        test_highlight!(
+            "rust",
+            r##"r#"a"b"# r"\" br"c" r#x"##,
+            Some(concat!(
+                r##"<span class="hl-string">r#"a"b"#</span> "##,
+                r#"<span class="hl-string">r"\"</span> "#,
+                r#"<span class="hl-string">br"c"</span> r#x"#,
+            )),
+        );
+    }
+
+    #[test]
+    fn unclosed() {
+        test_highlight!(
+            "c",
+            "/* a\n\"b",
+            Some(
+                r#"<span class="hl-comment">/* a
+"b</span>"#
+            ),
+        );
+        test_highlight!("js", "`a", Some(r#"<span class="hl-string">`a</span>"#),);
+    }
+}
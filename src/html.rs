@@ -1,8 +1,12 @@
 //! An HTML renderer that takes an iterator of [`Event`]s and emits HTML.
 
+use std::sync::Arc;
+
 use crate::Alignment;
 use crate::Container;
+use crate::CowStr;
 use crate::Event;
+use crate::Heading;
 use crate::LinkType;
 use crate::ListKind;
 use crate::Map;
@@ -10,6 +14,11 @@ use crate::OrderedListNumbering::*;
 use crate::Render;
 use crate::RenderRef;
 use crate::SpanLinkType;
+use crate::Toc;
+use crate::TocEntry;
+
+#[cfg(feature = "highlight")]
+pub use crate::highlight::Highlighter;
 
 /// Render events into a string.
 ///
@@ -143,6 +152,298 @@ impl Default for Indentation {
     }
 }
 
+/// Characters used for smart quotes in HTML output.
+///
+/// The fields are written as-is in place of the quote events, see [`Renderer::with_quotes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quotes {
+    /// Written for [`Event::LeftSingleQuote`].
+    pub left_single: String,
+    /// Written for [`Event::RightSingleQuote`] that closes a single quote.
+    pub right_single: String,
+    /// Written for [`Event::LeftDoubleQuote`].
+    pub left_double: String,
+    /// Written for [`Event::RightDoubleQuote`].
+    pub right_double: String,
+    /// Written for [`Event::RightSingleQuote`] that does not close a single quote, e.g. in
+    /// "don't".
+    pub apostrophe: String,
+}
+
+impl Quotes {
+    /// English quotes, ‘single’ and “double”.
+    ///
+    /// This is the default.
+    #[must_use]
+    pub fn english() -> Self {
+        Self::new("‘", "’", "“", "”")
+    }
+
+    /// German quotes, ‚single‘ and „double“.
+    #[must_use]
+    pub fn german() -> Self {
+        Self::new("‚", "‘", "„", "“")
+    }
+
+    /// French quotes, ‹ single › and « double », with non-breaking spaces inside the quotes.
+    #[must_use]
+    pub fn french() -> Self {
+        Self::new("‹\u{a0}", "\u{a0}›", "«\u{a0}", "\u{a0}»")
+    }
+
+    fn new(left_single: &str, right_single: &str, left_double: &str, right_double: &str) -> Self {
+        Self {
+            left_single: left_single.to_string(),
+            right_single: right_single.to_string(),
+            left_double: left_double.to_string(),
+            right_double: right_double.to_string(),
+            apostrophe: "’".to_string(),
+        }
+    }
+}
+
+impl Default for Quotes {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+/// How raw HTML is handled when sanitizing, see [`Sanitize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawHtml {
+    /// Omit raw HTML from the output.
+    Remove,
+    /// Write raw HTML as escaped text.
+    Escape,
+}
+
+impl Default for RawHtml {
+    fn default() -> Self {
+        Self::Remove
+    }
+}
+
+/// Options for sanitizing the output when rendering untrusted input.
+///
+/// When enabled with [`Renderer::with_sanitize`], raw HTML is removed or escaped, attributes that
+/// are not in the allowlist are omitted, and link and image destinations with a URL scheme that is
+/// not allowed are omitted.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// let src = "[a](javascript:x){.b onclick=c} `<b>`{=html}";
+/// let mut actual = String::new();
+/// let renderer = Renderer::default().with_sanitize(Sanitize::default());
+/// renderer.push(Parser::new(src), &mut actual).unwrap();
+/// assert_eq!(actual, "<p><a class=\"b\">a</a> </p>\n");
+/// ```
+///
+/// Raw HTML may be escaped instead of removed:
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// # let src = "[a](javascript:x){.b onclick=c} `<b>`{=html}";
+/// let mut actual = String::new();
+/// let renderer = Renderer::default().with_sanitize(Sanitize {
+///     raw_html: RawHtml::Escape,
+///     ..Sanitize::default()
+/// });
+/// renderer.push(Parser::new(src), &mut actual).unwrap();
+/// assert_eq!(actual, "<p><a class=\"b\">a</a> &lt;b&gt;</p>\n");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sanitize {
+    /// How to handle raw HTML blocks and inlines.
+    ///
+    /// Defaults to [`RawHtml::Remove`].
+    pub raw_html: RawHtml,
+    /// Names of the attributes that are written, all other attributes are omitted.
+    ///
+    /// Only applies to attributes specified in the input, attributes added by the renderer are
+    /// always written. Defaults to `id`, `class`, `title`, `lang` and `dir`.
+    pub attributes: Vec<String>,
+    /// URL schemes that are allowed in link and image destinations, compared case-insensitively.
+    ///
+    /// Destinations without a scheme, e.g. relative URLs, are always allowed. Defaults to `http`,
+    /// `https` and `mailto`.
+    pub url_schemes: Vec<String>,
+}
+
+impl Sanitize {
+    fn attribute_allowed(&self, name: &str) -> bool {
+        self.attributes.iter().any(|a| a == name)
+    }
+
+    fn url_allowed(&self, url: &str) -> bool {
+        // browsers ignore leading whitespace and control characters, and tabs or newlines within
+        // the url
+        let chars = url
+            .trim_start_matches(|c: char| c <= ' ')
+            .chars()
+            .filter(|c| !matches!(c, '\t' | '\n' | '\r'));
+        let mut scheme = String::new();
+        for c in chars {
+            match c {
+                ':' => {
+                    return self
+                        .url_schemes
+                        .iter()
+                        .any(|s| s.eq_ignore_ascii_case(&scheme))
+                }
+                '/' | '?' | '#' => return true,
+                _ => scheme.push(c),
+            }
+        }
+        true
+    }
+}
+
+impl Default for Sanitize {
+    fn default() -> Self {
+        Self {
+            raw_html: RawHtml::default(),
+            attributes: ["id", "class", "title", "lang", "dir"]
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            url_schemes: ["http", "https", "mailto"]
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// A syntax highlighter for code blocks, see [`Renderer::with_highlighter`].
+///
+/// The trait is implemented for closures that take the language and the content of a code block.
+/// With the `highlight` feature, a built-in implementation is provided by `Highlighter`.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// let src = "```upper\nabc\n```\n";
+/// let highlighter = |language: &str, code: &str| {
+///     if language == "upper" {
+///         Some(format!("<b>{}</b>", code.to_uppercase()))
+///     } else {
+///         None
+///     }
+/// };
+/// let mut actual = String::new();
+/// let renderer = Renderer::default().with_highlighter(highlighter);
+/// renderer.push(Parser::new(src), &mut actual).unwrap();
+/// assert_eq!(
+///     actual,
+///     "<pre><code class=\"language-upper\"><b>ABC\n</b></code></pre>\n",
+/// );
+/// ```
+pub trait CodeHighlighter {
+    /// Highlight the content of a code block, given its language which is empty if none was
+    /// specified.
+    ///
+    /// Returns the HTML to write inside the `<code>` element, or `None` to write the content as
+    /// escaped text, e.g. if the language is not supported. The returned HTML is written as-is.
+    fn highlight(&self, language: &str, code: &str) -> Option<String>;
+}
+
+impl<F> CodeHighlighter for F
+where
+    F: Fn(&str, &str) -> Option<String>,
+{
+    fn highlight(&self, language: &str, code: &str) -> Option<String> {
+        self(language, code)
+    }
+}
+
+/// Options for rendering a standalone HTML document, see [`Renderer::with_standalone`].
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// let src = "# Hello\n\nworld\n";
+/// let mut actual = String::new();
+/// let renderer = Renderer::default().with_standalone(Standalone {
+///     lang: Some("en".to_string()),
+///     stylesheets: vec!["style.css".to_string()],
+///     ..Standalone::default()
+/// });
+/// renderer.push(Parser::new(src), &mut actual).unwrap();
+/// assert_eq!(
+///     actual,
+///     concat!(
+///         "<!DOCTYPE html>\n",
+///         "<html lang=\"en\">\n",
+///         "<head>\n",
+///         "<meta charset=\"utf-8\">\n",
+///         "<title>Hello</title>\n",
+///         "<link rel=\"stylesheet\" href=\"style.css\">\n",
+///         "</head>\n",
+///         "<body>\n",
+///         "<section id=\"Hello\">\n",
+///         "<h1>Hello</h1>\n",
+///         "<p>world</p>\n",
+///         "</section>\n",
+///         "</body>\n",
+///         "</html>\n",
+///     ),
+/// );
+/// ```
+///
+/// Use a template with placeholders:
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// let src = "# Hello\n\nworld\n";
+/// let mut actual = String::new();
+/// let renderer = Renderer::minified().with_standalone(Standalone {
+///     title: Some("Greeting".to_string()),
+///     template: Some("<title>$title$</title>$toc$<main>$body$</main>".to_string()),
+///     ..Standalone::default()
+/// });
+/// renderer.push(Parser::new(src), &mut actual).unwrap();
+/// assert_eq!(
+///     actual,
+///     concat!(
+///         "<title>Greeting</title>",
+///         "<nav role=\"doc-toc\"><ol><li><a href=\"#Hello\">Hello</a></li></ol></nav>",
+///         "<main><section id=\"Hello\"><h1>Hello</h1><p>world</p></section></main>",
+///     ),
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Standalone {
+    /// Title of the document.
+    ///
+    /// If `None`, the text of the first heading is used.
+    pub title: Option<String>,
+    /// Language of the document, written as the `lang` attribute of the `<html>` element.
+    pub lang: Option<String>,
+    /// URLs of stylesheets that are linked from the `<head>` element.
+    pub stylesheets: Vec<String>,
+    /// Template for the document, used instead of the default document structure.
+    ///
+    /// The template is written as-is, except for the following placeholders:
+    ///
+    /// - `$body$`: the rendered document.
+    /// - `$title$`: the escaped title of the document.
+    /// - `$lang$`: the escaped language of the document.
+    /// - `$stylesheets$`: a `<link>` element for each stylesheet.
+    /// - `$toc$`: a table of contents with links to the headings of the document, in a `<nav>`
+    ///   element.
+    /// - `$$`: a single `$`.
+    pub template: Option<String>,
+}
+
 /// [`Render`] implementor that writes HTML output.
 ///
 /// By default, block elements are placed on separate lines. To configure the formatting of the
@@ -150,6 +451,12 @@ impl Default for Indentation {
 #[derive(Clone)]
 pub struct Renderer {
     indent: Option<Indentation>,
+    quotes: Quotes,
+    sanitize: Option<Sanitize>,
+    highlighter: Option<Arc<dyn CodeHighlighter + Send + Sync>>,
+    mathml: bool,
+    standalone: Option<Standalone>,
+    toc: bool,
 }
 
 impl Renderer {
@@ -176,7 +483,15 @@ impl Renderer {
     /// ```
     #[must_use]
     pub fn minified() -> Self {
-        Self { indent: None }
+        Self {
+            indent: None,
+            quotes: Quotes::default(),
+            sanitize: None,
+            highlighter: None,
+            mathml: false,
+            standalone: None,
+            toc: false,
+        }
     }
 
     /// Create a renderer that indents lines based on their block element depth.
@@ -219,8 +534,125 @@ impl Renderer {
     pub fn indented(indent: Indentation) -> Self {
         Self {
             indent: Some(indent),
+            quotes: Quotes::default(),
+            sanitize: None,
+            highlighter: None,
+            mathml: false,
+            standalone: None,
+            toc: false,
         }
     }
+
+    /// Use the given characters for smart quotes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "\"Don't\", sagte er.";
+    /// let mut actual = String::new();
+    /// let renderer = Renderer::default().with_quotes(Quotes::german());
+    /// renderer.push(Parser::new(src), &mut actual).unwrap();
+    /// assert_eq!(actual, "<p>„Don’t“, sagte er.</p>\n");
+    /// ```
+    #[must_use]
+    pub fn with_quotes(mut self, quotes: Quotes) -> Self {
+        self.quotes = quotes;
+        self
+    }
+
+    /// Sanitize the output, for rendering untrusted input.
+    ///
+    /// See [`Sanitize`] for the available options.
+    #[must_use]
+    pub fn with_sanitize(mut self, sanitize: Sanitize) -> Self {
+        self.sanitize = Some(sanitize);
+        self
+    }
+
+    /// Highlight the content of code blocks with the given highlighter.
+    ///
+    /// See [`CodeHighlighter`] for an example.
+    #[must_use]
+    pub fn with_highlighter<H>(mut self, highlighter: H) -> Self
+    where
+        H: CodeHighlighter + Send + Sync + 'static,
+    {
+        self.highlighter = Some(Arc::new(highlighter));
+        self
+    }
+
+    /// Write math as MathML instead of TeX for client-side rendering.
+    ///
+    /// The TeX is converted with [`crate::mathml`], display math is written with
+    /// `display="block"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "$`x^2`";
+    /// let mut actual = String::new();
+    /// let renderer = Renderer::minified().with_mathml();
+    /// renderer.push(Parser::new(src), &mut actual).unwrap();
+    /// assert_eq!(
+    ///     actual,
+    ///     concat!(
+    ///         r#"<p><math class="math inline"><semantics>"#,
+    ///         "<msup><mi>x</mi><mn>2</mn></msup>",
+    ///         r#"<annotation encoding="application/x-tex">x^2</annotation>"#,
+    ///         "</semantics></math></p>",
+    ///     ),
+    /// );
+    /// ```
+    #[cfg(feature = "mathml")]
+    #[must_use]
+    pub fn with_mathml(mut self) -> Self {
+        self.mathml = true;
+        self
+    }
+
+    /// Write a complete HTML document instead of a fragment.
+    ///
+    /// See [`Standalone`] for the available options.
+    #[must_use]
+    pub fn with_standalone(mut self, standalone: Standalone) -> Self {
+        self.standalone = Some(standalone);
+        self
+    }
+
+    /// Write a table of contents before the document, with links to its headings.
+    ///
+    /// The table of contents is a nested list of links in a `<nav>` element. For a standalone
+    /// document it is placed at the start of the `<body>` element, a template may instead place
+    /// it with the `$toc$` placeholder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "# a\n\n## b\n";
+    /// let mut actual = String::new();
+    /// let renderer = Renderer::minified().with_toc();
+    /// renderer.push(Parser::new(src), &mut actual).unwrap();
+    /// assert_eq!(
+    ///     actual,
+    ///     concat!(
+    ///         r#"<nav role="doc-toc"><ol>"#,
+    ///         r##"<li><a href="#a">a</a><ol><li><a href="#b">b</a></li></ol></li>"##,
+    ///         "</ol></nav>",
+    ///         r#"<section id="a"><h1>a</h1><section id="b"><h2>b</h2></section></section>"#,
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_toc(mut self) -> Self {
+        self.toc = true;
+        self
+    }
 }
 
 impl Default for Renderer {
@@ -266,6 +698,12 @@ impl Default for Renderer {
                 string: String::new(),
                 initial_level: 0,
             }),
+            quotes: Quotes::default(),
+            sanitize: None,
+            highlighter: None,
+            mathml: false,
+            standalone: None,
+            toc: false,
         }
     }
 }
@@ -276,9 +714,16 @@ impl Render for Renderer {
         I: Iterator<Item = Event<'s>>,
         W: std::fmt::Write,
     {
-        let mut w = Writer::new(&self.indent);
-        events.try_for_each(|e| w.render_event(&e, &mut out))?;
-        w.render_epilogue(&mut out)
+        let mut w = Writer::new(self);
+        if self.standalone.is_some() || self.toc {
+            let mut body = String::new();
+            events.try_for_each(|e| w.render_event(&e, &mut body))?;
+            w.render_epilogue(&mut body)?;
+            w.render_document(&body, out)
+        } else {
+            events.try_for_each(|e| w.render_event(&e, &mut out))?;
+            w.render_epilogue(&mut out)
+        }
     }
 }
 
@@ -289,9 +734,16 @@ impl RenderRef for Renderer {
         I: Iterator<Item = E>,
         W: std::fmt::Write,
     {
-        let mut w = Writer::new(&self.indent);
-        events.try_for_each(|e| w.render_event(e.as_ref(), &mut out))?;
-        w.render_epilogue(&mut out)
+        let mut w = Writer::new(self);
+        if self.standalone.is_some() || self.toc {
+            let mut body = String::new();
+            events.try_for_each(|e| w.render_event(e.as_ref(), &mut body))?;
+            w.render_epilogue(&mut body)?;
+            w.render_document(&body, out)
+        } else {
+            events.try_for_each(|e| w.render_event(e.as_ref(), &mut out))?;
+            w.render_epilogue(&mut out)
+        }
     }
 }
 
@@ -309,6 +761,14 @@ impl Default for Raw {
 
 struct Writer<'s, 'f> {
     indent: &'f Option<Indentation>,
+    quotes: &'f Quotes,
+    sanitize: Option<&'f Sanitize>,
+    highlighter: Option<&'f (dyn CodeHighlighter + Send + Sync)>,
+    mathml: bool,
+    /// Content of the current code block or math, buffered if a highlighter or MathML is used.
+    code: Option<String>,
+    /// Number of currently open single quotes.
+    single_quotes: usize,
     depth: usize,
     raw: Raw,
     img_alt_text: usize,
@@ -316,10 +776,18 @@ struct Writer<'s, 'f> {
     first_line: bool,
     ignore: bool,
     footnotes: Footnotes<'s>,
+    standalone: Option<&'f Standalone>,
+    toc: bool,
+    /// Headings collected for standalone documents and the table of contents, with the text as
+    /// rendered and without ranges.
+    headings: Option<Vec<Heading>>,
+    /// Whether currently within a heading whose text is collected.
+    in_heading: bool,
 }
 
 impl<'s, 'f> Writer<'s, 'f> {
-    fn new(indent: &'f Option<Indentation>) -> Self {
+    fn new(renderer: &'f Renderer) -> Self {
+        let indent = &renderer.indent;
         let depth = if let Some(indent) = indent {
             indent.initial_level
         } else {
@@ -327,6 +795,12 @@ impl<'s, 'f> Writer<'s, 'f> {
         };
         Self {
             indent,
+            quotes: &renderer.quotes,
+            sanitize: renderer.sanitize.as_ref(),
+            highlighter: renderer.highlighter.as_deref(),
+            mathml: renderer.mathml,
+            code: None,
+            single_quotes: 0,
             depth,
             raw: Raw::default(),
             img_alt_text: 0,
@@ -334,6 +808,14 @@ impl<'s, 'f> Writer<'s, 'f> {
             first_line: true,
             ignore: false,
             footnotes: Footnotes::default(),
+            standalone: renderer.standalone.as_ref(),
+            toc: renderer.toc,
+            headings: if renderer.standalone.is_some() || renderer.toc {
+                Some(Vec::new())
+            } else {
+                None
+            },
+            in_heading: false,
         }
     }
 
@@ -375,12 +857,38 @@ impl<'s, 'f> Writer<'s, 'f> {
         Ok(())
     }
 
+    fn attribute_allowed(&self, name: &str) -> bool {
+        self.sanitize.map_or(true, |s| s.attribute_allowed(name))
+    }
+
+    fn url_allowed(&self, url: &str) -> bool {
+        self.sanitize.map_or(true, |s| s.url_allowed(url))
+    }
+
+    /// The characters of a single quote event, where a right quote that does not close a left
+    /// quote is an apostrophe.
+    fn single_quote(&mut self, e: &Event) -> Option<&'f str> {
+        let quotes = self.quotes;
+        match e {
+            Event::LeftSingleQuote => {
+                self.single_quotes += 1;
+                Some(&quotes.left_single)
+            }
+            Event::RightSingleQuote if self.single_quotes > 0 => {
+                self.single_quotes -= 1;
+                Some(&quotes.right_single)
+            }
+            Event::RightSingleQuote => Some(&quotes.apostrophe),
+            _ => None,
+        }
+    }
+
     fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
     where
         W: std::fmt::Write,
     {
         if let Event::Start(Container::Footnote { label }, ..) = e {
-            self.footnotes.start(label, Vec::new());
+            self.footnotes.start(label.clone(), Vec::new());
             return Ok(());
         } else if let Some(events) = self.footnotes.current() {
             if matches!(e, Event::End(Container::Footnote { .. })) {
@@ -405,9 +913,51 @@ impl<'s, 'f> Writer<'s, 'f> {
             return Ok(());
         }
 
+        let single_quote = self.single_quote(e);
+
+        if let Some(headings) = &mut self.headings {
+            match e {
+                Event::Start(Container::Heading { level, id, .. }, attrs)
+                    if !self.footnotes.in_epilogue() =>
+                {
+                    let id = attrs
+                        .get_value("id")
+                        .map_or_else(|| id.to_string(), |v| v.to_string());
+                    headings.push(Heading {
+                        level: *level,
+                        text: String::new(),
+                        id,
+                        range: 0..0,
+                    });
+                    self.in_heading = true;
+                }
+                Event::End(Container::Heading { .. }) => self.in_heading = false,
+                _ if self.in_heading => {
+                    let text = &mut headings.last_mut().unwrap().text;
+                    match e {
+                        Event::Str(s) => text.push_str(s),
+                        Event::Softbreak => text.push(' '),
+                        Event::LeftSingleQuote | Event::RightSingleQuote => {
+                            text.push_str(single_quote.unwrap_or_default());
+                        }
+                        Event::LeftDoubleQuote => text.push_str(&self.quotes.left_double),
+                        Event::RightDoubleQuote => text.push_str(&self.quotes.right_double),
+                        Event::Ellipsis => text.push('…'),
+                        Event::EnDash => text.push('–'),
+                        Event::EmDash => text.push('—'),
+                        Event::NonBreakingSpace => text.push('\u{a0}'),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
         match e {
             Event::Start(c, attrs) => {
                 if c.is_block() {
+                    // quotes are only matched within a leaf block
+                    self.single_quotes = 0;
                     self.block(&mut out, c.is_block_container().into())?;
                 }
                 if self.img_alt_text > 0 && !matches!(c, Container::Image(..)) {
@@ -460,9 +1010,14 @@ impl<'s, 'f> Writer<'s, 'f> {
                     Container::Caption => out.write_str("<caption")?,
                     Container::DescriptionTerm => out.write_str("<dt")?,
                     Container::CodeBlock { .. } => out.write_str("<pre")?,
-                    Container::Span | Container::Math { .. } => out.write_str("<span")?,
+                    Container::Span => out.write_str("<span")?,
+                    Container::Math { .. } => {
+                        out.write_str(if self.mathml { "<math" } else { "<span" })?;
+                    }
                     Container::Link(dst, ty) => {
-                        if matches!(ty, LinkType::Span(SpanLinkType::Unresolved)) {
+                        if matches!(ty, LinkType::Span(SpanLinkType::Unresolved))
+                            || (!matches!(ty, LinkType::Email) && !self.url_allowed(dst))
+                        {
                             out.write_str("<a")?;
                         } else {
                             out.write_str(r#"<a href=""#)?;
@@ -483,10 +1038,11 @@ impl<'s, 'f> Writer<'s, 'f> {
                     }
                     Container::Verbatim => out.write_str("<code")?,
                     Container::RawBlock { format } | Container::RawInline { format } => {
-                        self.raw = if format == &"html" {
-                            Raw::Html
-                        } else {
-                            Raw::Other
+                        self.raw = match self.sanitize.map(|s| s.raw_html) {
+                            _ if format != "html" => Raw::Other,
+                            None => Raw::Html,
+                            Some(RawHtml::Remove) => Raw::Other,
+                            Some(RawHtml::Escape) => Raw::None,
                         };
                         return Ok(());
                     }
@@ -503,6 +1059,9 @@ impl<'s, 'f> Writer<'s, 'f> {
                 let mut id_written = false;
                 let mut class_written = false;
                 for (a, v) in attrs.unique_pairs() {
+                    if !self.attribute_allowed(a) {
+                        continue;
+                    }
                     write!(out, r#" {}=""#, a)?;
                     v.parts().try_for_each(|part| write_attr(part, &mut out))?;
                     match a {
@@ -564,6 +1123,9 @@ impl<'s, 'f> Writer<'s, 'f> {
                             write_attr(language, &mut out)?;
                             out.write_str(r#"">"#)?;
                         }
+                        if self.highlighter.is_some() {
+                            self.code = Some(String::new());
+                        }
                     }
                     Container::Image(..) => {
                         if self.img_alt_text == 1 {
@@ -571,7 +1133,16 @@ impl<'s, 'f> Writer<'s, 'f> {
                         }
                     }
                     Container::Math { display } => {
-                        out.write_str(if *display { r#">\["# } else { r#">\("# })?;
+                        if self.mathml {
+                            out.write_str(if *display {
+                                r#" display="block">"#
+                            } else {
+                                ">"
+                            })?;
+                            self.code = Some(String::new());
+                        } else {
+                            out.write_str(if *display { r#">\["# } else { r#">\("# })?;
+                        }
                     }
                     Container::TaskListItem { checked } => {
                         out.write_char('>')?;
@@ -626,12 +1197,23 @@ impl<'s, 'f> Writer<'s, 'f> {
                     Container::TableCell { head: true, .. } => out.write_str("</th>")?,
                     Container::Caption => out.write_str("</caption>")?,
                     Container::DescriptionTerm => out.write_str("</dt>")?,
-                    Container::CodeBlock { .. } => out.write_str("</code></pre>")?,
+                    Container::CodeBlock { language } => {
+                        if let (Some(code), Some(highlighter)) =
+                            (self.code.take(), self.highlighter)
+                        {
+                            if let Some(html) = highlighter.highlight(language, &code) {
+                                out.write_str(&html)?;
+                            } else {
+                                write_text(&code, &mut out)?;
+                            }
+                        }
+                        out.write_str("</code></pre>")?;
+                    }
                     Container::Span => out.write_str("</span>")?,
                     Container::Link(..) => out.write_str("</a>")?,
                     Container::Image(src, ..) => {
                         if self.img_alt_text == 1 {
-                            if !src.is_empty() {
+                            if !src.is_empty() && self.url_allowed(src) {
                                 out.write_str(r#"" src=""#)?;
                                 write_attr(src, &mut out)?;
                             }
@@ -640,6 +1222,10 @@ impl<'s, 'f> Writer<'s, 'f> {
                         self.img_alt_text -= 1;
                     }
                     Container::Verbatim => out.write_str("</code>")?,
+                    Container::Math { .. } if self.mathml => {
+                        write_mathml(&self.code.take().unwrap_or_default(), &mut out)?;
+                        out.write_str("</math>")?;
+                    }
                     Container::Math { display } => {
                         out.write_str(if *display {
                             r#"\]</span>"#
@@ -661,13 +1247,14 @@ impl<'s, 'f> Writer<'s, 'f> {
                 }
             }
             Event::Str(s) => match self.raw {
+                Raw::None if self.code.is_some() => self.code.as_mut().unwrap().push_str(s),
                 Raw::None if self.img_alt_text > 0 => write_attr(s, &mut out)?,
                 Raw::None => write_text(s, &mut out)?,
                 Raw::Html => out.write_str(s)?,
                 Raw::Other => {}
             },
             Event::FootnoteReference(label) => {
-                let number = self.footnotes.reference(label);
+                let number = self.footnotes.reference(label.clone());
                 if self.img_alt_text == 0 {
                     write!(
                         out,
@@ -677,10 +1264,11 @@ impl<'s, 'f> Writer<'s, 'f> {
                 }
             }
             Event::Symbol(sym) => write!(out, ":{}:", sym)?,
-            Event::LeftSingleQuote => out.write_str("‘")?,
-            Event::RightSingleQuote => out.write_str("’")?,
-            Event::LeftDoubleQuote => out.write_str("“")?,
-            Event::RightDoubleQuote => out.write_str("”")?,
+            Event::LeftSingleQuote | Event::RightSingleQuote => {
+                out.write_str(single_quote.unwrap_or_default())?;
+            }
+            Event::LeftDoubleQuote => out.write_str(&self.quotes.left_double)?,
+            Event::RightDoubleQuote => out.write_str(&self.quotes.right_double)?,
             Event::Ellipsis => out.write_str("…")?,
             Event::EnDash => out.write_str("–")?,
             Event::EmDash => out.write_str("—")?,
@@ -698,6 +1286,9 @@ impl<'s, 'f> Writer<'s, 'f> {
                 self.block(&mut out, 0)?;
                 out.write_str("<hr")?;
                 for (a, v) in attrs.unique_pairs() {
+                    if !self.attribute_allowed(a) {
+                        continue;
+                    }
                     write!(out, r#" {}=""#, a)?;
                     v.parts().try_for_each(|part| write_attr(part, &mut out))?;
                     out.write_char('"')?;
@@ -766,6 +1357,118 @@ impl<'s, 'f> Writer<'s, 'f> {
 
         Ok(())
     }
+
+    fn render_document<W>(&self, body: &str, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let headings = self.headings.as_deref().unwrap_or_default();
+        let toc = Toc::new(headings.iter().cloned());
+        let standalone = if let Some(standalone) = self.standalone {
+            standalone
+        } else {
+            write_toc(&toc, &mut out)?;
+            if self.indent.is_some() && !headings.is_empty() {
+                out.write_char('\n')?;
+            }
+            return out.write_str(body);
+        };
+        let title = standalone
+            .title
+            .as_deref()
+            .or_else(|| headings.first().map(|h| h.text.as_str()))
+            .unwrap_or_default();
+
+        let template = if let Some(template) = &standalone.template {
+            template
+        } else {
+            out.write_str("<!DOCTYPE html>\n<html")?;
+            if let Some(lang) = &standalone.lang {
+                out.write_str(r#" lang=""#)?;
+                write_attr(lang, &mut out)?;
+                out.write_char('"')?;
+            }
+            out.write_str(">\n<head>\n<meta charset=\"utf-8\">\n<title>")?;
+            write_text(title, &mut out)?;
+            out.write_str("</title>\n")?;
+            write_stylesheets(&standalone.stylesheets, &mut out)?;
+            out.write_str("</head>\n<body>\n")?;
+            if self.toc && !headings.is_empty() {
+                write_toc(&toc, &mut out)?;
+                out.write_char('\n')?;
+            }
+            out.write_str(body)?;
+            if !body.ends_with('\n') {
+                out.write_char('\n')?;
+            }
+            return out.write_str("</body>\n</html>\n");
+        };
+
+        let mut rest = template.as_str();
+        while let Some(i) = rest.find('$') {
+            out.write_str(&rest[..i])?;
+            rest = &rest[i + 1..];
+            let name = rest.find('$').map_or("", |j| &rest[..j]);
+            match name {
+                "" if rest.starts_with('$') => out.write_char('$')?,
+                "body" => out.write_str(body)?,
+                "title" => write_text(title, &mut out)?,
+                "lang" => write_text(standalone.lang.as_deref().unwrap_or_default(), &mut out)?,
+                "stylesheets" => write_stylesheets(&standalone.stylesheets, &mut out)?,
+                "toc" => write_toc(&toc, &mut out)?,
+                _ => {
+                    out.write_char('$')?;
+                    continue;
+                }
+            }
+            rest = &rest[name.len() + 1..];
+        }
+        out.write_str(rest)
+    }
+}
+
+fn write_stylesheets<W>(stylesheets: &[String], mut out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    for href in stylesheets {
+        out.write_str(r#"<link rel="stylesheet" href=""#)?;
+        write_attr(href, &mut out)?;
+        out.write_str("\">\n")?;
+    }
+    Ok(())
+}
+
+/// Write a table of contents as nested lists of links to the headings.
+fn write_toc<W>(toc: &Toc, mut out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    if toc.entries.is_empty() {
+        return Ok(());
+    }
+    out.write_str(r#"<nav role="doc-toc">"#)?;
+    write_toc_entries(&toc.entries, &mut out)?;
+    out.write_str("</nav>")
+}
+
+fn write_toc_entries<W>(entries: &[TocEntry], out: &mut W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    out.write_str("<ol>")?;
+    for entry in entries {
+        out.write_str(r##"<li><a href="#"##)?;
+        write_attr(&entry.heading.id, &mut *out)?;
+        out.write_str(r#"">"#)?;
+        write_text(&entry.heading.text, &mut *out)?;
+        out.write_str("</a>")?;
+        if !entry.children.is_empty() {
+            write_toc_entries(&entry.children, out)?;
+        }
+        out.write_str("</li>")?;
+    }
+    out.write_str("</ol>")
 }
 
 fn write_class<W>(c: &Container, mut first_written: bool, out: &mut W) -> std::fmt::Result
@@ -795,7 +1498,23 @@ where
     Ok(())
 }
 
-fn write_text<W>(s: &str, out: W) -> std::fmt::Result
+#[cfg(feature = "mathml")]
+fn write_mathml<W>(tex: &str, out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    crate::mathml::write_content(tex, out)
+}
+
+#[cfg(not(feature = "mathml"))]
+fn write_mathml<W>(tex: &str, out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    write_text(tex, out)
+}
+
+pub(crate) fn write_text<W>(s: &str, out: W) -> std::fmt::Result
 where
     W: std::fmt::Write,
 {
@@ -842,11 +1561,11 @@ where
 #[derive(Default)]
 struct Footnotes<'s> {
     /// Stack of current open footnotes, with label and staging buffer.
-    open: Vec<(&'s str, Vec<Event<'s>>)>,
+    open: Vec<(CowStr<'s>, Vec<Event<'s>>)>,
     /// Footnote references in the order they were first encountered.
-    references: Vec<&'s str>,
+    references: Vec<CowStr<'s>>,
     /// Events for each footnote.
-    events: Map<&'s str, Vec<Event<'s>>>,
+    events: Map<CowStr<'s>, Vec<Event<'s>>>,
     /// Number of last footnote that was emitted.
     number: usize,
 }
@@ -863,7 +1582,7 @@ impl<'s> Footnotes<'s> {
     }
 
     /// Add a footnote reference.
-    fn reference(&mut self, label: &'s str) -> usize {
+    fn reference(&mut self, label: CowStr<'s>) -> usize {
         self.references
             .iter()
             .position(|t| *t == label)
@@ -877,7 +1596,7 @@ impl<'s> Footnotes<'s> {
     }
 
     /// Start aggregating a footnote.
-    fn start(&mut self, label: &'s str, events: Vec<Event<'s>>) {
+    fn start(&mut self, label: CowStr<'s>, events: Vec<Event<'s>>) {
         self.open.push((label, events));
     }
 
@@ -907,6 +1626,9 @@ impl<'s> Iterator for Footnotes<'s> {
 #[cfg(test)]
 mod test {
     use super::Indentation;
+    use super::Quotes;
+    use super::Sanitize;
+    use super::Standalone;
     use crate::Render;
 
     macro_rules! test_html {
@@ -914,7 +1636,15 @@ mod test {
             #[allow(unused)]
             let mut indent = None;
             $(indent = Some($indent);)?
-            let renderer = super::Renderer { indent };
+            let renderer = super::Renderer {
+                indent,
+                quotes: Quotes::default(),
+                sanitize: None,
+                highlighter: None,
+                mathml: false,
+                standalone: None,
+                toc: false,
+            };
             let mut actual = String::new();
             renderer
                 .push(crate::Parser::new($src), &mut actual)
@@ -1019,4 +1749,157 @@ mod test {
             Indentation::default(),
         );
     }
+
+    #[test]
+    fn quotes() {
+        let render = |src, quotes| {
+            let mut actual = String::new();
+            super::Renderer::minified()
+                .with_quotes(quotes)
+                .push(crate::Parser::new(src), &mut actual)
+                .unwrap();
+            actual
+        };
+        let src = "'a \"b\" c', it's";
+        assert_eq!(render(src, Quotes::default()), "<p>‘a “b” c’, it’s</p>");
+        assert_eq!(render(src, Quotes::german()), "<p>‚a „b“ c‘, it’s</p>");
+        assert_eq!(
+            render(src, Quotes::french()),
+            "<p>‹\u{a0}a «\u{a0}b\u{a0}» c\u{a0}›, it’s</p>",
+        );
+        // unclosed quotes do not affect the following blocks
+        assert_eq!(
+            render("a{'b\n\ndon't", Quotes::german()),
+            "<p>a‚b</p><p>don’t</p>",
+        );
+        // the table of contents uses the same quotes as the heading
+        let mut actual = String::new();
+        super::Renderer::minified()
+            .with_quotes(Quotes::german())
+            .with_toc()
+            .push(crate::Parser::new("# 'a' b's"), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r##"<nav role="doc-toc"><ol><li><a href="#a-bs">‚a‘ b’s</a></li></ol></nav>"##,
+                r#"<section id="a-bs"><h1>‚a‘ b’s</h1></section>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn sanitize() {
+        let src = concat!(
+            "[a](JavaScript:x) [b](\tjava\nscript:x) [c](https://x) [d](/x:y)\n", //
+            "![e](data:x) ![f](x.png) <g@h> [i](#j){title=k style=l}\n",          //
+            "\n",                                                                 //
+            "{onclick=x}\n",                                                      //
+            "***\n",                                                              //
+            "\n",                                                                 //
+            "```=html\n",                                                         //
+            "<script></script>\n",                                                //
+            "```\n",
+        );
+        let mut actual = String::new();
+        super::Renderer::minified()
+            .with_sanitize(Sanitize::default())
+            .push(crate::Parser::new(src), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                "<p><a>a</a> <a>b</a> <a href=\"https://x\">c</a> <a href=\"/x:y\">d</a>\n",
+                "<img alt=\"e\"> <img alt=\"f\" src=\"x.png\"> ",
+                "<a href=\"mailto:g@h\">g@h</a> <a href=\"#j\" title=\"k\">i</a></p>",
+                "<hr>",
+            ),
+        );
+    }
+
+    #[cfg(feature = "mathml")]
+    #[test]
+    fn mathml() {
+        let mut actual = String::new();
+        super::Renderer::minified()
+            .with_mathml()
+            .push(crate::Parser::new("$$`x`{#a}"), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p><math id="a" class="math display" display="block"><semantics><mi>x</mi>"#,
+                r#"<annotation encoding="application/x-tex">x</annotation></semantics></math></p>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn toc_nesting() {
+        let src = "## a\n\n# b\n\n### c\n\n## d\n";
+        let mut actual = String::new();
+        super::Renderer::minified()
+            .with_toc()
+            .push(crate::Parser::new(src), &mut actual)
+            .unwrap();
+        let toc = actual.split("</nav>").next().unwrap();
+        assert_eq!(
+            toc,
+            concat!(
+                r#"<nav role="doc-toc"><ol>"#,
+                r##"<li><a href="#a">a</a></li>"##,
+                r##"<li><a href="#b">b</a><ol>"##,
+                r##"<li><a href="#c">c</a></li>"##,
+                r##"<li><a href="#d">d</a></li>"##,
+                "</ol></li></ol>",
+            ),
+        );
+        let tree: Vec<_> = crate::Parser::new(src)
+            .toc()
+            .entries
+            .iter()
+            .map(|e| (e.heading.id.clone(), e.children.len()))
+            .collect();
+        assert_eq!(tree, [("a".to_string(), 0), ("b".to_string(), 2)]);
+    }
+
+    #[test]
+    fn standalone_template() {
+        let src = concat!(
+            "# a\n",      //
+            "\n",         //
+            "## b `c`\n", //
+            "\n",         //
+            "{#e}\n",     //
+            "### d\n",    //
+            "\n",         //
+            "# \"f\" < g\n",
+        );
+        let mut actual = String::new();
+        super::Renderer::minified()
+            .with_standalone(Standalone {
+                lang: Some("sv".to_string()),
+                stylesheets: vec!["a.css".to_string(), "b.css".to_string()],
+                template: Some("$lang$ $title$ $$ $x $stylesheets$$toc$".to_string()),
+                ..Standalone::default()
+            })
+            .push(crate::Parser::new(src), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                "sv a $ $x ",
+                "<link rel=\"stylesheet\" href=\"a.css\">\n",
+                "<link rel=\"stylesheet\" href=\"b.css\">\n",
+                "<nav role=\"doc-toc\"><ol>",
+                "<li><a href=\"#a\">a</a><ol>",
+                "<li><a href=\"#b-c\">b c</a><ol>",
+                "<li><a href=\"#e\">d</a></li>",
+                "</ol></li>",
+                "</ol></li>",
+                "<li><a href=\"#f-g\">“f” &lt; g</a></li>",
+                "</ol></nav>",
+            ),
+        );
+    }
 }
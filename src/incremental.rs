@@ -0,0 +1,669 @@
+use std::ops::Range;
+
+use crate::block;
+use crate::diagnostic::Diagnostics;
+use crate::stream::block_starts;
+use crate::stream::Definitions;
+use crate::stream::Sections;
+use crate::Container;
+use crate::DefaultHeadingIds;
+use crate::Diagnostic;
+use crate::Event;
+use crate::HeadingIdGenerator;
+use crate::LinkResolver;
+use crate::Parser;
+use crate::ParserOptions;
+use crate::Set;
+use crate::Syntax;
+
+/// A parser that keeps its input and events, and updates them after edits of the input, e.g. for
+/// a live preview in an editor.
+///
+/// The events are stored per top-level block, together with their byte ranges in the input as by
+/// [`Parser::into_offset_iter`]. After an edit, only the top-level blocks around the edit are
+/// parsed again, until the following blocks start at the same positions as before. The events
+/// of the other blocks are kept, with their ranges shifted.
+///
+/// Link definitions and headings affect references and heading ids throughout the input, so an
+/// edit that adds, changes or removes a link definition or heading causes the whole input to be
+/// parsed again.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// let mut parser = IncrementalParser::new("a\n\nb\n\nc\n".to_string());
+/// let change = parser.edit(3..4, "*x*");
+/// assert_eq!(parser.src(), "a\n\n*x*\n\nc\n");
+/// // `b` was replaced
+/// assert_eq!(change.old, 5..6);
+/// assert_eq!(
+///     &parser.events()[change.new],
+///     [
+///         (Event::Start(Container::Strong, Attributes::new()), 3..4),
+///         (Event::Str("x".into()), 4..5),
+///         (Event::End(Container::Strong), 5..6),
+///     ],
+/// );
+/// ```
+pub struct IncrementalParser<G = DefaultHeadingIds> {
+    src: String,
+    syntax: Syntax,
+    heading_ids: G,
+    /// Link definitions and heading ids of the whole input.
+    definitions: Definitions,
+    events: Vec<(Event<'static>, Range<usize>)>,
+    blocks: Vec<Block>,
+    /// Index of the events after the last block, that close sections at the end of the input.
+    tail: usize,
+}
+
+/// A top-level block of the input.
+struct Block {
+    /// Start of the block in the input.
+    start: usize,
+    /// Index of the first event of the block.
+    event: usize,
+    /// Labels of the link definitions used by references in the block.
+    used_definitions: Vec<String>,
+}
+
+/// Events that were replaced by an edit, see [`IncrementalParser::edit`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedEvents {
+    /// Indices of the replaced events, in the events before the edit.
+    pub old: Range<usize>,
+    /// Indices of the replacing events, in the events after the edit.
+    pub new: Range<usize>,
+}
+
+impl IncrementalParser {
+    /// Parse the input with the default options.
+    #[must_use]
+    pub fn new(src: String) -> Self {
+        Self::with_options(src, ParserOptions::default())
+    }
+}
+
+impl<G: HeadingIdGenerator> IncrementalParser<G> {
+    /// Parse the input with the given options.
+    #[must_use]
+    pub fn with_options(src: String, options: ParserOptions<G>) -> Self {
+        let mut p = Self {
+            src,
+            syntax: options.syntax,
+            heading_ids: options.heading_ids,
+            definitions: Definitions::default(),
+            events: Vec::new(),
+            blocks: Vec::new(),
+            tail: 0,
+        };
+        p.parse();
+        p
+    }
+
+    /// The current input.
+    #[must_use]
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    /// The events of the current input and their byte ranges in the input.
+    ///
+    /// The events are equal to the events of [`Parser::into_offset_iter`] for the current input,
+    /// except that references to duplicate link definitions or headings may resolve differently.
+    #[must_use]
+    pub fn events(&self) -> &[(Event<'static>, Range<usize>)] {
+        &self.events
+    }
+
+    /// Find problems in the current input, as by [`Parser::diagnostics`].
+    ///
+    /// The diagnostics are obtained from the stored events, so this does not require parsing the
+    /// input again.
+    #[must_use]
+    pub fn diagnostics(&self) -> Vec<Diagnostic<'static>> {
+        let mut diagnostics = Diagnostics::default();
+        for (e, r) in &self.events {
+            diagnostics.event(e, r.clone());
+        }
+        let used_definitions = self
+            .blocks
+            .iter()
+            .flat_map(|b| &b.used_definitions)
+            .map(String::as_str)
+            .collect::<Set<_>>();
+        diagnostics.finish(&used_definitions)
+    }
+
+    /// Replace the given byte range of the input with new text and update the events.
+    ///
+    /// Returns the range of events that were replaced. Events before the range are unchanged.
+    /// Events after the range are unchanged, except that their ranges are shifted by the change
+    /// in length of the input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or does not lie on character boundaries.
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> ChangedEvents {
+        self.src.replace_range(range.clone(), text);
+        let shift = |pos: usize| pos - range.end + range.start + text.len();
+        // events after the edit are unchanged if they are equal apart from the shift
+        let shifted = |r: &Range<usize>| {
+            if r.start >= range.end {
+                Some(shift(r.start)..shift(r.end))
+            } else {
+                None
+            }
+        };
+        let syntax = Syntax {
+            sections: false,
+            ..self.syntax
+        };
+
+        // the edited text may also continue the block before it, e.g. a list
+        let first = self
+            .blocks
+            .partition_point(|b| b.start <= range.start)
+            .saturating_sub(2);
+        let start = self.blocks[first].start;
+
+        // parse until a block starts at the same position as before the edit, the following
+        // blocks are then parsed the same as before
+        let mut last = self.blocks.partition_point(|b| b.start <= range.end);
+        let end = loop {
+            if let Some(b) = self.blocks.get(last) {
+                let next = shift(b.start);
+                let after = self
+                    .blocks
+                    .get(last + 1)
+                    .map_or(self.src.len(), |b| shift(b.start));
+                if block_starts(&self.src[start..after], syntax).contains(&(next - start)) {
+                    break next;
+                }
+                last += 1;
+            } else {
+                break self.src.len();
+            }
+        };
+
+        let old = self.blocks[first].event..self.blocks.get(last).map_or(self.tail, |b| b.event);
+
+        // headings keep their previous ids, which are only valid if the headings are unchanged
+        let mut ids = self.events[old.clone()]
+            .iter()
+            .filter_map(|(e, _)| match e {
+                Event::Start(Container::Heading { id, .. }, _) => Some(id.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .into_iter();
+        let mut sections = Sections::new(
+            self.syntax.sections,
+            open_sections(&self.events[..old.start]),
+        );
+        let mut events = Vec::new();
+        let mut blocks = Vec::new();
+        let starts = block_starts(&self.src[start..end], syntax);
+        for (s, e) in std::iter::once(0)
+            .chain(starts.iter().copied())
+            .zip(starts.iter().copied().chain(std::iter::once(end - start)))
+        {
+            let event = old.start + events.len();
+            let (block, used_definitions) = self.parse_block(start + s..start + e, &mut ids);
+            for (e, r) in block {
+                sections.push(e, r, |e, r| events.push((e, r)));
+            }
+            blocks.push(Block {
+                start: start + s,
+                event,
+                used_definitions,
+            });
+        }
+        sections.flush(|e, r| events.push((e, r)));
+
+        // link definitions and headings may affect the events of other blocks
+        if !definitions(&self.events[old.clone()]).eq(definitions(&events)) {
+            return self.parse_all(shifted);
+        }
+
+        let new = old.start..old.start + events.len();
+        let change = changed(&self.events[old.clone()], &events, shifted, old.start);
+        self.events.splice(old.clone(), events);
+        for (_, r) in &mut self.events[new.end..] {
+            *r = shift(r.start)..shift(r.end);
+        }
+        let n = blocks.len();
+        self.blocks.splice(first..last, blocks);
+        for b in &mut self.blocks[first + n..] {
+            b.start = shift(b.start);
+            b.event = b.event - old.end + new.end;
+        }
+        self.tail = self.tail - old.end + new.end;
+
+        change
+    }
+
+    /// Parse the whole input again, replacing all events.
+    fn parse_all<F>(&mut self, shifted: F) -> ChangedEvents
+    where
+        F: Fn(&Range<usize>) -> Option<Range<usize>>,
+    {
+        let old = std::mem::take(&mut self.events);
+        self.parse();
+        changed(&old, &self.events, shifted, 0)
+    }
+
+    /// Parse the whole input.
+    fn parse(&mut self) {
+        // sections are added afterwards, as each block is parsed separately
+        let syntax = Syntax {
+            sections: false,
+            ..self.syntax
+        };
+
+        let heading_ids = &mut self.heading_ids;
+        let options = ParserOptions {
+            syntax,
+            heading_ids: |text: &str| heading_ids.heading_id(text),
+        };
+        let parser = Parser::with_options(&self.src, options);
+        let pre_pass = &parser.pre_pass;
+        let mut definitions = Definitions::default();
+        for (label, (url, attrs)) in &pre_pass.link_definitions {
+            definitions.links.insert(
+                label.to_string(),
+                (url.to_string(), attrs.clone().into_owned()),
+            );
+        }
+        for h in &pre_pass.headings {
+            if let Some(id) = pre_pass.heading_id_by_tag(&h.text) {
+                definitions.headings.insert(h.text.clone(), id.to_string());
+            }
+        }
+        let mut ids = pre_pass
+            .headings
+            .iter()
+            .map(|h| h.id_auto.clone())
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.definitions = definitions;
+
+        let starts = block_starts(&self.src, syntax);
+        let mut sections = Sections::new(self.syntax.sections, Vec::new());
+        let mut events = Vec::new();
+        let mut blocks = Vec::new();
+        for (start, end) in std::iter::once(0).chain(starts.iter().copied()).zip(
+            starts
+                .iter()
+                .copied()
+                .chain(std::iter::once(self.src.len())),
+        ) {
+            let event = events.len();
+            let (block, used_definitions) = self.parse_block(start..end, &mut ids);
+            for (e, r) in block {
+                sections.push(e, r, |e, r| events.push((e, r)));
+            }
+            blocks.push(Block {
+                start,
+                event,
+                used_definitions,
+            });
+        }
+        // attributes at the end of the input belong to the last block
+        sections.flush(|e, r| events.push((e, r)));
+        self.tail = events.len();
+        sections.close(self.src.len(), |e, r| events.push((e, r)));
+        self.events = events;
+        self.blocks = blocks;
+    }
+
+    /// Parse a single top-level block, with the generated ids of its headings. The labels of the
+    /// link definitions used by its references are returned together with the events.
+    fn parse_block(
+        &self,
+        range: Range<usize>,
+        ids: &mut dyn Iterator<Item = String>,
+    ) -> (Vec<(Event<'static>, Range<usize>)>, Vec<String>) {
+        let syntax = Syntax {
+            sections: false,
+            ..self.syntax
+        };
+        let options = ParserOptions {
+            syntax,
+            heading_ids: |_: &str| ids.next().unwrap_or_default(),
+        };
+        let mut parser = Parser::with_options(&self.src[range.clone()], options)
+            .with_link_resolver(|label: &str| self.definitions.resolve(label))
+            .with_references();
+        let mut events = Vec::new();
+        let mut used_definitions = Vec::new();
+        while let Some((e, r)) = parser.next_span() {
+            events.push((e.into_owned(), range.start + r.start..range.start + r.end));
+            used_definitions.extend(
+                parser
+                    .take_references()
+                    .into_iter()
+                    .map(|r| r.tag.to_string())
+                    .filter(|tag| self.definitions.links.contains_key(tag)),
+            );
+        }
+        // a top-level list is closed at the start of the following block, which may be after
+        // its indentation, rather than at the end of the line before it
+        if let Some((Event::End(c), r)) = events.last_mut() {
+            if matches!(c, Container::List { .. } | Container::DescriptionList)
+                && r.start == range.end
+            {
+                let next = &self.src[range.end..];
+                let line = &next[..next.find('\n').map_or(next.len(), |i| i + 1)];
+                let pos = range.end
+                    + block::parse(line, syntax)
+                        .first()
+                        .map_or(0, |e| e.span.start);
+                *r = pos..pos;
+            }
+        }
+        (events, used_definitions)
+    }
+}
+
+/// Determine the events that differ between the old and new events at index `offset`, where
+/// `shifted` gives the new range of an old range after the edit.
+fn changed<F>(
+    old: &[(Event<'static>, Range<usize>)],
+    new: &[(Event<'static>, Range<usize>)],
+    shifted: F,
+    offset: usize,
+) -> ChangedEvents
+where
+    F: Fn(&Range<usize>) -> Option<Range<usize>>,
+{
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|((a, ra), (b, rb))| a == b && shifted(ra).as_ref() == Some(rb))
+        .count();
+    ChangedEvents {
+        old: offset + prefix..offset + old.len() - suffix,
+        new: offset + prefix..offset + new.len() - suffix,
+    }
+}
+
+/// Find the levels and ids of the sections that are open after the events.
+fn open_sections(events: &[(Event<'static>, Range<usize>)]) -> Vec<(u16, String)> {
+    let mut open = Vec::new();
+    for (e, _) in events {
+        match e {
+            Event::Start(
+                Container::Heading {
+                    level,
+                    has_section: true,
+                    id,
+                },
+                _,
+            ) => open.push((*level, id.to_string())),
+            Event::End(Container::Section { .. }) => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    open
+}
+
+/// The events of link definitions, headings and sections, which may affect the events of other
+/// blocks.
+fn definitions<'a>(
+    events: &'a [(Event<'static>, Range<usize>)],
+) -> impl Iterator<Item = &'a Event<'static>> + 'a {
+    let mut depth = 0;
+    events.iter().map(|(e, _)| e).filter(move |e| match e {
+        Event::Start(Container::LinkDefinition { .. } | Container::Heading { .. }, _) => {
+            depth += 1;
+            true
+        }
+        Event::End(Container::LinkDefinition { .. } | Container::Heading { .. }) => {
+            depth -= 1;
+            true
+        }
+        Event::Start(Container::Section { .. }, _) | Event::End(Container::Section { .. }) => true,
+        _ => depth > 0,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::IncrementalParser;
+    use crate::Event;
+    use crate::Parser;
+
+    fn events(src: &str) -> Vec<(Event<'static>, std::ops::Range<usize>)> {
+        Parser::new(src)
+            .into_offset_iter()
+            .map(|(e, r)| (e.into_owned(), r))
+            .collect()
+    }
+
+    /// Apply an edit and compare the events and diagnostics with those of a new parser.
+    fn edit(p: &mut IncrementalParser, range: std::ops::Range<usize>, text: &str) {
+        let old = p.events().to_vec();
+        let change = p.edit(range, text);
+        assert_eq!(p.events(), events(p.src()), "\n\n{}\n\n", p.src());
+        assert_eq!(
+            p.diagnostics(),
+            Parser::new(p.src()).diagnostics(),
+            "\n\n{}\n\n",
+            p.src(),
+        );
+        let new = p.events();
+        assert_eq!(old[..change.old.start], new[..change.new.start]);
+        assert_eq!(
+            old[change.old.end..]
+                .iter()
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>(),
+            new[change.new.end..]
+                .iter()
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    /// Apply edits and compare the events with the events of a new parser after each edit.
+    macro_rules! test_edits {
+        ($src:expr, $($range:expr => $text:expr),* $(,)?) => {
+            #[allow(unused)]
+            let mut p = IncrementalParser::new($src.to_string());
+            assert_eq!(p.events(), events($src), "\n\n{}\n\n", $src);
+            $(edit(&mut p, $range, $text);)*
+        };
+    }
+
+    #[test]
+    fn paragraphs() {
+        test_edits!(
+            "a\n\nb\n\nc\n",
+            3..4 => "_x_ y",
+            0..0 => "z ",
+            // join paragraphs
+            3..5 => "",
+            // split paragraph
+            5..6 => "\n\n",
+        );
+    }
+
+    #[test]
+    fn containers() {
+        test_edits!(
+            concat!(
+                "- a\n", //
+                "\n",    //
+                "b\n",   //
+                "\n",    //
+                "> c\n", //
+                "d\n",   //
+                "\n",    //
+                "e\n",   //
+            ),
+            // continue list item
+            5..5 => "  ",
+            // continue list
+            5..7 => "- ",
+            // end blockquote
+            14..14 => "\n",
+            // open div around the rest
+            0..0 => "::: x\n",
+            // close div
+            26..26 => ":::\n",
+        );
+    }
+
+    #[test]
+    fn verbatim() {
+        test_edits!(
+            "a\n\n```\n\nb\n",
+            // close code block
+            10..10 => "```\n",
+            // open code block
+            3..6 => "",
+            0..1 => "``` rust\n",
+        );
+    }
+
+    #[test]
+    fn definitions() {
+        test_edits!(
+            "# a\n\n[b][] [a][]\n\nc\n",
+            // add link definition
+            18..18 => "\n[b]: /url\n",
+            // change heading
+            2..3 => "x",
+            // add heading
+            0..0 => "## y\n\n",
+            // remove heading
+            0..6 => "",
+            // edit paragraph after references
+            16..17 => "d",
+        );
+    }
+
+    #[test]
+    fn sections() {
+        test_edits!(
+            "# a\n\n{#x}\n\n# b\n",
+            // attributes before a heading that closes a section
+            11..11 => "{.y}\n\n",
+            // trailing attributes
+            21..21 => "\n{#z}\n",
+        );
+        test_edits!(
+            "{#x}\n[x]: u\n# {#x}*)## b::: (> *{#x}::: [^f]",
+            // heading after a link definition with attributes
+            24..30 => "",
+        );
+    }
+
+    #[test]
+    fn change() {
+        let mut p = IncrementalParser::new("a\n\nb\n\nc\n".to_string());
+        let change = p.edit(3..4, "x");
+        assert_eq!(change.old, 5..6);
+        assert_eq!(change.new, 5..6);
+        assert_eq!(p.events()[5], (Event::Str("x".into()), 3..4));
+        let change = p.edit(3..3, "");
+        assert!(change.old.is_empty());
+        assert!(change.new.is_empty());
+    }
+
+    #[test]
+    fn list_end() {
+        test_edits!(
+            "x\n[^g]: F\n\n    - l\n\n    s\n",
+            // list closed by indented paragraph
+            22..22 => "t",
+            // list closed by link definition
+            11..11 => "[a]: /url\n\n",
+        );
+    }
+
+    #[test]
+    fn random_edits() {
+        // no link definitions or multiple headings, which may resolve differently when
+        // duplicated
+        let src = concat!(
+            "# Heading\n",
+            "\n",
+            "Some[^a] paragraph[^b] with _footnotes_[^g].\n",
+            "\n",
+            "[^a]: A typical footnote.\n",
+            "[^b]: Another footnote.\n",
+            "[^g]: Footnotes may also be\n",
+            "\n",
+            "    - long and,\n",
+            "    - contain multiple block elements.\n",
+            "\n",
+            "    such as\n",
+            "\n",
+            "    > blockquotes.\n",
+            "\n",
+            "::: note\n",
+            "1. *x*\n",
+            "2. y\n",
+            ":::\n",
+            "\n",
+            "term\n",
+            ": details\n",
+        );
+        let pieces = &[
+            "", "\n", "\n\n", "  ", "    ", "- ", "1. ", ": ", "> ", "```\n", ":::\n", "*", "_",
+            "x", "[^a]", "[^g]: ", "{.c}\n", "| x |\n",
+        ];
+        edit_randomly(src, pieces);
+    }
+
+    #[test]
+    fn random_edits_headings() {
+        // no references, which may resolve differently to duplicated link definitions and
+        // headings, so the only brackets are those of a single link definition
+        let src = concat!(
+            "# a\n",        //
+            "\n",           //
+            "{#x}\n",       //
+            "\n",           //
+            "## b\n",       //
+            "\n",           //
+            "c\n",          //
+            "\n",           //
+            "{.d}\n",       //
+            "[e]: /url\n",  //
+            "\n",           //
+            "> # f {#y}\n", //
+        );
+        let pieces = &[
+            "", "\n", "\n\n", "  ", "- ", "> ", "# ", "## ", "x", "{#x}\n", "{.c}", "{#y}\n\n",
+            ":::\n", "| x |\n",
+        ];
+        edit_randomly(src, pieces);
+    }
+
+    /// Apply random edits that insert the pieces, and compare the events with the events of a new
+    /// parser after each edit.
+    fn edit_randomly(src: &str, pieces: &[&str]) {
+        let mut seed: u64 = 1;
+        let mut rand = |n: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % n
+        };
+        let mut p = IncrementalParser::new(src.to_string());
+        for _ in 0..2000 {
+            // the input is ascii, so all positions are char boundaries
+            let start = rand(p.src().len() + 1);
+            let end = (start + rand(4)).min(p.src().len());
+            edit(&mut p, start..end, pieces[rand(pieces.len())]);
+        }
+    }
+}
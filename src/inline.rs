@@ -3,6 +3,7 @@ use std::ops::Range;
 use crate::attr;
 use crate::lex;
 use crate::CowStr;
+use crate::Syntax;
 
 use lex::Delimiter;
 use lex::Sequence;
@@ -228,6 +229,8 @@ pub struct Parser<'s> {
     pub(crate) store_cowstrs: Vec<CowStr<'s>>,
     /// Storage of attributes, used to reduce size of [`EventKind`].
     pub(crate) store_attributes: Vec<attr::Attributes<'s>>,
+    /// Syntax that is enabled.
+    syntax: Syntax,
 }
 
 enum ControlFlow {
@@ -243,7 +246,7 @@ enum ControlFlow {
 }
 
 impl<'s> Parser<'s> {
-    pub fn new(src: &'s str) -> Self {
+    pub(crate) fn new(src: &'s str, syntax: Syntax) -> Self {
         Self {
             input: Input::new(src),
             openers: Vec::new(),
@@ -252,6 +255,7 @@ impl<'s> Parser<'s> {
             attributes: None,
             store_cowstrs: Vec::new(),
             store_attributes: Vec::new(),
+            syntax,
         }
     }
 
@@ -287,6 +291,7 @@ impl<'s> Parser<'s> {
                 .or_else(|| self.parse_autolink(&first))
                 .or_else(|| self.parse_symbol(&first))
                 .or_else(|| self.parse_footnote_reference(&first))
+                .or_else(|| self.parse_literal(&first))
                 .or_else(|| self.parse_container(&first))
                 .or_else(|| self.parse_atom(&first))
                 .unwrap_or_else(|| self.push(EventKind::Str).unwrap())
@@ -310,7 +315,11 @@ impl<'s> Parser<'s> {
             if usize::from(len_opener) == first.len
                 && matches!(first.kind, lex::Kind::Seq(Sequence::Backtick))
             {
-                let raw_format = self.input.ahead_raw_format();
+                let raw_format = if self.syntax.raw {
+                    self.input.ahead_raw_format()
+                } else {
+                    None
+                };
                 if let Some(span_format) = raw_format.clone() {
                     self.events[event_opener].kind = EventKind::Enter(RawFormat {
                         format: &self.input.src[span_format.clone()],
@@ -334,6 +343,7 @@ impl<'s> Parser<'s> {
                 self.input.lexer.verbatim = false;
                 self.verbatim = None;
                 if raw_format.is_none()
+                    && self.syntax.attributes
                     && self.input.peek().map_or(false, |t| {
                         matches!(t.kind, lex::Kind::Open(Delimiter::Brace))
                     })
@@ -357,7 +367,7 @@ impl<'s> Parser<'s> {
                         && self.input.peek().map_or(false, |t| {
                             matches!(
                                 t.kind,
-                                lex::Kind::Seq(Sequence::Backtick) if t.len != len_opener.into(),
+                                lex::Kind::Seq(Sequence::Backtick) if t.len != len_opener as usize,
                             )
                         })
                     {
@@ -377,7 +387,8 @@ impl<'s> Parser<'s> {
                 .back()
                 .and_then(|e| matches!(&e.kind, EventKind::Str).then(|| e.span.clone()))
                 .filter(|sp| {
-                    sp.end == self.input.span.start
+                    self.syntax.math
+                        && sp.end == self.input.span.start
                         && self.input.src.as_bytes()[sp.start + sp.len() - 1] == b'$'
                         && sp
                             .end
@@ -421,7 +432,7 @@ impl<'s> Parser<'s> {
     }
 
     fn parse_attributes(&mut self, first: &lex::Token) -> Option<ControlFlow> {
-        if first.kind == lex::Kind::Open(Delimiter::Brace) {
+        if first.kind == lex::Kind::Open(Delimiter::Brace) && self.syntax.attributes {
             if let Some(state) = self.attributes.take() {
                 self.resume_attributes(state, true, false)
             } else {
@@ -604,7 +615,7 @@ impl<'s> Parser<'s> {
     }
 
     fn parse_symbol(&mut self, first: &lex::Token) -> Option<ControlFlow> {
-        if first.kind == lex::Kind::Sym(Symbol::Colon) {
+        if first.kind == lex::Kind::Sym(Symbol::Colon) && self.syntax.symbols {
             let mut end = false;
             let mut valid = true;
             let len = self
@@ -633,6 +644,7 @@ impl<'s> Parser<'s> {
 
     fn parse_footnote_reference(&mut self, first: &lex::Token) -> Option<ControlFlow> {
         if first.kind == lex::Kind::Open(Delimiter::Bracket)
+            && self.syntax.footnotes
             && matches!(
                 self.input.peek(),
                 Some(lex::Token {
@@ -819,9 +831,11 @@ impl<'s> Parser<'s> {
                     }
                 };
 
-                if self.input.peek().map_or(false, |t| {
-                    matches!(t.kind, lex::Kind::Open(Delimiter::Brace))
-                }) {
+                if self.syntax.attributes
+                    && self.input.peek().map_or(false, |t| {
+                        matches!(t.kind, lex::Kind::Open(Delimiter::Brace))
+                    })
+                {
                     let elem_ty = if matches!(opener, Opener::DoubleQuoted | Opener::SingleQuoted) {
                         // quote delimiters will turn into atoms instead of containers, so cannot
                         // place attributes on the container start
@@ -883,6 +897,25 @@ impl<'s> Parser<'s> {
             })
     }
 
+    /// Parse punctuation as plain text if it should not be replaced by typographic characters.
+    fn parse_literal(&mut self, first: &lex::Token) -> Option<ControlFlow> {
+        let literal = match first.kind {
+            lex::Kind::Sym(Symbol::Quote1 | Symbol::Quote2)
+            | lex::Kind::Open(Delimiter::BraceQuote1 | Delimiter::BraceQuote2)
+            | lex::Kind::Close(Delimiter::BraceQuote1 | Delimiter::BraceQuote2) => {
+                !self.syntax.smart_punctuation.quotes
+            }
+            lex::Kind::Seq(Sequence::Period) => !self.syntax.smart_punctuation.ellipsis,
+            lex::Kind::Seq(Sequence::Hyphen) => !self.syntax.smart_punctuation.dashes,
+            _ => false,
+        };
+        if literal {
+            self.push(EventKind::Str)
+        } else {
+            None
+        }
+    }
+
     fn parse_atom(&mut self, first: &lex::Token) -> Option<ControlFlow> {
         let atom = match first.kind {
             lex::Kind::Newline => Softbreak,
@@ -1220,11 +1253,13 @@ mod test {
     use super::EventKind::*;
     use super::QuoteType;
     use super::Verbatim;
+    use crate::SmartPunctuation;
+    use crate::Syntax;
 
     macro_rules! test_parse {
         ($($st:ident,)? $src:expr $(,$($token:expr),* $(,)?)?) => {
             #[allow(unused)]
-            let mut p = super::Parser::new($src);
+            let mut p = super::Parser::new($src, Syntax::default());
             p.feed_line(0..$src.len(), true);
             let actual = p.map(|ev| (ev.kind, &$src[ev.span])).collect::<Vec<_>>();
             let expected = &[$($($token),*,)?];
@@ -2011,4 +2046,39 @@ mod test {
             (Empty, "{.b}"),
         );
     }
+
+    #[test]
+    fn smart_punctuation_disabled() {
+        let src = "'a' {\"b\"} c... d--e";
+        let parse = |smart_punctuation| {
+            let syntax = Syntax {
+                smart_punctuation,
+                ..Syntax::default()
+            };
+            let mut p = super::Parser::new(src, syntax);
+            p.feed_line(0..src.len(), true);
+            p.map(|ev| (ev.kind, &src[ev.span])).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            parse(SmartPunctuation {
+                quotes: false,
+                ellipsis: false,
+                dashes: false,
+            }),
+            &[(Str, src)],
+        );
+        assert_eq!(
+            parse(SmartPunctuation {
+                quotes: false,
+                ..SmartPunctuation::default()
+            }),
+            &[
+                (Str, "'a' {\"b\"} c"),
+                (Atom(Ellipsis), "..."),
+                (Str, " d"),
+                (Atom(EnDash), "--"),
+                (Str, "e"),
+            ],
+        );
+    }
 }
@@ -0,0 +1,897 @@
+//! A JSON renderer that takes an iterator of [`Event`]s and emits the abstract syntax tree used by
+//! the [djot.js](https://github.com/jgm/djot.js) reference implementation.
+//!
+//! The output is a single JSON object with the `doc` tag, e.g.
+//! `{"tag":"doc","children":[...],"references":{},"autoReferences":{},"footnotes":{}}`. Link
+//! definitions, footnotes and implicit heading references are placed in the `references`,
+//! `footnotes` and `autoReferences` objects, respectively, rather than in the tree itself.
+//!
+//! Source positions are only known when the events come from [`Parser::into_offset_iter`], see
+//! [`Renderer::push_offset`].
+//!
+//! [`Parser::into_offset_iter`]: crate::Parser::into_offset_iter
+
+use std::ops::Range;
+
+use crate::Alignment;
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::LineIndex;
+use crate::LinkType;
+use crate::ListKind;
+use crate::PositionEncoding;
+use crate::Render;
+use crate::RenderRef;
+use crate::SpanLinkType;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("_hello_");
+/// assert_eq!(
+///     jotdown::json::render_to_string(events),
+///     concat!(
+///         r#"{"tag":"doc","children":[{"tag":"para","children":["#,
+///         r#"{"tag":"emph","children":[{"tag":"str","text":"hello"}]}]}],"#,
+///         r#""references":{},"autoReferences":{},"footnotes":{}}"#,
+///     ),
+/// );
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer.push(events, &mut s).unwrap();
+    s
+}
+
+/// [`Render`] implementor that writes the djot.js JSON AST.
+#[derive(Clone, Default)]
+pub struct Renderer;
+
+impl Renderer {
+    /// Write the AST with the source position of each node, from events with byte ranges of the
+    /// source, as emitted by [`Parser::into_offset_iter`].
+    ///
+    /// Positions are in the form `{"start":{"line":1,"col":1,"offset":0},"end":{..}}`, where both
+    /// the start and the end refer to a character within the node. Lines and columns are counted
+    /// from 1 and offsets from 0. As in djot.js, columns and offsets are counted in UTF-16 code
+    /// units.
+    ///
+    /// The source is also used to obtain the labels of reference links and the original text of
+    /// smart punctuation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let src = "a\nb";
+    /// let mut json = String::new();
+    /// json::Renderer
+    ///     .push_offset(src, Parser::new(src).into_offset_iter(), &mut json)
+    ///     .unwrap();
+    /// assert!(json.contains(concat!(
+    ///     r#"{"tag":"soft_break","pos":{"#,
+    ///     r#""start":{"line":1,"col":2,"offset":1},"#,
+    ///     r#""end":{"line":1,"col":2,"offset":1}}}"#,
+    /// )));
+    /// ```
+    ///
+    /// [`Parser::into_offset_iter`]: crate::Parser::into_offset_iter
+    pub fn push_offset<'s, I, W>(&self, src: &'s str, events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = (Event<'s>, Range<usize>)>,
+        W: std::fmt::Write,
+    {
+        let mut b = Builder::new(Some(src));
+        events.for_each(|(e, r)| b.event(&e, Some(r)));
+        b.finish(&mut out)
+    }
+}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut b = Builder::new(None);
+        events.for_each(|e| b.event(&e, None));
+        b.finish(&mut out)
+    }
+}
+
+impl RenderRef for Renderer {
+    fn push_ref<'s, E, I, W>(&self, events: I, mut out: W) -> std::fmt::Result
+    where
+        E: AsRef<Event<'s>>,
+        I: Iterator<Item = E>,
+        W: std::fmt::Write,
+    {
+        let mut b = Builder::new(None);
+        events.for_each(|e| b.event(e.as_ref(), None));
+        b.finish(&mut out)
+    }
+}
+
+enum Value {
+    Str(String),
+    Int(u64),
+    Bool(bool),
+}
+
+struct Node {
+    tag: &'static str,
+    /// Fields specific to the type of node.
+    fields: Vec<(&'static str, Value)>,
+    children: Option<Vec<Node>>,
+    attributes: Vec<(String, String)>,
+    /// Automatically generated attributes, i.e. heading ids.
+    auto_attributes: Vec<(String, String)>,
+    /// Byte range within the source.
+    pos: Option<Range<usize>>,
+}
+
+impl Node {
+    fn leaf(tag: &'static str, pos: Option<Range<usize>>) -> Self {
+        Self {
+            tag,
+            fields: Vec::new(),
+            children: None,
+            attributes: Vec::new(),
+            auto_attributes: Vec::new(),
+            pos,
+        }
+    }
+
+    fn with_children(tag: &'static str, pos: Option<Range<usize>>) -> Self {
+        Self {
+            children: Some(Vec::new()),
+            ..Self::leaf(tag, pos)
+        }
+    }
+
+    fn field(mut self, key: &'static str, value: Value) -> Self {
+        self.fields.push((key, value));
+        self
+    }
+
+    fn str_field(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find_map(|(k, v)| match v {
+            Value::Str(s) if *k == key => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Concatenated text of all `str` children.
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .flatten()
+            .filter_map(|n| n.str_field("text"))
+            .collect()
+    }
+
+    fn push(&mut self, node: Node) {
+        let children = self.children.get_or_insert_with(Vec::new);
+        if let Some(last) = children.last_mut() {
+            if last.tag == "str" && node.tag == "str" {
+                // merge consecutive strings, e.g. around escapes
+                if let (Some((_, Value::Str(a))), Some((_, Value::Str(b)))) =
+                    (last.fields.first_mut(), node.fields.first())
+                {
+                    a.push_str(b);
+                }
+                if let (Some(a), Some(b)) = (&mut last.pos, &node.pos) {
+                    a.end = b.end;
+                }
+                return;
+            }
+        }
+        children.push(node);
+    }
+}
+
+/// Converts byte offsets to source positions.
+struct Positions<'s> {
+    src: &'s str,
+    index: LineIndex<'s>,
+    /// UTF-16 offset of the start of each line.
+    lines_utf16: Vec<usize>,
+}
+
+impl<'s> Positions<'s> {
+    fn new(src: &'s str) -> Self {
+        let lines_utf16 = std::iter::once(0)
+            .chain(src.split_inclusive('\n').scan(0, |n, l| {
+                *n += l.encode_utf16().count();
+                Some(*n)
+            }))
+            .collect();
+        Self {
+            src,
+            index: LineIndex::new(src),
+            lines_utf16,
+        }
+    }
+
+    fn write<W>(&self, offset: usize, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let pos = self.index.position(offset, PositionEncoding::Utf16);
+        write!(
+            out,
+            r#"{{"line":{},"col":{},"offset":{}}}"#,
+            pos.line,
+            pos.column,
+            self.lines_utf16[pos.line - 1] + pos.column - 1,
+        )
+    }
+
+    /// Write the position of a node with the given byte range.
+    fn write_range<W>(&self, range: &Range<usize>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let end = if range.end > range.start {
+            // end refers to the last character
+            self.src[..range.end]
+                .char_indices()
+                .next_back()
+                .map_or(range.start, |(i, _)| i)
+        } else {
+            range.start
+        };
+        out.write_str(r#"{"start":"#)?;
+        self.write(range.start, out)?;
+        out.write_str(r#","end":"#)?;
+        self.write(end, out)?;
+        out.write_char('}')
+    }
+}
+
+struct Builder<'s> {
+    src: Option<&'s str>,
+    /// Stack of open nodes, the root document at the bottom.
+    stack: Vec<Node>,
+    references: Vec<(String, Node)>,
+    auto_references: Vec<(String, Node)>,
+    footnotes: Vec<(String, Node)>,
+    /// Text of the current heading, formatting stripped.
+    heading_text: Option<String>,
+}
+
+impl<'s> Builder<'s> {
+    fn new(src: Option<&'s str>) -> Self {
+        Self {
+            src,
+            stack: vec![Node::with_children("doc", None)],
+            references: Vec::new(),
+            auto_references: Vec::new(),
+            footnotes: Vec::new(),
+            heading_text: None,
+        }
+    }
+
+    fn top(&mut self) -> &mut Node {
+        self.stack.last_mut().unwrap()
+    }
+
+    fn event(&mut self, e: &Event<'s>, range: Option<Range<usize>>) {
+        if let Some(text) = &mut self.heading_text {
+            match e {
+                Event::Str(s) => text.push_str(s),
+                Event::Softbreak => text.push(' '),
+                _ => {}
+            }
+        }
+
+        match e {
+            Event::Start(c, attrs) => self.enter(c, attrs, range),
+            Event::End(c) => self.exit(c, range),
+            Event::Str(s) => {
+                let node = Node::leaf("str", range).field("text", Value::Str(s.to_string()));
+                self.top().push(node);
+            }
+            Event::FootnoteReference(label) => {
+                let node = Node::leaf("footnote_reference", range)
+                    .field("text", Value::Str(label.to_string()));
+                self.top().push(node);
+            }
+            Event::Symbol(sym) => {
+                let node = Node::leaf("symb", range).field("alias", Value::Str(sym.to_string()));
+                self.top().push(node);
+            }
+            Event::LeftSingleQuote => self.smart("left_single_quote", "'", range),
+            Event::RightSingleQuote => self.close_quote("single_quoted", "'", range),
+            Event::LeftDoubleQuote => self.smart("left_double_quote", "\"", range),
+            Event::RightDoubleQuote => self.close_quote("double_quoted", "\"", range),
+            Event::Ellipsis => self.smart("ellipses", "...", range),
+            Event::EnDash => self.smart("en_dash", "--", range),
+            Event::EmDash => self.smart("em_dash", "---", range),
+            Event::NonBreakingSpace => self.top().push(Node::leaf("non_breaking_space", range)),
+            Event::Hardbreak => self.top().push(Node::leaf("hard_break", range)),
+            Event::Softbreak => self.top().push(Node::leaf("soft_break", range)),
+            Event::ThematicBreak(attrs) => {
+                let mut node = Node::leaf("thematic_break", range);
+                node.attributes = attributes(attrs);
+                self.top().push(node);
+            }
+            Event::Escape | Event::Blankline | Event::Attributes(..) => {}
+        }
+    }
+
+    fn smart(&mut self, ty: &str, text: &str, range: Option<Range<usize>>) {
+        let text = match (self.src, &range) {
+            (Some(src), Some(r)) => &src[r.clone()],
+            _ => text,
+        };
+        let node = Node::leaf("smart_punctuation", range)
+            .field("type", Value::Str(ty.to_string()))
+            .field("text", Value::Str(text.to_string()));
+        self.top().push(node);
+    }
+
+    /// Wrap the content since a matching left quote, or add an unmatched right quote.
+    fn close_quote(&mut self, tag: &'static str, text: &str, range: Option<Range<usize>>) {
+        let left = if tag == "double_quoted" {
+            "left_double_quote"
+        } else {
+            "left_single_quote"
+        };
+        let children = self.top().children.get_or_insert_with(Vec::new);
+        let open = children
+            .iter()
+            .rposition(|n| n.tag == "smart_punctuation" && n.str_field("type") == Some(left));
+        if let Some(i) = open {
+            let inner = children.split_off(i + 1);
+            let left = children.pop().unwrap();
+            let pos = match (left.pos, &range) {
+                (Some(l), Some(r)) => Some(l.start..r.end),
+                _ => None,
+            };
+            let mut node = Node::with_children(tag, pos);
+            inner.into_iter().for_each(|n| node.push(n));
+            self.top().push(node);
+        } else {
+            let ty = if tag == "double_quoted" {
+                "right_double_quote"
+            } else {
+                "right_single_quote"
+            };
+            self.smart(ty, text, range);
+        }
+    }
+
+    /// Close a definition list item that has no definition.
+    fn close_definition_list_item(&mut self) {
+        if self.top().tag == "definition_list_item" {
+            let item = self.stack.pop().unwrap();
+            self.top().push(item);
+        }
+    }
+
+    fn enter(&mut self, c: &Container<'s>, attrs: &Attributes<'s>, range: Option<Range<usize>>) {
+        let pos = range.map(|r| r.start..r.end);
+        let mut node =
+            match c {
+                Container::Blockquote => Node::with_children("block_quote", pos),
+                Container::List { kind, tight } => match kind {
+                    ListKind::Unordered(b) => Node::with_children("bullet_list", pos)
+                        .field("style", Value::Str(char::from(*b).to_string()))
+                        .field("tight", Value::Bool(*tight)),
+                    ListKind::Task(..) => {
+                        Node::with_children("task_list", pos).field("tight", Value::Bool(*tight))
+                    }
+                    ListKind::Ordered {
+                        numbering,
+                        style,
+                        start,
+                    } => {
+                        let mut node = Node::with_children("ordered_list", pos).field(
+                            "style",
+                            Value::Str(style.marker(&numbering.format_number(1))),
+                        );
+                        if *start != 1 {
+                            node = node.field("start", Value::Int(*start));
+                        }
+                        node.field("tight", Value::Bool(*tight))
+                    }
+                },
+                Container::ListItem => Node::with_children("list_item", pos),
+                Container::TaskListItem { checked } => Node::with_children("task_list_item", pos)
+                    .field(
+                        "checkbox",
+                        Value::Str(if *checked { "checked" } else { "unchecked" }.to_string()),
+                    ),
+                Container::DescriptionList => Node::with_children("definition_list", pos),
+                Container::DescriptionTerm => {
+                    self.close_definition_list_item();
+                    self.stack
+                        .push(Node::with_children("definition_list_item", pos.clone()));
+                    Node::with_children("term", pos)
+                }
+                Container::DescriptionDetails => Node::with_children("definition", pos),
+                Container::Footnote { label } => Node::with_children("footnote", pos)
+                    .field("label", Value::Str(label.to_string())),
+                Container::Table => Node::with_children("table", pos),
+                Container::TableRow { head } => {
+                    Node::with_children("row", pos).field("head", Value::Bool(*head))
+                }
+                Container::Section { id } => {
+                    let mut node = Node::with_children("section", pos);
+                    if !attrs.contains_key("id") {
+                        node.auto_attributes
+                            .push(("id".to_string(), id.to_string()));
+                    }
+                    node
+                }
+                Container::Div { class } => {
+                    let mut node = Node::with_children("div", pos);
+                    if !class.is_empty() {
+                        node.attributes
+                            .push(("class".to_string(), class.to_string()));
+                    }
+                    node
+                }
+                Container::Paragraph => Node::with_children("para", pos),
+                Container::Heading {
+                    level,
+                    has_section,
+                    id,
+                } => {
+                    self.heading_text = Some(String::new());
+                    let mut node = Node::with_children("heading", pos)
+                        .field("level", Value::Int(u64::from(*level)));
+                    if !has_section && !attrs.contains_key("id") {
+                        node.auto_attributes
+                            .push(("id".to_string(), id.to_string()));
+                    }
+                    node
+                }
+                Container::TableCell { alignment, head } => {
+                    let align = match alignment {
+                        Alignment::Unspecified => "default",
+                        Alignment::Left => "left",
+                        Alignment::Center => "center",
+                        Alignment::Right => "right",
+                    };
+                    Node::with_children("cell", pos)
+                        .field("head", Value::Bool(*head))
+                        .field("align", Value::Str(align.to_string()))
+                }
+                Container::Caption => Node::with_children("caption", pos),
+                Container::LinkDefinition { label } => Node::with_children("reference", pos)
+                    .field("label", Value::Str(label.to_string())),
+                Container::RawBlock { format } => Node::with_children("raw_block", pos)
+                    .field("format", Value::Str(format.to_string())),
+                Container::CodeBlock { language } => {
+                    let mut node = Node::with_children("code_block", pos);
+                    if !language.is_empty() {
+                        node = node.field("lang", Value::Str(language.to_string()));
+                    }
+                    node
+                }
+                Container::Span => Node::with_children("span", pos),
+                Container::Link(dst, ty) => match ty {
+                    LinkType::AutoLink => Node::with_children("url", pos),
+                    LinkType::Email => Node::with_children("email", pos),
+                    LinkType::Span(..) => Node::with_children("link", pos)
+                        .field("destination", Value::Str(dst.to_string())),
+                },
+                Container::Image(dst, ..) => Node::with_children("image", pos)
+                    .field("destination", Value::Str(dst.to_string())),
+                Container::Verbatim => Node::with_children("verbatim", pos),
+                Container::Math { display } => Node::with_children(
+                    if *display {
+                        "display_math"
+                    } else {
+                        "inline_math"
+                    },
+                    pos,
+                ),
+                Container::RawInline { format } => Node::with_children("raw_inline", pos)
+                    .field("format", Value::Str(format.to_string())),
+                Container::Subscript => Node::with_children("subscript", pos),
+                Container::Superscript => Node::with_children("superscript", pos),
+                Container::Insert => Node::with_children("insert", pos),
+                Container::Delete => Node::with_children("delete", pos),
+                Container::Strong => Node::with_children("strong", pos),
+                Container::Emphasis => Node::with_children("emph", pos),
+                Container::Mark => Node::with_children("mark", pos),
+            };
+        for (k, v) in attributes(attrs) {
+            if let Some((_, class)) = node.attributes.iter_mut().find(|(a, _)| *a == k) {
+                class.push(' ');
+                class.push_str(&v);
+            } else {
+                node.attributes.push((k, v));
+            }
+        }
+        self.stack.push(node);
+    }
+
+    fn exit(&mut self, c: &Container<'s>, range: Option<Range<usize>>) {
+        if matches!(c, Container::DescriptionList) {
+            self.close_definition_list_item();
+        }
+
+        let mut node = self.stack.pop().unwrap();
+        if let (Some(pos), Some(r)) = (&mut node.pos, range) {
+            pos.end = r.end;
+        }
+
+        match c {
+            Container::Verbatim
+            | Container::Math { .. }
+            | Container::RawInline { .. }
+            | Container::RawBlock { .. }
+            | Container::CodeBlock { .. }
+            | Container::Link(_, LinkType::AutoLink | LinkType::Email) => {
+                let text = node.text();
+                node.children = None;
+                node.fields.push(("text", Value::Str(text)));
+            }
+            Container::Link(_, LinkType::Span(ty)) | Container::Image(_, ty) => {
+                if matches!(ty, SpanLinkType::Reference | SpanLinkType::Unresolved) {
+                    if let Some(label) = self.reference_label(&node) {
+                        node.fields.clear();
+                        node.fields.push(("reference", Value::Str(label)));
+                    }
+                }
+            }
+            Container::LinkDefinition { label } => {
+                let destination = node.text();
+                node.children = None;
+                node.fields.push(("destination", Value::Str(destination)));
+                if !self.references.iter().any(|(l, _)| l == label) {
+                    self.references.push((label.to_string(), node));
+                }
+                return;
+            }
+            Container::Footnote { label } => {
+                if !self.footnotes.iter().any(|(l, _)| l == label) {
+                    self.footnotes.push((label.to_string(), node));
+                }
+                return;
+            }
+            Container::Heading { id, .. } => {
+                let text = self.heading_text.take().unwrap_or_default();
+                if !self.auto_references.iter().any(|(l, _)| *l == text) {
+                    let reference = Node::leaf("reference", None)
+                        .field("label", Value::Str(text.clone()))
+                        .field("destination", Value::Str(format!("#{}", id)));
+                    self.auto_references.push((text, reference));
+                }
+            }
+            Container::DescriptionDetails => {
+                // definition ends the item
+                let end = node.pos.as_ref().map(|p| p.end);
+                self.top().push(node);
+                node = self.stack.pop().unwrap();
+                if let (Some(pos), Some(end)) = (&mut node.pos, end) {
+                    pos.end = end;
+                }
+            }
+            _ => {}
+        }
+
+        self.top().push(node);
+    }
+
+    /// Obtain the label of a reference link or image from the source.
+    fn reference_label(&self, node: &Node) -> Option<String> {
+        let src = self.src?;
+        let pos = node.pos.as_ref()?;
+        // e.g. `[text][label]`, `[text][]` or `[text]{attrs}` within the source
+        let s = &src[pos.clone()];
+        let label_start = s.rfind("][").map(|i| i + 2)?;
+        let label_end = label_start + s[label_start..].find(']')?;
+        let label = &s[label_start..label_end];
+        if label.is_empty() {
+            Some(plain_text(node))
+        } else {
+            Some(label.split_whitespace().collect::<Vec<_>>().join(" "))
+        }
+    }
+
+    fn finish<W>(mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        while self.stack.len() > 1 {
+            let node = self.stack.pop().unwrap();
+            self.top().push(node);
+        }
+        let doc = self.stack.pop().unwrap();
+        let positions = self.src.map(Positions::new);
+        let positions = positions.as_ref();
+
+        out.write_str(r#"{"tag":"doc","children":["#)?;
+        write_nodes(doc.children.iter().flatten(), positions, out)?;
+        out.write_str(r#"],"references":"#)?;
+        write_map(&self.references, positions, out)?;
+        out.write_str(r#","autoReferences":"#)?;
+        write_map(&self.auto_references, positions, out)?;
+        out.write_str(r#","footnotes":"#)?;
+        write_map(&self.footnotes, positions, out)?;
+        out.write_char('}')
+    }
+}
+
+/// Text of all descendants of a node, formatting stripped.
+fn plain_text(node: &Node) -> String {
+    let mut s = String::new();
+    for n in node.children.iter().flatten() {
+        match n.tag {
+            "str" => s.push_str(n.str_field("text").unwrap_or_default()),
+            "soft_break" => s.push(' '),
+            _ => s.push_str(&plain_text(n)),
+        }
+    }
+    s
+}
+
+fn attributes(attrs: &Attributes) -> Vec<(String, String)> {
+    attrs
+        .unique_pairs()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn write_nodes<'a, I, W>(nodes: I, positions: Option<&Positions>, out: &mut W) -> std::fmt::Result
+where
+    I: Iterator<Item = &'a Node>,
+    W: std::fmt::Write,
+{
+    for (i, node) in nodes.enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write_node(node, positions, out)?;
+    }
+    Ok(())
+}
+
+fn write_node<W>(node: &Node, positions: Option<&Positions>, out: &mut W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    out.write_str(r#"{"tag":"#)?;
+    write_str(node.tag, out)?;
+    for (k, v) in &node.fields {
+        out.write_char(',')?;
+        write_str(k, out)?;
+        out.write_char(':')?;
+        match v {
+            Value::Str(s) => write_str(s, out)?,
+            Value::Int(n) => write!(out, "{}", n)?,
+            Value::Bool(b) => write!(out, "{}", b)?,
+        }
+    }
+    if let Some(children) = &node.children {
+        out.write_str(r#","children":["#)?;
+        write_nodes(children.iter(), positions, out)?;
+        out.write_char(']')?;
+    }
+    for (key, attrs) in [
+        ("attributes", &node.attributes),
+        ("autoAttributes", &node.auto_attributes),
+    ] {
+        if !attrs.is_empty() {
+            write!(out, r#","{}":{{"#, key)?;
+            for (i, (k, v)) in attrs.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_str(k, out)?;
+                out.write_char(':')?;
+                write_str(v, out)?;
+            }
+            out.write_char('}')?;
+        }
+    }
+    if let (Some(positions), Some(pos)) = (positions, &node.pos) {
+        out.write_str(r#","pos":"#)?;
+        positions.write_range(pos, out)?;
+    }
+    out.write_char('}')
+}
+
+fn write_map<W>(
+    map: &[(String, Node)],
+    positions: Option<&Positions>,
+    out: &mut W,
+) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    out.write_char('{')?;
+    for (i, (k, node)) in map.iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write_str(k, out)?;
+        out.write_char(':')?;
+        write_node(node, positions, out)?;
+    }
+    out.write_char('}')
+}
+
+fn write_str<W>(s: &str, out: &mut W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Render;
+
+    macro_rules! test_json {
+        ($src:expr, $expected:expr $(,)?) => {
+            let mut actual = String::new();
+            super::Renderer
+                .push(crate::Parser::new($src), &mut actual)
+                .unwrap();
+            let expected = format!(
+                r#"{{"tag":"doc","children":[{}],"references":{{}},"autoReferences":{{}},"footnotes":{{}}}}"#,
+                $expected
+            );
+            assert_eq!(actual, expected);
+        };
+    }
+
+    #[test]
+    fn str_merge() {
+        test_json!(
+            "a\\*b\nc",
+            concat!(
+                r#"{"tag":"para","children":["#,
+                r#"{"tag":"str","text":"a*b"},"#,
+                r#"{"tag":"soft_break"},"#,
+                r#"{"tag":"str","text":"c"}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn quotes() {
+        test_json!(
+            "\"a 'b'\" c'",
+            concat!(
+                r#"{"tag":"para","children":["#,
+                r#"{"tag":"double_quoted","children":["#,
+                r#"{"tag":"str","text":"a "},"#,
+                r#"{"tag":"single_quoted","children":[{"tag":"str","text":"b"}]}"#,
+                r#"]},"#,
+                r#"{"tag":"str","text":" c"},"#,
+                r#"{"tag":"smart_punctuation","type":"right_single_quote","text":"'"}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn verbatim() {
+        test_json!(
+            "`a` $`b` `c`{=html} <d@e>",
+            concat!(
+                r#"{"tag":"para","children":["#,
+                r#"{"tag":"verbatim","text":"a"},"#,
+                r#"{"tag":"str","text":" "},"#,
+                r#"{"tag":"inline_math","text":"b"},"#,
+                r#"{"tag":"str","text":" "},"#,
+                r#"{"tag":"raw_inline","format":"html","text":"c"},"#,
+                r#"{"tag":"str","text":" "},"#,
+                r#"{"tag":"email","text":"d@e"}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn list_ordered() {
+        test_json!(
+            "b) a\n\n   c\n",
+            concat!(
+                r#"{"tag":"ordered_list","style":"a)","start":2,"tight":true,"children":["#,
+                r#"{"tag":"list_item","children":["#,
+                r#"{"tag":"para","children":[{"tag":"str","text":"a"}]},"#,
+                r#"{"tag":"para","children":[{"tag":"str","text":"c"}]}"#,
+                r#"]}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn table() {
+        test_json!(
+            "|a|\n|-:|\n",
+            concat!(
+                r#"{"tag":"table","children":["#,
+                r#"{"tag":"row","head":true,"children":["#,
+                r#"{"tag":"cell","head":true,"align":"right","children":["#,
+                r#"{"tag":"str","text":"a"}"#,
+                r#"]}"#,
+                r#"]}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        test_json!(
+            "{#a .b}\n::: c\nd\n:::\n",
+            concat!(
+                r#"{"tag":"div","children":["#,
+                r#"{"tag":"para","children":[{"tag":"str","text":"d"}]}"#,
+                r#"],"attributes":{"class":"c b","id":"a"}}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn references_footnotes() {
+        let src = concat!(
+            "# h\n", //
+            "\n",
+            "[a][b][^c]\n",
+            "\n",
+            "[b]: u\n",
+            "[^c]: d\n",
+        );
+        let mut actual = String::new();
+        super::Renderer
+            .push_offset(src, crate::Parser::new(src).into_offset_iter(), &mut actual)
+            .unwrap();
+        assert!(actual.contains(r#"{"tag":"link","reference":"b","children":"#));
+        assert!(actual.ends_with(concat!(
+            r#""references":{"b":{"tag":"reference","label":"b","destination":"u","pos":"#,
+            r#"{"start":{"line":5,"col":1,"offset":17},"end":{"line":5,"col":7,"offset":23}}}},"#,
+            r##""autoReferences":{"h":{"tag":"reference","label":"h","destination":"#h"}},"##,
+            r#""footnotes":{"c":{"tag":"footnote","label":"c","children":["#,
+            r#"{"tag":"para","children":[{"tag":"str","text":"d","pos":"#,
+            r#"{"start":{"line":6,"col":7,"offset":30},"end":{"line":6,"col":7,"offset":30}}}],"pos":"#,
+            r#"{"start":{"line":6,"col":7,"offset":30},"end":{"line":6,"col":8,"offset":31}}}],"pos":"#,
+            r#"{"start":{"line":6,"col":1,"offset":24},"end":{"line":6,"col":8,"offset":31}}}}}"#,
+        )));
+    }
+
+    #[test]
+    fn positions_utf16() {
+        let src = "😀 a";
+        let mut actual = String::new();
+        super::Renderer
+            .push_offset(src, crate::Parser::new(src).into_offset_iter(), &mut actual)
+            .unwrap();
+        assert!(actual.contains(concat!(
+            r#"{"tag":"str","text":"😀 a","pos":"#,
+            r#"{"start":{"line":1,"col":1,"offset":0},"end":{"line":1,"col":4,"offset":3}}}"#,
+        )));
+    }
+}
@@ -0,0 +1,968 @@
+//! A LaTeX renderer that takes an iterator of [`Event`]s and emits LaTeX.
+//!
+//! Math is passed through verbatim, as is raw content with the `latex` or `tex` format. Raw
+//! content of any other format is omitted. Footnotes are placed at their first reference, using
+//! the `\footnote` command.
+
+use std::ops::Range;
+
+use crate::Alignment;
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::ListKind;
+use crate::Map;
+use crate::OrderedListNumbering;
+use crate::OrderedListStyle;
+use crate::Render;
+use crate::RenderRef;
+use crate::Set;
+use crate::SpanLinkType;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("hello _world_");
+/// assert_eq!(
+///     jotdown::latex::render_to_string(events),
+///     "hello \\emph{world}\n",
+/// );
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer::default().push(events, &mut s).unwrap();
+    s
+}
+
+/// Options for the preamble of a standalone LaTeX document.
+#[derive(Clone)]
+pub struct Preamble {
+    /// Class of the document.
+    ///
+    /// Defaults to `article`.
+    pub document_class: String,
+    /// Options for the document class, e.g. `a4paper,12pt`.
+    ///
+    /// Defaults to no options.
+    pub class_options: String,
+    /// Additional content to place at the end of the preamble, e.g. `\usepackage` commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::latex::*;
+    /// let mut latex = String::new();
+    /// let renderer = Renderer::standalone(Preamble {
+    ///     extra: "\\usepackage{microtype}\n".to_string(),
+    ///     ..Preamble::default()
+    /// });
+    /// renderer.push(Parser::new("a"), &mut latex).unwrap();
+    /// assert!(latex.contains("\\usepackage{microtype}\n\\begin{document}\n"));
+    /// ```
+    pub extra: String,
+}
+
+impl Default for Preamble {
+    fn default() -> Self {
+        Self {
+            document_class: "article".to_string(),
+            class_options: String::new(),
+            extra: String::new(),
+        }
+    }
+}
+
+/// [`Render`] implementor that writes LaTeX output.
+///
+/// By default, only the body of the document is written. To write a complete document, see the
+/// [`Renderer::standalone`] constructor.
+///
+/// The output makes use of the `amssymb`, `graphicx`, `hyperref`, `soul`, `ulem` and `xcolor`
+/// packages, which are included in the preamble of standalone documents.
+///
+/// NOTE: Footnote definitions may appear after their references, so all events are buffered before
+/// any output is written.
+#[derive(Clone, Default)]
+pub struct Renderer {
+    preamble: Option<Preamble>,
+}
+
+impl Renderer {
+    /// Create a renderer that writes a complete document with the given preamble.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::latex::*;
+    /// let mut latex = String::new();
+    /// let renderer = Renderer::standalone(Preamble {
+    ///     class_options: "a4paper".to_string(),
+    ///     ..Preamble::default()
+    /// });
+    /// renderer.push(Parser::new("a"), &mut latex).unwrap();
+    /// assert!(latex.starts_with("\\documentclass[a4paper]{article}\n"));
+    /// assert!(latex.ends_with("\\begin{document}\n\na\n\n\\end{document}\n"));
+    /// ```
+    #[must_use]
+    pub fn standalone(preamble: Preamble) -> Self {
+        Self {
+            preamble: Some(preamble),
+        }
+    }
+
+    fn render<W>(&self, events: &[Event], mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if let Some(preamble) = &self.preamble {
+            out.write_str("\\documentclass")?;
+            if !preamble.class_options.is_empty() {
+                write!(out, "[{}]", preamble.class_options)?;
+            }
+            writeln!(out, "{{{}}}", preamble.document_class)?;
+            out.write_str(concat!(
+                "\\usepackage{amssymb}\n",
+                "\\usepackage{graphicx}\n",
+                "\\usepackage[normalem]{ulem}\n",
+                "\\usepackage{xcolor}\n",
+                "\\usepackage{soul}\n",
+                "\\usepackage{hyperref}\n",
+            ))?;
+            out.write_str(&preamble.extra)?;
+            out.write_str("\\begin{document}\n\n")?;
+        }
+
+        let mut w = Writer::new(events);
+        w.render_range(0..events.len(), &mut out)?;
+
+        if self.preamble.is_some() {
+            out.write_str("\n\\end{document}\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, events: I, out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let events: Vec<Event> = events.collect();
+        self.render(&events, out)
+    }
+}
+
+impl RenderRef for Renderer {
+    fn push_ref<'s, E, I, W>(&self, events: I, out: W) -> std::fmt::Result
+    where
+        E: AsRef<Event<'s>>,
+        I: Iterator<Item = E>,
+        W: std::fmt::Write,
+    {
+        let events: Vec<Event> = events.map(|e| e.as_ref().clone()).collect();
+        self.render(&events, out)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Raw {
+    None,
+    /// Write text without escaping, e.g. math or raw LaTeX.
+    Verbatim,
+}
+
+#[derive(Default)]
+struct Table {
+    rows: Vec<(bool, Vec<String>)>,
+    alignments: Vec<Alignment>,
+    caption: Option<String>,
+}
+
+struct Writer<'s, 'e> {
+    events: &'e [Event<'s>],
+    /// Event ranges of the content of each footnote.
+    footnotes: Map<&'e str, Range<usize>>,
+    /// Footnotes that have been written.
+    written: Set<&'e str>,
+    /// Buffers for content that must be written in a different place, e.g. table cells.
+    bufs: Vec<String>,
+    /// Whether a block has been written within each open block container.
+    blocks: Vec<bool>,
+    raw: Raw,
+    /// Depth of elements whose content is omitted.
+    ignore: usize,
+    /// Depth of open enumerate environments.
+    enumerate: usize,
+    table: Option<Table>,
+    /// Whether the last written character is a hyphen.
+    hyphen: bool,
+}
+
+impl<'s, 'e> Writer<'s, 'e> {
+    fn new(events: &'e [Event<'s>]) -> Self {
+        let mut footnotes = Map::new();
+        let mut open = Vec::new();
+        for (i, e) in events.iter().enumerate() {
+            match e {
+                Event::Start(Container::Footnote { label }, ..) => {
+                    open.push((label.as_ref(), i + 1))
+                }
+                Event::End(Container::Footnote { .. }) => {
+                    if let Some((label, start)) = open.pop() {
+                        footnotes.entry(label).or_insert(start..i);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            events,
+            footnotes,
+            written: Set::new(),
+            bufs: Vec::new(),
+            blocks: vec![false],
+            raw: Raw::None,
+            ignore: 0,
+            enumerate: 0,
+            table: None,
+            hyphen: false,
+        }
+    }
+
+    /// Render a range of events, skipping footnote definitions.
+    fn render_range<W>(&mut self, range: Range<usize>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let events = self.events;
+        let mut footnote_depth = 0;
+        for e in &events[range] {
+            match e {
+                Event::Start(Container::Footnote { .. }, ..) => footnote_depth += 1,
+                Event::End(Container::Footnote { .. }) => footnote_depth -= 1,
+                e if footnote_depth == 0 => self.render_event(e, out)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn write<W>(&mut self, s: &str, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if let Some(c) = s.chars().last() {
+            self.hyphen = c == '-';
+        }
+        if let Some(buf) = self.bufs.last_mut() {
+            buf.push_str(s);
+            Ok(())
+        } else {
+            out.write_str(s)
+        }
+    }
+
+    /// Separate a new block from a preceding sibling block.
+    fn block<W>(&mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let separate = self.blocks.last().copied().unwrap_or(false);
+        if let Some(b) = self.blocks.last_mut() {
+            *b = true;
+        }
+        if separate {
+            self.write("\n", out)?;
+        }
+        Ok(())
+    }
+
+    fn render_event<W>(&mut self, e: &'e Event<'s>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.ignore > 0 {
+            match e {
+                Event::Start(..) => self.ignore += 1,
+                Event::End(..) => self.ignore -= 1,
+                _ => {}
+            }
+            // the end of the ignored element is also ignored
+            return Ok(());
+        }
+
+        match e {
+            Event::Start(c, ..) => self.enter(c, out)?,
+            Event::End(c) => self.exit(c, out)?,
+            Event::Str(s) => {
+                if self.raw == Raw::Verbatim {
+                    self.write(s, out)?;
+                } else {
+                    let mut escaped = String::new();
+                    if self.hyphen && s.starts_with('-') {
+                        escaped.push_str("{}");
+                    }
+                    write_escape(s, &mut escaped);
+                    self.write(&escaped, out)?;
+                }
+            }
+            Event::FootnoteReference(label) => self.footnote(label, out)?,
+            Event::Symbol(sym) => {
+                let mut escaped = String::new();
+                write_escape(&format!(":{}:", sym), &mut escaped);
+                self.write(&escaped, out)?;
+            }
+            Event::LeftSingleQuote => self.write("`", out)?,
+            Event::RightSingleQuote => self.write("'", out)?,
+            Event::LeftDoubleQuote => self.write("``", out)?,
+            Event::RightDoubleQuote => self.write("''", out)?,
+            Event::Ellipsis => self.write("\\ldots{}", out)?,
+            Event::EnDash => self.write("--", out)?,
+            Event::EmDash => self.write("---", out)?,
+            Event::NonBreakingSpace => self.write("~", out)?,
+            Event::Hardbreak => self.write("\\\\\n", out)?,
+            Event::Softbreak => self.write("\n", out)?,
+            Event::Escape | Event::Blankline | Event::Attributes(..) => {}
+            Event::ThematicBreak(..) => {
+                self.block(out)?;
+                self.write(
+                    "\\begin{center}\n\\rule{0.5\\linewidth}{0.5pt}\n\\end{center}\n",
+                    out,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn enter<W>(&mut self, c: &Container<'s>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => {
+                self.block(out)?;
+                self.write("\\begin{quote}\n", out)?;
+                self.blocks.push(false);
+            }
+            Container::List { kind, .. } => {
+                self.block(out)?;
+                match kind {
+                    ListKind::Unordered(..) | ListKind::Task(..) => {
+                        self.write("\\begin{itemize}\n", out)?;
+                    }
+                    ListKind::Ordered {
+                        numbering,
+                        style,
+                        start,
+                    } => {
+                        self.enumerate += 1;
+                        self.write("\\begin{enumerate}\n", out)?;
+                        let counter = format!("enum{}", "i".repeat(self.enumerate.min(4)));
+                        let default = matches!(numbering, OrderedListNumbering::Decimal)
+                            && matches!(style, OrderedListStyle::Period);
+                        if !default {
+                            let number = match numbering {
+                                OrderedListNumbering::Decimal => "arabic",
+                                OrderedListNumbering::AlphaLower => "alph",
+                                OrderedListNumbering::AlphaUpper => "Alph",
+                                OrderedListNumbering::RomanLower => "roman",
+                                OrderedListNumbering::RomanUpper => "Roman",
+                            };
+                            let (l, r) = match style {
+                                OrderedListStyle::Period => ("", "."),
+                                OrderedListStyle::Paren => ("", ")"),
+                                OrderedListStyle::ParenParen => ("(", ")"),
+                            };
+                            let label = format!(
+                                "\\renewcommand{{\\label{}}}{{{}\\{}{{{}}}{}}}\n",
+                                counter, l, number, counter, r
+                            );
+                            self.write(&label, out)?;
+                        }
+                        if *start != 1 {
+                            let counter = format!(
+                                "\\setcounter{{{}}}{{{}}}\n",
+                                counter,
+                                start.saturating_sub(1)
+                            );
+                            self.write(&counter, out)?;
+                        }
+                    }
+                }
+            }
+            Container::ListItem => {
+                self.write("\\item ", out)?;
+                self.blocks.push(false);
+            }
+            Container::TaskListItem { checked } => {
+                self.write(
+                    if *checked {
+                        "\\item[$\\boxtimes$] "
+                    } else {
+                        "\\item[$\\square$] "
+                    },
+                    out,
+                )?;
+                self.blocks.push(false);
+            }
+            Container::DescriptionList => {
+                self.block(out)?;
+                self.write("\\begin{description}\n", out)?;
+            }
+            Container::DescriptionTerm => self.write("\\item[{", out)?,
+            Container::DescriptionDetails => {}
+            Container::Footnote { .. } => self.ignore += 1,
+            Container::Table => {
+                self.block(out)?;
+                self.table = Some(Table::default());
+            }
+            Container::TableRow { head } => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push((*head, Vec::new()));
+                }
+            }
+            Container::TableCell { alignment, .. } => {
+                if let Some(table) = &mut self.table {
+                    let column = table.rows.last().map_or(0, |(_, cells)| cells.len());
+                    if table.alignments.len() <= column {
+                        table.alignments.push(*alignment);
+                    } else if matches!(table.alignments[column], Alignment::Unspecified) {
+                        table.alignments[column] = *alignment;
+                    }
+                }
+                self.bufs.push(String::new());
+            }
+            Container::Caption => self.bufs.push(String::new()),
+            Container::Section { .. } | Container::Div { .. } | Container::Span => {}
+            Container::Paragraph => self.block(out)?,
+            Container::Heading { level, .. } => {
+                self.block(out)?;
+                let cmd = match level {
+                    1 => "section",
+                    2 => "subsection",
+                    3 => "subsubsection",
+                    4 => "paragraph",
+                    _ => "subparagraph",
+                };
+                self.write(&format!("\\{}{{", cmd), out)?;
+            }
+            Container::LinkDefinition { .. } => self.ignore += 1,
+            Container::CodeBlock { .. } => {
+                self.block(out)?;
+                self.write("\\begin{verbatim}\n", out)?;
+                self.raw = Raw::Verbatim;
+                self.bufs.push(String::new());
+            }
+            Container::RawBlock { format } | Container::RawInline { format } => {
+                if matches!(format.as_ref(), "latex" | "tex") {
+                    if matches!(c, Container::RawBlock { .. }) {
+                        self.block(out)?;
+                        self.bufs.push(String::new());
+                    }
+                    self.raw = Raw::Verbatim;
+                } else {
+                    self.ignore += 1;
+                }
+            }
+            Container::Link(dst, ty) => match ty {
+                LinkType::AutoLink => {
+                    let mut s = "\\url{".to_string();
+                    write_url(dst, &mut s);
+                    s.push('}');
+                    self.write(&s, out)?;
+                    self.ignore += 1;
+                }
+                LinkType::Email => {
+                    let mut s = "\\href{mailto:".to_string();
+                    write_url(dst, &mut s);
+                    s.push_str("}{\\nolinkurl{");
+                    write_url(dst, &mut s);
+                    s.push_str("}}");
+                    self.write(&s, out)?;
+                    self.ignore += 1;
+                }
+                LinkType::Span(SpanLinkType::Unresolved) => {}
+                LinkType::Span(..) => {
+                    let s = if let Some(id) = dst.strip_prefix('#') {
+                        format!("\\hyperref[{}]{{", id)
+                    } else {
+                        let mut s = "\\href{".to_string();
+                        write_url(dst, &mut s);
+                        s.push_str("}{");
+                        s
+                    };
+                    self.write(&s, out)?;
+                }
+            },
+            Container::Image(src, ty) => {
+                if !matches!(ty, SpanLinkType::Unresolved) {
+                    let mut s = "\\includegraphics{".to_string();
+                    write_url(src, &mut s);
+                    s.push('}');
+                    self.write(&s, out)?;
+                    self.ignore += 1;
+                }
+            }
+            Container::Verbatim => self.write("\\texttt{", out)?,
+            Container::Math { display } => {
+                self.write(if *display { "\\[" } else { "\\(" }, out)?;
+                self.raw = Raw::Verbatim;
+            }
+            Container::Subscript => self.write("\\textsubscript{", out)?,
+            Container::Superscript => self.write("\\textsuperscript{", out)?,
+            Container::Insert => self.write("\\uline{", out)?,
+            Container::Delete => self.write("\\sout{", out)?,
+            Container::Strong => self.write("\\textbf{", out)?,
+            Container::Emphasis => self.write("\\emph{", out)?,
+            Container::Mark => self.write("\\hl{", out)?,
+        }
+        Ok(())
+    }
+
+    fn exit<W>(&mut self, c: &Container<'s>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => {
+                self.blocks.pop();
+                self.write("\\end{quote}\n", out)?;
+            }
+            Container::List { kind, .. } => {
+                if let ListKind::Ordered { .. } = kind {
+                    self.enumerate -= 1;
+                    self.write("\\end{enumerate}\n", out)?;
+                } else {
+                    self.write("\\end{itemize}\n", out)?;
+                }
+            }
+            Container::ListItem | Container::TaskListItem { .. } => {
+                if !self.blocks.pop().unwrap_or(true) {
+                    // empty item
+                    self.write("\n", out)?;
+                }
+            }
+            Container::DescriptionList => self.write("\\end{description}\n", out)?,
+            Container::DescriptionTerm => {
+                self.write("}] ", out)?;
+                self.blocks.push(false);
+            }
+            Container::DescriptionDetails => {
+                if !self.blocks.pop().unwrap_or(true) {
+                    self.write("\n", out)?;
+                }
+            }
+            Container::TableCell { .. } => {
+                let cell = self.bufs.pop().unwrap_or_default();
+                if let Some((_, cells)) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    cells.push(cell);
+                }
+            }
+            Container::Caption => {
+                let caption = self.bufs.pop();
+                if let Some(table) = &mut self.table {
+                    table.caption = caption;
+                }
+            }
+            Container::Table => self.table_end(out)?,
+            Container::Paragraph => self.write("\n", out)?,
+            Container::Heading { id, .. } => {
+                self.write(&format!("}}\\label{{{}}}\n", id), out)?;
+            }
+            Container::CodeBlock { .. } => {
+                self.raw_block_end(out)?;
+                self.write("\\end{verbatim}\n", out)?;
+            }
+            Container::RawBlock { .. } => self.raw_block_end(out)?,
+            Container::RawInline { .. } => self.raw = Raw::None,
+            Container::Math { display } => {
+                self.raw = Raw::None;
+                self.write(if *display { "\\]" } else { "\\)" }, out)?;
+            }
+            Container::Link(_, LinkType::Span(SpanLinkType::Unresolved)) => {}
+            Container::Link(_, LinkType::Span(..))
+            | Container::Verbatim
+            | Container::Subscript
+            | Container::Superscript
+            | Container::Insert
+            | Container::Delete
+            | Container::Strong
+            | Container::Emphasis
+            | Container::Mark => self.write("}", out)?,
+            Container::Link(..)
+            | Container::Image(..)
+            | Container::Footnote { .. }
+            | Container::LinkDefinition { .. }
+            | Container::TableRow { .. }
+            | Container::Section { .. }
+            | Container::Div { .. }
+            | Container::Span => {}
+        }
+        Ok(())
+    }
+
+    /// Write the buffered content of a verbatim block, ending with a newline.
+    fn raw_block_end<W>(&mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        self.raw = Raw::None;
+        let mut content = self.bufs.pop().unwrap_or_default();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        self.write(&content, out)
+    }
+
+    fn footnote<W>(&mut self, label: &'e str, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let range = if let Some(range) = self.footnotes.get(label) {
+            range.clone()
+        } else {
+            return Ok(());
+        };
+        let key = footnote_key(label);
+
+        if !self.written.insert(label) {
+            // already written, refer to the existing footnote number
+            return self.write(&format!("\\textsuperscript{{\\ref{{{}}}}}", key), out);
+        }
+
+        let (raw, ignore) = (self.raw, self.ignore);
+        self.raw = Raw::None;
+        self.bufs.push(String::new());
+        self.blocks.push(false);
+        self.render_range(range, out)?;
+        self.blocks.pop();
+        let content = self.bufs.pop().unwrap_or_default();
+        self.raw = raw;
+        self.ignore = ignore;
+
+        self.write(
+            &format!("\\footnote{{{}\\label{{{}}}}}", content.trim_end(), key),
+            out,
+        )
+    }
+
+    fn table_end<W>(&mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let table = if let Some(table) = self.table.take() {
+            table
+        } else {
+            return Ok(());
+        };
+
+        let columns: String = table
+            .alignments
+            .iter()
+            .map(|a| match a {
+                Alignment::Center => 'c',
+                Alignment::Right => 'r',
+                Alignment::Left | Alignment::Unspecified => 'l',
+            })
+            .collect();
+
+        let mut s = "\\begin{table}[htbp]\n\\centering\n".to_string();
+        if let Some(caption) = &table.caption {
+            s.push_str("\\caption{");
+            s.push_str(caption.trim());
+            s.push_str("}\n");
+        }
+        s.push_str("\\begin{tabular}{");
+        s.push_str(&columns);
+        s.push_str("}\n\\hline\n");
+        let mut prev_head = false;
+        for (head, cells) in &table.rows {
+            if prev_head && !head {
+                s.push_str("\\hline\n");
+            }
+            s.push_str(&cells.join(" & "));
+            s.push_str(" \\\\\n");
+            prev_head = *head;
+        }
+        s.push_str("\\hline\n\\end{tabular}\n\\end{table}\n");
+        self.write(&s, out)
+    }
+}
+
+/// Key used to label a footnote, in order to refer to it from multiple references.
+fn footnote_key(label: &str) -> String {
+    let label: String = label
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("fn:{}", label)
+}
+
+fn write_escape(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' | '}' | '#' | '$' | '%' | '&' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '^' => out.push_str("\\textasciicircum{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '<' => out.push_str("\\textless{}"),
+            '>' => out.push_str("\\textgreater{}"),
+            '|' => out.push_str("\\textbar{}"),
+            '`' => out.push_str("\\textasciigrave{}"),
+            '\'' => out.push_str("\\textquotesingle{}"),
+            '"' => out.push_str("\\textquotedbl{}"),
+            '-' if out.ends_with('-') => {
+                // avoid ligatures of consecutive hyphens, which were not dashes in the source
+                out.push_str("{}-");
+            }
+            _ => out.push(c),
+        }
+    }
+}
+
+fn write_url(s: &str, out: &mut String) {
+    for c in s.chars() {
+        if matches!(c, '#' | '%' | '{' | '}' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Render;
+
+    macro_rules! test_latex {
+        ($src:expr, $expected:expr $(,)?) => {
+            let mut actual = String::new();
+            super::Renderer::default()
+                .push(crate::Parser::new($src), &mut actual)
+                .unwrap();
+            assert_eq!(actual, $expected);
+        };
+    }
+
+    #[test]
+    fn para() {
+        test_latex!(
+            concat!(
+                "a\n", //
+                "b\\\n", "c\n", "\n", "d\n",
+            ),
+            concat!(
+                "a\n", //
+                "b\\\\\n", "c\n", "\n", "d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn heading() {
+        test_latex!(
+            concat!(
+                "# a\n", //
+                "\n", "## b\n", "\n", "{#c}\n", "#### d\n",
+            ),
+            concat!(
+                "\\section{a}\\label{a}\n", //
+                "\n",
+                "\\subsection{b}\\label{b}\n",
+                "\n",
+                "\\paragraph{d}\\label{c}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_ordered() {
+        test_latex!(
+            concat!(
+                "3. a\n", //
+                "4. b\n",
+                "\n",
+                "   (a) c\n",
+            ),
+            concat!(
+                "\\begin{enumerate}\n", //
+                "\\setcounter{enumi}{2}\n",
+                "\\item a\n",
+                "\\item b\n",
+                "\n",
+                "\\begin{enumerate}\n",
+                "\\renewcommand{\\labelenumii}{(\\alph{enumii})}\n",
+                "\\item c\n",
+                "\\end{enumerate}\n",
+                "\\end{enumerate}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_task() {
+        test_latex!(
+            concat!(
+                "- [ ] a\n", //
+                "- [x] b\n",
+            ),
+            concat!(
+                "\\begin{itemize}\n", //
+                "\\item[$\\square$] a\n",
+                "\\item[$\\boxtimes$] b\n",
+                "\\end{itemize}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn description_list() {
+        test_latex!(
+            concat!(
+                ": a\n", //
+                "\n", "  b\n", "\n", "  c\n",
+            ),
+            concat!(
+                "\\begin{description}\n", //
+                "\\item[{a}] b\n",
+                "\n",
+                "c\n",
+                "\\end{description}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn blockquote() {
+        test_latex!(
+            "> a\n",
+            concat!(
+                "\\begin{quote}\n", //
+                "a\n",
+                "\\end{quote}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn code_block() {
+        test_latex!(
+            concat!(
+                "```rust\n", //
+                "fn main() {}\n",
+                "```\n",
+            ),
+            concat!(
+                "\\begin{verbatim}\n", //
+                "fn main() {}\n",
+                "\\end{verbatim}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn raw() {
+        test_latex!(
+            concat!(
+                "``` =latex\n", //
+                "\\newpage\n",
+                "```\n",
+                "\n",
+                "``` =html\n",
+                "<br>\n",
+                "```\n",
+                "\n",
+                "`\\LaTeX`{=latex}`<br>`{=html}\n",
+            ),
+            concat!(
+                "\\newpage\n", //
+                "\n",
+                "\\LaTeX\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn math() {
+        test_latex!(
+            "$`a_1^2` $$`\\frac{1}{2}`\n",
+            "\\(a_1^2\\) \\[\\frac{1}{2}\\]\n",
+        );
+    }
+
+    #[test]
+    fn footnote() {
+        test_latex!(
+            concat!(
+                "a[^b] c[^b]\n", //
+                "\n",
+                "[^b]: d\n",
+                "\n",
+                "    e\n",
+            ),
+            concat!(
+                "a\\footnote{d\n", //
+                "\n",
+                "e\\label{fn:b}} c\\textsuperscript{\\ref{fn:b}}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn table() {
+        test_latex!(
+            concat!(
+                "|a|b|\n", //
+                "|-|-:|\n", "|c|d|\n", "^ e\n",
+            ),
+            concat!(
+                "\\begin{table}[htbp]\n", //
+                "\\centering\n",
+                "\\caption{e}\n",
+                "\\begin{tabular}{lr}\n",
+                "\\hline\n",
+                "a & b \\\\\n",
+                "\\hline\n",
+                "c & d \\\\\n",
+                "\\hline\n",
+                "\\end{tabular}\n",
+                "\\end{table}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn inline() {
+        test_latex!(
+            "*a* _b_ ^c^ ~d~ {+e+} {-f-} {=g=} `h_i` [j](k#l) [m](#n) <https://o>",
+            concat!(
+                "\\textbf{a} \\emph{b} \\textsuperscript{c} \\textsubscript{d} \\uline{e} ",
+                "\\sout{f} \\hl{g} \\texttt{h\\_i} \\href{k\\#l}{j} \\hyperref[n]{m} ",
+                "\\url{https://o}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn escape() {
+        test_latex!(
+            "\"a\" 'b' c--d \\-\\- 100% $1 & #2 \\\\ {x}",
+            "``a'' `b' c--d -{}- 100\\% \\$1 \\& \\#2 \\textbackslash{} \\{x\\}\n",
+        );
+    }
+}
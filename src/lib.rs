@@ -2,11 +2,32 @@
 //!
 //! The main entry is through [`Parser`] which constructs an [`Iterator`] of [`Event`]s. The events
 //! can then be processed before rendering them via the [`Render`] trait. This crate provides an
-//! [`html`] module that implements an HTML renderer.
+//! [`html`] module that implements an HTML renderer. The [`symbol`] module can be used to replace
+//! symbols such as `:smile:` before rendering. Large inputs can be parsed incrementally from a
+//! reader with [`StreamParser`], and edited inputs can be parsed again incrementally with
+//! [`IncrementalParser`].
 //!
 //! # Feature flags
 //!
+//! - `djot`: build the djot module that renders events back to Djot or formats Djot documents,
+//!   and the `fmt` subcommand of the binary.
+//! - `emoji`: include a table of emoji in the symbol module, used to resolve emoji symbols such
+//!   as `:smile:`.
+//! - `highlight`: include a syntax highlighter for code blocks in the html module, see
+//!   `html::Highlighter`.
 //! - `html` (default): build the html module and a binary that converts djot to HTML.
+//! - `json`: build the json module that renders events to the JSON AST of djot.js.
+//! - `latex`: build the latex module that renders events to LaTeX.
+//! - `lsp`: build a binary, `jotdown-lsp`, that provides a language server for djot over stdio.
+//! - `markdown`: build the markdown module that renders events to CommonMark with GitHub
+//!   Flavored Markdown extensions.
+//! - `mathml`: build the mathml module that converts TeX math to MathML, and allow the html
+//!   module to emit MathML for math.
+//! - `pandoc`: build the pandoc module that renders events to the JSON AST of Pandoc.
+//! - `serde`: implement `Serialize` and `Deserialize` from [serde](https://serde.rs) for
+//!   [`Event`] and the types it contains, e.g. to store or send parsed events.
+//! - `text`: build the text module that renders events to plain text without markup.
+//! - `tree`: build the tree module that builds a document tree from events.
 //!
 //! # Examples
 //!
@@ -48,17 +69,48 @@ use std::fmt::Write as FmtWrite;
 use std::io;
 use std::ops::Range;
 
+#[cfg(feature = "djot")]
+pub mod djot;
 #[cfg(feature = "html")]
 pub mod html;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "latex")]
+pub mod latex;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+#[cfg(feature = "mathml")]
+pub mod mathml;
+#[cfg(feature = "pandoc")]
+pub mod pandoc;
+pub mod symbol;
+#[cfg(feature = "text")]
+pub mod text;
+#[cfg(feature = "tree")]
+pub mod tree;
 
 mod attr;
 mod block;
+mod diagnostic;
+#[cfg(feature = "highlight")]
+mod highlight;
+mod incremental;
 mod inline;
 mod lex;
+#[cfg(any(feature = "djot", feature = "markdown"))]
+mod lines;
+mod position;
+mod stream;
+mod toc;
 
 pub use attr::{
     AttributeKind, AttributeValue, AttributeValueParts, Attributes, ParseAttributesError,
 };
+pub use diagnostic::{Diagnostic, DiagnosticKind};
+pub use incremental::{ChangedEvents, IncrementalParser};
+pub use position::{LineIndex, Position, PositionEncoding};
+pub use stream::StreamParser;
+pub use toc::{Heading, Toc, TocEntry};
 
 type CowStr<'s> = std::borrow::Cow<'s, str>;
 
@@ -203,6 +255,7 @@ impl<'s> AsRef<Event<'s>> for &Event<'s> {
 /// events representing its content, and finally a [`Event::End`]. Atomic elements without any
 /// inside elements are represented by a single event.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event<'s> {
     /// Start of a container.
     ///
@@ -277,7 +330,7 @@ pub enum Event<'s> {
     ///     &[
     ///         Event::Start(Container::Paragraph, Attributes::new()),
     ///         Event::Str("txt".into()),
-    ///         Event::FootnoteReference("nb"),
+    ///         Event::FootnoteReference("nb".into()),
     ///         Event::Str(".".into()),
     ///         Event::End(Container::Paragraph),
     ///     ],
@@ -295,7 +348,7 @@ pub enum Event<'s> {
     /// );
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
-    FootnoteReference(&'s str),
+    FootnoteReference(CowStr<'s>),
     /// A symbol, by default rendered literally but may be treated specially.
     ///
     /// # Examples
@@ -677,6 +730,60 @@ pub enum Event<'s> {
     Attributes(Attributes<'s>),
 }
 
+impl Event<'_> {
+    /// Convert into an event that owns all of its content and no longer borrows from the input.
+    ///
+    /// Useful for e.g. storing events after the input has been dropped or sending them to other
+    /// threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let events: Vec<Event<'static>> = {
+    ///     let src = String::from("{.a}\n:::\nword\n:::\n");
+    ///     Parser::new(&src).map(Event::into_owned).collect()
+    /// };
+    /// assert_eq!(
+    ///     &events,
+    ///     &[
+    ///         Event::Start(
+    ///             Container::Div { class: "".into() },
+    ///             [(AttributeKind::Class, "a".into())].into_iter().collect(),
+    ///         ),
+    ///         Event::Start(Container::Paragraph, Attributes::new()),
+    ///         Event::Str("word".into()),
+    ///         Event::End(Container::Paragraph),
+    ///         Event::End(Container::Div { class: "".into() }),
+    ///     ],
+    /// );
+    /// ```
+    #[must_use]
+    pub fn into_owned(self) -> Event<'static> {
+        match self {
+            Self::Start(c, attrs) => Event::Start(c.into_owned(), attrs.into_owned()),
+            Self::End(c) => Event::End(c.into_owned()),
+            Self::Str(s) => Event::Str(s.into_owned().into()),
+            Self::FootnoteReference(label) => Event::FootnoteReference(label.into_owned().into()),
+            Self::Symbol(sym) => Event::Symbol(sym.into_owned().into()),
+            Self::LeftSingleQuote => Event::LeftSingleQuote,
+            Self::RightSingleQuote => Event::RightSingleQuote,
+            Self::LeftDoubleQuote => Event::LeftDoubleQuote,
+            Self::RightDoubleQuote => Event::RightDoubleQuote,
+            Self::Ellipsis => Event::Ellipsis,
+            Self::EnDash => Event::EnDash,
+            Self::EmDash => Event::EmDash,
+            Self::NonBreakingSpace => Event::NonBreakingSpace,
+            Self::Softbreak => Event::Softbreak,
+            Self::Hardbreak => Event::Hardbreak,
+            Self::Escape => Event::Escape,
+            Self::Blankline => Event::Blankline,
+            Self::ThematicBreak(attrs) => Event::ThematicBreak(attrs.into_owned()),
+            Self::Attributes(attrs) => Event::Attributes(attrs.into_owned()),
+        }
+    }
+}
+
 /// A container that may contain other elements.
 ///
 /// There are three types of containers:
@@ -685,6 +792,7 @@ pub enum Event<'s> {
 /// - block leaf, may only contain inline elements,
 /// - block container, may contain any block-level elements.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Container<'s> {
     /// A blockquote element.
     ///
@@ -930,13 +1038,13 @@ pub enum Container<'s> {
     ///         Event::End(Container::Paragraph),
     ///         Event::Blankline,
     ///         Event::Start(
-    ///             Container::Footnote { label: "nb" },
+    ///             Container::Footnote { label: "nb".into() },
     ///             Attributes::new(),
     ///         ),
     ///         Event::Start(Container::Paragraph, Attributes::new()),
     ///         Event::Str("actually..".into()),
     ///         Event::End(Container::Paragraph),
-    ///         Event::End(Container::Footnote { label: "nb" }),
+    ///         Event::End(Container::Footnote { label: "nb".into() }),
     ///     ],
     /// );
     /// let html = concat!(
@@ -952,7 +1060,7 @@ pub enum Container<'s> {
     /// );
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
-    Footnote { label: &'s str },
+    Footnote { label: CowStr<'s> },
     /// A table element.
     ///
     /// # Examples
@@ -1130,13 +1238,13 @@ pub enum Container<'s> {
     ///     &events,
     ///     &[
     ///         Event::Start(
-    ///             Container::Div { class: "note" },
+    ///             Container::Div { class: "note".into() },
     ///             Attributes::new(),
     ///         ),
     ///         Event::Start(Container::Paragraph, Attributes::new()),
     ///         Event::Str("this is a note".into()),
     ///         Event::End(Container::Paragraph),
-    ///         Event::End(Container::Div { class: "note" }),
+    ///         Event::End(Container::Div { class: "note".into() }),
     ///     ],
     /// );
     /// let html = concat!(
@@ -1146,7 +1254,7 @@ pub enum Container<'s> {
     /// );
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
-    Div { class: &'s str },
+    Div { class: CowStr<'s> },
     /// A paragraph.
     Paragraph,
     /// A heading.
@@ -1258,17 +1366,17 @@ pub enum Container<'s> {
     ///     &events,
     ///     &[
     ///         Event::Start(
-    ///             Container::LinkDefinition { label: "label" },
+    ///             Container::LinkDefinition { label: "label".into() },
     ///             Attributes::new(),
     ///         ),
     ///         Event::Str("url".into()),
-    ///         Event::End(Container::LinkDefinition { label: "label" }),
+    ///         Event::End(Container::LinkDefinition { label: "label".into() }),
     ///     ],
     /// );
     /// let html = "\n";
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
-    LinkDefinition { label: &'s str },
+    LinkDefinition { label: CowStr<'s> },
     /// A block with raw markup for a specific output format.
     ///
     /// # Examples
@@ -1285,17 +1393,17 @@ pub enum Container<'s> {
     ///     &events,
     ///     &[
     ///         Event::Start(
-    ///             Container::RawBlock { format: "html" },
+    ///             Container::RawBlock { format: "html".into() },
     ///             Attributes::new(),
     ///         ),
     ///         Event::Str("<tag>x</tag>".into()),
-    ///         Event::End(Container::RawBlock { format: "html" }),
+    ///         Event::End(Container::RawBlock { format: "html".into() }),
     ///     ],
     /// );
     /// let html = "<tag>x</tag>\n";
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
-    RawBlock { format: &'s str },
+    RawBlock { format: CowStr<'s> },
     /// A block with code in a specific language.
     ///
     /// # Examples
@@ -1312,11 +1420,11 @@ pub enum Container<'s> {
     ///     &events,
     ///     &[
     ///         Event::Start(
-    ///             Container::CodeBlock { language: "html" },
+    ///             Container::CodeBlock { language: "html".into() },
     ///             Attributes::new(),
     ///         ),
     ///         Event::Str("<tag>x</tag>\n".into()),
-    ///         Event::End(Container::CodeBlock { language: "html" }),
+    ///         Event::End(Container::CodeBlock { language: "html".into() }),
     ///     ],
     /// );
     /// let html = concat!(
@@ -1325,7 +1433,7 @@ pub enum Container<'s> {
     /// );
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
-    CodeBlock { language: &'s str },
+    CodeBlock { language: CowStr<'s> },
     /// An inline divider element.
     ///
     /// # Examples
@@ -1491,11 +1599,11 @@ pub enum Container<'s> {
     ///         Event::End(Container::Paragraph),
     ///         Event::Blankline,
     ///         Event::Start(
-    ///             Container::LinkDefinition { label: "label" },
+    ///             Container::LinkDefinition { label: "label".into() },
     ///             Attributes::new(),
     ///         ),
     ///         Event::Str("url".into()),
-    ///         Event::End(Container::LinkDefinition { label: "label" }),
+    ///         Event::End(Container::LinkDefinition { label: "label".into() }),
     ///     ],
     /// );
     /// let html = concat!(
@@ -1611,17 +1719,17 @@ pub enum Container<'s> {
     ///     &[
     ///         Event::Start(Container::Paragraph, Attributes::new()),
     ///         Event::Start(
-    ///             Container::RawInline { format: "html" }, Attributes::new(),
+    ///             Container::RawInline { format: "html".into() }, Attributes::new(),
     ///         ),
     ///         Event::Str("<tag>a</tag>".into()),
-    ///         Event::End(Container::RawInline { format: "html" }),
+    ///         Event::End(Container::RawInline { format: "html".into() }),
     ///         Event::End(Container::Paragraph),
     ///     ],
     /// );
     /// let html = "<p><tag>a</tag></p>\n";
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
-    RawInline { format: &'s str },
+    RawInline { format: CowStr<'s> },
     /// A subscripted element.
     ///
     /// # Examples
@@ -1779,6 +1887,68 @@ pub enum Container<'s> {
 }
 
 impl Container<'_> {
+    /// Convert into a container that owns all of its content and no longer borrows from the
+    /// input.
+    #[must_use]
+    pub fn into_owned(self) -> Container<'static> {
+        match self {
+            Self::Blockquote => Container::Blockquote,
+            Self::List { kind, tight } => Container::List { kind, tight },
+            Self::ListItem => Container::ListItem,
+            Self::TaskListItem { checked } => Container::TaskListItem { checked },
+            Self::DescriptionList => Container::DescriptionList,
+            Self::DescriptionDetails => Container::DescriptionDetails,
+            Self::Footnote { label } => Container::Footnote {
+                label: label.into_owned().into(),
+            },
+            Self::Table => Container::Table,
+            Self::TableRow { head } => Container::TableRow { head },
+            Self::Section { id } => Container::Section {
+                id: id.into_owned().into(),
+            },
+            Self::Div { class } => Container::Div {
+                class: class.into_owned().into(),
+            },
+            Self::Paragraph => Container::Paragraph,
+            Self::Heading {
+                level,
+                has_section,
+                id,
+            } => Container::Heading {
+                level,
+                has_section,
+                id: id.into_owned().into(),
+            },
+            Self::TableCell { alignment, head } => Container::TableCell { alignment, head },
+            Self::Caption => Container::Caption,
+            Self::DescriptionTerm => Container::DescriptionTerm,
+            Self::LinkDefinition { label } => Container::LinkDefinition {
+                label: label.into_owned().into(),
+            },
+            Self::RawBlock { format } => Container::RawBlock {
+                format: format.into_owned().into(),
+            },
+            Self::CodeBlock { language } => Container::CodeBlock {
+                language: language.into_owned().into(),
+            },
+            Self::Span => Container::Span,
+            Self::Link(url, ty) => Container::Link(url.into_owned().into(), ty),
+            Self::Image(src, ty) => Container::Image(src.into_owned().into(), ty),
+            Self::Verbatim => Container::Verbatim,
+            Self::Math { display } => Container::Math { display },
+            Self::RawInline { format } => Container::RawInline {
+                format: format.into_owned().into(),
+            },
+            Self::Subscript => Container::Subscript,
+            Self::Superscript => Container::Superscript,
+            Self::Insert => Container::Insert,
+            Self::Delete => Container::Delete,
+            Self::Strong => Container::Strong,
+            Self::Emphasis => Container::Emphasis,
+            Self::Mark => Container::Mark,
+        }
+    }
+
     /// Is a block element.
     #[must_use]
     pub fn is_block(&self) -> bool {
@@ -1860,6 +2030,7 @@ impl Container<'_> {
 
 /// Alignment of a table column.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Alignment {
     Unspecified,
     Left,
@@ -1869,6 +2040,7 @@ pub enum Alignment {
 
 /// The type of an inline span link.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SpanLinkType {
     /// E.g. `[text](url)`
     Inline,
@@ -1880,6 +2052,7 @@ pub enum SpanLinkType {
 
 /// The type of an inline link.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LinkType {
     /// E.g. `[text](url)`.
     Span(SpanLinkType),
@@ -1891,6 +2064,7 @@ pub enum LinkType {
 
 /// Character used to create an unordered list item.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ListBulletType {
     /// `-`
     Dash,
@@ -1927,8 +2101,8 @@ impl From<ListBulletType> for u8 {
     fn from(t: ListBulletType) -> Self {
         match t {
             ListBulletType::Dash => b'-',
-            ListBulletType::Star => b'-',
-            ListBulletType::Plus => b'-',
+            ListBulletType::Star => b'*',
+            ListBulletType::Plus => b'+',
         }
     }
 }
@@ -1941,6 +2115,7 @@ impl From<ListBulletType> for char {
 
 /// The type of a list.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ListKind {
     /// A bullet list.
     Unordered(ListBulletType),
@@ -1956,6 +2131,7 @@ pub enum ListKind {
 
 /// Numbering type of an ordered list.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderedListNumbering {
     /// Decimal numbering, e.g. `1)`.
     Decimal,
@@ -1971,6 +2147,7 @@ pub enum OrderedListNumbering {
 
 /// Style of an ordered list.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderedListStyle {
     /// Number is followed by a period, e.g. `1.`.
     Period,
@@ -2032,12 +2209,73 @@ impl OrderedListNumbering {
             }
         }
     }
+
+    /// Inverse of [`Self::parse_number`].
+    #[cfg(any(feature = "djot", feature = "json", feature = "text"))]
+    fn format_number(self, mut n: u64) -> String {
+        match self {
+            Self::Decimal => n.to_string(),
+            Self::AlphaLower | Self::AlphaUpper => {
+                let d0 = if matches!(self, Self::AlphaLower) {
+                    b'a'
+                } else {
+                    b'A'
+                };
+                let mut s = Vec::new();
+                while n > 0 {
+                    n -= 1;
+                    s.push(d0 + (n % 26) as u8);
+                    n /= 26;
+                }
+                s.reverse();
+                String::from_utf8(s).unwrap()
+            }
+            Self::RomanLower | Self::RomanUpper => {
+                const DIGITS: [(u64, &str); 13] = [
+                    (1000, "m"),
+                    (900, "cm"),
+                    (500, "d"),
+                    (400, "cd"),
+                    (100, "c"),
+                    (90, "xc"),
+                    (50, "l"),
+                    (40, "xl"),
+                    (10, "x"),
+                    (9, "ix"),
+                    (5, "v"),
+                    (4, "iv"),
+                    (1, "i"),
+                ];
+                let mut s = String::new();
+                for (v, d) in DIGITS {
+                    while n >= v {
+                        s.push_str(d);
+                        n -= v;
+                    }
+                }
+                if matches!(self, Self::RomanUpper) {
+                    s.make_ascii_uppercase();
+                }
+                s
+            }
+        }
+    }
 }
 
 impl OrderedListStyle {
     fn number(self, marker: &str) -> &str {
         &marker[usize::from(matches!(self, Self::ParenParen))..marker.len() - 1]
     }
+
+    /// Inverse of [`Self::number`].
+    #[cfg(any(feature = "djot", feature = "json", feature = "text"))]
+    fn marker(self, number: &str) -> String {
+        match self {
+            Self::Period => format!("{}.", number),
+            Self::Paren => format!("{})", number),
+            Self::ParenParen => format!("({})", number),
+        }
+    }
 }
 
 #[cfg(not(feature = "deterministic"))]
@@ -2062,6 +2300,9 @@ type Set<T> = std::collections::BTreeSet<T>;
 pub struct Parser<'s> {
     src: &'s str,
 
+    /// Syntax that is enabled.
+    syntax: Syntax,
+
     /// Block tree parsed at first.
     blocks: std::iter::Peekable<std::vec::IntoIter<block::Event<'s>>>,
 
@@ -2079,12 +2320,372 @@ pub struct Parser<'s> {
 
     /// Inline parser.
     inline_parser: inline::Parser<'s>,
+
+    /// Labels of link definitions used by reference links, if diagnostics are collected.
+    used_definitions: Option<Set<&'s str>>,
+
+    /// Resolved reference links and images, if recorded.
+    references: Option<Vec<Reference<'s>>>,
+
+    /// Resolver of references that match neither a link definition nor a heading.
+    link_resolver: Option<std::rc::Rc<dyn LinkResolver<'s> + 's>>,
+}
+
+/// A resolver of link references that are not defined in the input, see
+/// [`Parser::with_link_resolver`].
+///
+/// The trait is implemented for closures that take the label of a reference, and for maps from
+/// labels to URLs.
+pub trait LinkResolver<'s> {
+    /// Resolve the label of a reference to a URL and the attributes of the link, or `None` if the
+    /// reference is unresolved.
+    fn resolve(&self, label: &str) -> Option<(CowStr<'s>, Attributes<'s>)>;
+}
+
+impl<'s, F> LinkResolver<'s> for F
+where
+    F: Fn(&str) -> Option<(CowStr<'s>, Attributes<'s>)>,
+{
+    fn resolve(&self, label: &str) -> Option<(CowStr<'s>, Attributes<'s>)> {
+        self(label)
+    }
+}
+
+impl<'s, K, V, S> LinkResolver<'s> for std::collections::HashMap<K, V, S>
+where
+    K: std::borrow::Borrow<str> + std::hash::Hash + Eq,
+    V: AsRef<str>,
+    S: std::hash::BuildHasher,
+{
+    fn resolve(&self, label: &str) -> Option<(CowStr<'s>, Attributes<'s>)> {
+        self.get(label)
+            .map(|url| (url.as_ref().to_string().into(), Attributes::new()))
+    }
+}
+
+impl<'s, K, V> LinkResolver<'s> for std::collections::BTreeMap<K, V>
+where
+    K: std::borrow::Borrow<str> + Ord,
+    V: AsRef<str>,
+{
+    fn resolve(&self, label: &str) -> Option<(CowStr<'s>, Attributes<'s>)> {
+        self.get(label)
+            .map(|url| (url.as_ref().to_string().into(), Attributes::new()))
+    }
+}
+
+/// A reference link or image that was resolved, see [`Parser::with_references`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Reference<'s> {
+    /// Tag of the reference, i.e. the label of the link definition or heading it refers to.
+    pub(crate) tag: CowStr<'s>,
+    /// Number of attributes at the start of the attributes of the link or image that were
+    /// obtained from the link definition or [`LinkResolver`], rather than written on the link or
+    /// image itself.
+    pub(crate) definition_attributes: usize,
+}
+
+/// A generator of heading ids, see [`ParserOptions::heading_ids`].
+///
+/// The trait is implemented for closures that take the text of the heading and return an id.
+pub trait HeadingIdGenerator {
+    /// Create an id from the text of a heading, with all formatting removed.
+    fn heading_id(&mut self, text: &str) -> String;
+}
+
+impl<F> HeadingIdGenerator for F
+where
+    F: FnMut(&str) -> String,
+{
+    fn heading_id(&mut self, text: &str) -> String {
+        self(text)
+    }
+}
+
+/// The default generator of heading ids, used by [`Parser::new`].
+///
+/// Whitespace is replaced by `-` and punctuation other than `-` and `_` is removed.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// assert_eq!(DefaultHeadingIds.heading_id("Some *Title*, v2!"), "Some-Title-v2");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultHeadingIds;
+
+impl HeadingIdGenerator for DefaultHeadingIds {
+    fn heading_id(&mut self, text: &str) -> String {
+        let mut id = String::new();
+        let mut last_whitespace = true;
+        for c in text.chars() {
+            if c.is_ascii_whitespace() {
+                if !last_whitespace {
+                    last_whitespace = true;
+                    id.push('-');
+                }
+            } else if !c.is_ascii_punctuation() || matches!(c, '-' | '_') {
+                id.push(c);
+                last_whitespace = false;
+            }
+        }
+        id.drain(id.trim_end_matches('-').len()..);
+        id
+    }
+}
+
+/// Kinds of punctuation that are replaced by typographic characters, see
+/// [`ParserOptions::smart_punctuation`].
+///
+/// All kinds are replaced by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmartPunctuation {
+    /// Replace straight quotes with [`Event::LeftSingleQuote`], [`Event::RightSingleQuote`],
+    /// [`Event::LeftDoubleQuote`] and [`Event::RightDoubleQuote`].
+    pub quotes: bool,
+    /// Replace `...` with [`Event::Ellipsis`].
+    pub ellipsis: bool,
+    /// Replace `--` and `---` with [`Event::EnDash`] and [`Event::EmDash`].
+    pub dashes: bool,
+}
+
+impl Default for SmartPunctuation {
+    fn default() -> Self {
+        Self {
+            quotes: true,
+            ellipsis: true,
+            dashes: true,
+        }
+    }
+}
+
+/// Options for a [`Parser`], see [`Parser::with_options`].
+///
+/// All syntax is enabled by default. Disabled syntax has no special meaning and is typically parsed
+/// as plain text or as a paragraph.
+///
+/// # Examples
+///
+/// Accept only a restricted subset of Djot, e.g. for user-generated comments:
+///
+/// ```
+/// # #[cfg(feature = "html")]
+/// # {
+/// # use jotdown::*;
+/// let options = ParserOptions::default()
+///     .tables(false)
+///     .raw(false)
+///     .math(false)
+///     .sections(false)
+///     .attributes(false);
+/// let src = "`<script>`{=html} $`x` {.big}\n";
+/// let html = html::render_to_string(Parser::with_options(src, options));
+/// assert_eq!(html, "<p><code>&lt;script&gt;</code>{=html} $<code>x</code> {.big}</p>\n");
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParserOptions<G = DefaultHeadingIds> {
+    syntax: Syntax,
+    heading_ids: G,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            syntax: Syntax::default(),
+            heading_ids: DefaultHeadingIds,
+        }
+    }
+}
+
+impl<G> ParserOptions<G> {
+    /// Enable or disable tables.
+    #[must_use]
+    pub fn tables(mut self, enabled: bool) -> Self {
+        self.syntax.tables = enabled;
+        self
+    }
+
+    /// Enable or disable footnotes and footnote references.
+    #[must_use]
+    pub fn footnotes(mut self, enabled: bool) -> Self {
+        self.syntax.footnotes = enabled;
+        self
+    }
+
+    /// Enable or disable description lists.
+    #[must_use]
+    pub fn description_lists(mut self, enabled: bool) -> Self {
+        self.syntax.description_lists = enabled;
+        self
+    }
+
+    /// Enable or disable task lists. If disabled, task list items are parsed as unordered list
+    /// items.
+    #[must_use]
+    pub fn task_lists(mut self, enabled: bool) -> Self {
+        self.syntax.task_lists = enabled;
+        self
+    }
+
+    /// Enable or disable raw blocks and raw inlines. If disabled, raw blocks are parsed as code
+    /// blocks and raw inlines as verbatim.
+    #[must_use]
+    pub fn raw(mut self, enabled: bool) -> Self {
+        self.syntax.raw = enabled;
+        self
+    }
+
+    /// Enable or disable inline and display math. If disabled, math is parsed as verbatim.
+    #[must_use]
+    pub fn math(mut self, enabled: bool) -> Self {
+        self.syntax.math = enabled;
+        self
+    }
+
+    /// Enable or disable automatic sections around top-level headings.
+    #[must_use]
+    pub fn sections(mut self, enabled: bool) -> Self {
+        self.syntax.sections = enabled;
+        self
+    }
+
+    /// Enable or disable block and inline attributes, including spans, which require
+    /// attributes.
+    #[must_use]
+    pub fn attributes(mut self, enabled: bool) -> Self {
+        self.syntax.attributes = enabled;
+        self
+    }
+
+    /// Enable or disable symbols, e.g. `:smile:`.
+    #[must_use]
+    pub fn symbols(mut self, enabled: bool) -> Self {
+        self.syntax.symbols = enabled;
+        self
+    }
+
+    /// Set the kinds of punctuation that are replaced by typographic characters.
+    ///
+    /// Punctuation of disabled kinds is emitted as plain text, as it appears in the input.
+    ///
+    /// # Examples
+    ///
+    /// Keep quotes and dashes as ASCII but still replace ellipses:
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let options = ParserOptions::default().smart_punctuation(SmartPunctuation {
+    ///     quotes: false,
+    ///     dashes: false,
+    ///     ..SmartPunctuation::default()
+    /// });
+    /// let text: String = Parser::with_options("\"a\" -- b...", options)
+    ///     .filter_map(|e| match e {
+    ///         Event::Str(s) => Some(s),
+    ///         Event::Ellipsis => Some("…".into()),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(text, "\"a\" -- b…");
+    /// ```
+    #[must_use]
+    pub fn smart_punctuation(mut self, smart_punctuation: SmartPunctuation) -> Self {
+        self.syntax.smart_punctuation = smart_punctuation;
+        self
+    }
+
+    /// Use a custom generator for the ids of headings.
+    ///
+    /// The generated ids are used for the ids of [`Container::Heading`] and
+    /// [`Container::Section`], and for the links of references that resolve to a heading. Headings
+    /// with an explicit id attribute keep their explicit id.
+    ///
+    /// The generator creates an id from the text of the heading. The ids do not need to be
+    /// unique, as `-1`, `-2`, etc. is appended to ids that have already been used by a previous
+    /// heading. An empty id is replaced by `s`.
+    ///
+    /// # Examples
+    ///
+    /// Generate lowercase ids with a prefix:
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let mut generator = |text: &str| format!("doc-{}", DefaultHeadingIds.heading_id(text));
+    /// let options = ParserOptions::default().heading_ids(|text: &str| {
+    ///     generator(text).to_lowercase()
+    /// });
+    /// let src = "# Intro\n\n# Intro\n\n# Getting Started\n\n[Getting Started][]\n";
+    /// let events: Vec<_> = Parser::with_options(src, options)
+    ///     .filter_map(|e| match e {
+    ///         Event::Start(Container::Section { id }, _) => Some(id),
+    ///         Event::Start(Container::Link(url, _), _) => Some(url),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(
+    ///     events,
+    ///     [
+    ///         "doc-intro",
+    ///         "doc-intro-1",
+    ///         "doc-getting-started",
+    ///         "#doc-getting-started",
+    ///     ],
+    /// );
+    /// ```
+    #[must_use]
+    pub fn heading_ids<H>(self, heading_ids: H) -> ParserOptions<H>
+    where
+        H: HeadingIdGenerator,
+    {
+        ParserOptions {
+            syntax: self.syntax,
+            heading_ids,
+        }
+    }
+}
+
+/// Syntax that is enabled in the block and inline parsers.
+#[derive(Clone, Copy, Debug)]
+struct Syntax {
+    tables: bool,
+    footnotes: bool,
+    description_lists: bool,
+    task_lists: bool,
+    raw: bool,
+    math: bool,
+    sections: bool,
+    attributes: bool,
+    symbols: bool,
+    smart_punctuation: SmartPunctuation,
+}
+
+impl Default for Syntax {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            footnotes: true,
+            description_lists: true,
+            task_lists: true,
+            raw: true,
+            math: true,
+            sections: true,
+            attributes: true,
+            symbols: true,
+            smart_punctuation: SmartPunctuation::default(),
+        }
+    }
 }
 
 #[derive(Clone)]
-struct Heading {
+struct PrePassHeading {
     /// Location of heading in src.
     location: u32,
+    /// End of the heading text in src.
+    end: u32,
+    /// Level of the heading.
+    level: u16,
     /// Automatically generated id from heading text.
     id_auto: String,
     /// Text of heading, formatting stripped.
@@ -2099,20 +2700,75 @@ struct PrePass<'s> {
     /// Link definitions and their attributes.
     link_definitions: Map<&'s str, (CowStr<'s>, attr::Attributes<'s>)>,
     /// Cache of all heading ids.
-    headings: Vec<Heading>,
+    headings: Vec<PrePassHeading>,
     /// Indices to headings sorted lexicographically.
     headings_lex: Vec<usize>,
 }
 
+impl<'s> From<inline::Atom<'s>> for Event<'s> {
+    fn from(atom: inline::Atom<'s>) -> Self {
+        match atom {
+            inline::Atom::FootnoteReference { label } => Event::FootnoteReference(label.into()),
+            inline::Atom::Symbol(sym) => Event::Symbol(sym.into()),
+            inline::Atom::Quote { ty, left } => match (ty, left) {
+                (inline::QuoteType::Single, true) => Event::LeftSingleQuote,
+                (inline::QuoteType::Single, false) => Event::RightSingleQuote,
+                (inline::QuoteType::Double, true) => Event::LeftDoubleQuote,
+                (inline::QuoteType::Double, false) => Event::RightDoubleQuote,
+            },
+            inline::Atom::Ellipsis => Event::Ellipsis,
+            inline::Atom::EnDash => Event::EnDash,
+            inline::Atom::EmDash => Event::EmDash,
+            inline::Atom::Nbsp => Event::NonBreakingSpace,
+            inline::Atom::Softbreak => Event::Softbreak,
+            inline::Atom::Hardbreak => Event::Hardbreak,
+            inline::Atom::Escape => Event::Escape,
+        }
+    }
+}
+
+/// Make an id unique by appending `-1`, `-2`, etc. if it has already been used, and mark it as
+/// used. An empty id is replaced by `s`.
+fn unique_id(mut id: String, used_ids: &mut Set<String>) -> String {
+    if used_ids.contains::<str>(&id) || id.is_empty() {
+        if id.is_empty() {
+            id.push('s');
+        }
+        let mut num = 1;
+        id.push('-');
+        let i_num = id.len();
+        write!(id, "{}", num).unwrap();
+        while used_ids.contains::<str>(&id) {
+            num += 1;
+            id.drain(i_num..);
+            write!(id, "{}", num).unwrap();
+        }
+    }
+    used_ids.insert(id.clone());
+    id
+}
+
+/// Append the text of an event with all formatting stripped, as used to generate heading ids and to
+/// match implicit heading references. Only the content of strings is kept, and soft breaks are
+/// replaced by spaces.
+pub(crate) fn push_stripped(text: &mut String, event: &Event) {
+    match event {
+        Event::Str(s) => text.push_str(s),
+        Event::Softbreak => text.push(' '),
+        _ => {}
+    }
+}
+
 impl<'s> PrePass<'s> {
     #[must_use]
     fn new(
         src: &'s str,
         mut blocks: std::slice::Iter<block::Event<'s>>,
         inline_parser: &mut inline::Parser<'s>,
+        id_generator: &mut dyn HeadingIdGenerator,
     ) -> Self {
         let mut link_definitions = Map::new();
-        let mut headings: Vec<Heading> = Vec::new();
+        let mut headings: Vec<PrePassHeading> = Vec::new();
         let mut used_ids: Set<String> = Set::new();
 
         let mut attr_prev: Option<Range<usize>> = None;
@@ -2123,8 +2779,11 @@ impl<'s> PrePass<'s> {
                 })) => {
                     // All link definition tags have to be obtained initially, as references can
                     // appear before the definition.
-                    let attrs = attr_prev.as_ref().map_or_else(Attributes::new, |sp| {
-                        src[sp.clone()].try_into().expect("should be valid")
+                    //
+                    // The exit of the definition is consumed below, so the attributes are reset
+                    // here.
+                    let attrs = attr_prev.take().map_or_else(Attributes::new, |sp| {
+                        src[sp].try_into().expect("should be valid")
                     });
                     let url = if let Some(block::Event {
                         kind: block::EventKind::Inline,
@@ -2161,25 +2820,26 @@ impl<'s> PrePass<'s> {
                     };
                     link_definitions.insert(label, (url, attrs));
                 }
-                block::EventKind::Enter(block::Node::Leaf(block::Leaf::Heading { .. })) => {
+                block::EventKind::Enter(block::Node::Leaf(block::Leaf::Heading {
+                    level, ..
+                })) => {
                     // All headings ids have to be obtained initially, as references can appear
                     // before the heading. Additionally, determining the id requires inline parsing
                     // as formatting must be removed.
                     //
                     // We choose to parse all headers twice instead of caching them.
+                    // the exit of the heading is consumed below, so the attributes are reset here
                     let attrs = attr_prev
-                        .as_ref()
-                        .map(|sp| Attributes::try_from(&src[sp.clone()]).expect("should be valid"));
+                        .take()
+                        .map(|sp| Attributes::try_from(&src[sp]).expect("should be valid"));
                     let id_override = attrs
                         .as_ref()
                         .and_then(|attrs| attrs.get_value("id"))
                         .map(|s| s.to_string());
 
-                    let mut id_auto = String::new();
                     let mut text = String::new();
-                    let mut last_whitespace = true;
                     inline_parser.reset();
-                    let mut last_end = 0;
+                    let mut last_end = e.span.end;
                     loop {
                         let span_inline = blocks.next().and_then(|e| {
                             if matches!(e.kind, block::EventKind::Inline) {
@@ -2193,59 +2853,23 @@ impl<'s> PrePass<'s> {
                             span_inline.as_ref().cloned().unwrap_or(last_end..last_end),
                             span_inline.is_none(),
                         );
-                        inline_parser.for_each(|ev| match ev.kind {
-                            inline::EventKind::Str => {
-                                text.push_str(&src[ev.span.clone()]);
-                                let mut chars = src[ev.span].chars().peekable();
-                                while let Some(c) = chars.next() {
-                                    if c.is_ascii_whitespace() {
-                                        while chars
-                                            .peek()
-                                            .map_or(false, |c| c.is_ascii_whitespace())
-                                        {
-                                            chars.next();
-                                        }
-                                        if !last_whitespace {
-                                            last_whitespace = true;
-                                            id_auto.push('-');
-                                        }
-                                    } else if !c.is_ascii_punctuation() || matches!(c, '-' | '_') {
-                                        id_auto.push(c);
-                                        last_whitespace = false;
-                                    }
-                                }
-                            }
-                            inline::EventKind::Atom(inline::Atom::Softbreak) => {
-                                text.push(' ');
-                                id_auto.push('-');
-                            }
-                            _ => {}
+                        inline_parser.for_each(|ev| {
+                            let event = match ev.kind {
+                                inline::EventKind::Str => Event::Str(src[ev.span].into()),
+                                inline::EventKind::Atom(a) => a.into(),
+                                _ => return,
+                            };
+                            push_stripped(&mut text, &event);
                         });
                         if span_inline.is_none() {
                             break;
                         }
                     }
-                    id_auto.drain(id_auto.trim_end_matches('-').len()..);
-
-                    // ensure id unique
-                    if used_ids.contains::<str>(&id_auto) || id_auto.is_empty() {
-                        if id_auto.is_empty() {
-                            id_auto.push('s');
-                        }
-                        let mut num = 1;
-                        id_auto.push('-');
-                        let i_num = id_auto.len();
-                        write!(id_auto, "{}", num).unwrap();
-                        while used_ids.contains::<str>(&id_auto) {
-                            num += 1;
-                            id_auto.drain(i_num..);
-                            write!(id_auto, "{}", num).unwrap();
-                        }
-                    }
-
-                    used_ids.insert(id_auto.clone());
-                    headings.push(Heading {
+                    let id_auto = unique_id(id_generator.heading_id(&text), &mut used_ids);
+                    headings.push(PrePassHeading {
                         location: e.span.start as u32,
+                        end: last_end as u32,
+                        level,
                         id_auto,
                         text,
                         id_override,
@@ -2297,21 +2921,144 @@ impl<'s> PrePass<'s> {
 impl<'s> Parser<'s> {
     #[must_use]
     pub fn new(src: &'s str) -> Self {
-        let blocks = block::parse(src);
-        let mut inline_parser = inline::Parser::new(src);
-        let pre_pass = PrePass::new(src, blocks.iter(), &mut inline_parser);
+        Self::with_options(src, ParserOptions::default())
+    }
+
+    /// Create a parser with the given options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let options = ParserOptions::default().symbols(false);
+    /// let events: Vec<_> = Parser::with_options(":smile:", options).collect();
+    /// assert_eq!(
+    ///     events,
+    ///     [
+    ///         Event::Start(Container::Paragraph, Attributes::new()),
+    ///         Event::Str(":smile:".into()),
+    ///         Event::End(Container::Paragraph),
+    ///     ],
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_options<G>(src: &'s str, options: ParserOptions<G>) -> Self
+    where
+        G: HeadingIdGenerator,
+    {
+        let ParserOptions {
+            syntax,
+            mut heading_ids,
+        } = options;
+        let blocks = block::parse(src, syntax);
+        let mut inline_parser = inline::Parser::new(src, syntax);
+        let pre_pass = PrePass::new(src, blocks.iter(), &mut inline_parser, &mut heading_ids);
 
         Self {
             src,
+            syntax,
             blocks: blocks.into_iter().peekable(),
             pre_pass,
             block_attributes: None,
             table_head_row: false,
             verbatim: false,
             inline_parser,
+            used_definitions: None,
+            references: None,
+            link_resolver: None,
         }
     }
 
+    /// Use an external resolver for references that match neither a link definition nor a
+    /// heading in the input.
+    ///
+    /// The parser takes ownership of the resolver. A resolver that is shared with other code may be
+    /// passed as a closure that borrows it.
+    ///
+    /// References resolved by the resolver are emitted with [`SpanLinkType::Reference`], with the
+    /// URL and attributes returned by the resolver, as if they had been defined by a link
+    /// definition.
+    ///
+    /// # Examples
+    ///
+    /// Resolve references from a shared glossary:
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use std::collections::HashMap;
+    /// let mut glossary = HashMap::new();
+    /// glossary.insert("Djot", "https://djot.net");
+    /// let src = "[Djot][] and [Markdown][]\n";
+    /// let links: Vec<_> = Parser::new(src)
+    ///     .with_link_resolver(glossary)
+    ///     .filter_map(|e| match e {
+    ///         Event::Start(Container::Link(url, LinkType::Span(ty)), _) => Some((url, ty)),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(
+    ///     links,
+    ///     [
+    ///         ("https://djot.net".into(), SpanLinkType::Reference),
+    ///         ("Markdown".into(), SpanLinkType::Unresolved),
+    ///     ],
+    /// );
+    /// ```
+    ///
+    /// Resolve references with a closure that also adds attributes:
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let resolver = |label: &str| {
+    ///     let url = format!("/wiki/{}", label.replace(' ', "_"));
+    ///     Some((url.into(), Attributes::try_from("{.wiki}").unwrap()))
+    /// };
+    /// let src = "[Page Title][]";
+    /// let link = Parser::new(src)
+    ///     .with_link_resolver(resolver)
+    ///     .find(|e| matches!(e, Event::Start(Container::Link(..), _)));
+    /// assert_eq!(
+    ///     link,
+    ///     Some(Event::Start(
+    ///         Container::Link(
+    ///             "/wiki/Page_Title".into(),
+    ///             LinkType::Span(SpanLinkType::Reference),
+    ///         ),
+    ///         Attributes::try_from("{.wiki}").unwrap(),
+    ///     )),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_link_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: LinkResolver<'s> + 's,
+    {
+        self.link_resolver = Some(std::rc::Rc::new(resolver));
+        self
+    }
+
+    /// Record the reference links and images that are resolved, in order to retrieve their tags
+    /// with [`Parser::take_references`].
+    ///
+    /// The tag of a resolved reference is otherwise only part of the input, as the emitted
+    /// [`Container::Link`] or [`Container::Image`] contains the URL it resolves to, e.g. for
+    /// writing the reference again rather than the URL.
+    #[must_use]
+    pub(crate) fn with_references(mut self) -> Self {
+        self.references = Some(Vec::new());
+        self
+    }
+
+    /// Take the resolved references that have been recorded since the last call, in the order of
+    /// their start events, if enabled with [`Parser::with_references`].
+    ///
+    /// A reference is recorded when its start event is emitted.
+    pub(crate) fn take_references(&mut self) -> Vec<Reference<'s>> {
+        self.references
+            .as_mut()
+            .map_or_else(Vec::new, std::mem::take)
+    }
+
     /// Turn the [`Parser`] into an iterator of tuples, each with an [`Event`] and a start/end byte
     /// offset for its corresponding input (as a [`Range<usize>`]).
     ///
@@ -2420,6 +3167,171 @@ impl<'s> Parser<'s> {
         OffsetIter { parser: self }
     }
 
+    /// Returns the headings of the input, in the order they appear.
+    ///
+    /// The headings are obtained when the parser is created, so this does not require parsing the
+    /// input again and can be called at any time. The ids are the same as the ids of the emitted
+    /// [`Container::Heading`] and [`Container::Section`] events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let src = "# Intro\n\n{#usage}\n## How to *use*\n";
+    /// let headings = Parser::new(src).headings();
+    /// assert_eq!(
+    ///     headings,
+    ///     [
+    ///         Heading {
+    ///             level: 1,
+    ///             text: "Intro".to_string(),
+    ///             id: "Intro".to_string(),
+    ///             range: 0..7,
+    ///         },
+    ///         Heading {
+    ///             level: 2,
+    ///             text: "How to use".to_string(),
+    ///             id: "usage".to_string(),
+    ///             range: 18..33,
+    ///         },
+    ///     ],
+    /// );
+    /// assert_eq!(&src[headings[1].range.clone()], "## How to *use*");
+    /// ```
+    #[must_use]
+    pub fn headings(&self) -> Vec<Heading> {
+        self.pre_pass
+            .headings
+            .iter()
+            .enumerate()
+            .map(|(i, h)| Heading {
+                level: h.level,
+                text: h.text.clone(),
+                id: self.pre_pass.heading_id(i).to_string(),
+                range: h.location as usize..h.end as usize,
+            })
+            .collect()
+    }
+
+    /// Returns a nested table of contents of the headings of the input.
+    ///
+    /// See [`Parser::headings`] and [`Toc::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let src = "# a\n\n## b\n\n# c\n";
+    /// let toc = Parser::new(src).toc();
+    /// assert_eq!(toc.entries.len(), 2);
+    /// assert_eq!(toc.entries[0].children[0].heading.text, "b");
+    /// ```
+    #[must_use]
+    pub fn toc(&self) -> Toc {
+        Toc::new(self.headings())
+    }
+
+    /// Consume the [`Parser`] and return the problems found in the remaining input.
+    ///
+    /// The input is parsed as usual, but instead of emitting the events, the following problems
+    /// are reported, in the order of their location in the input:
+    ///
+    /// - reference links and images that could not be resolved,
+    /// - footnote references without a matching footnote,
+    /// - link definitions and footnotes with labels that have already been defined,
+    /// - link definitions and footnotes that are never referenced,
+    /// - elements with an id that has already been used by a previous element, e.g. an explicit
+    ///   `{#id}` attribute that collides with the id generated for a heading.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let src = concat!(
+    ///     "# Intro\n",
+    ///     "\n",
+    ///     "{#Intro}\n",
+    ///     "See [the docs][docs] and [^note].\n",
+    ///     "\n",
+    ///     "[doc]: https://example.com\n",
+    /// );
+    /// let kinds: Vec<_> = Parser::new(src)
+    ///     .diagnostics()
+    ///     .into_iter()
+    ///     .map(|d| (d.kind, &src[d.range]))
+    ///     .collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     [
+    ///         (
+    ///             DiagnosticKind::DuplicateId {
+    ///                 id: "Intro".into(),
+    ///                 first: 0..8,
+    ///             },
+    ///             "{#Intro}\nSee [the docs][docs] and [^note].\n",
+    ///         ),
+    ///         (
+    ///             DiagnosticKind::UnresolvedReference {
+    ///                 label: "docs".into(),
+    ///             },
+    ///             "[the docs][docs]",
+    ///         ),
+    ///         (
+    ///             DiagnosticKind::UnresolvedFootnote {
+    ///                 label: "note".into(),
+    ///             },
+    ///             "[^note]",
+    ///         ),
+    ///         (
+    ///             DiagnosticKind::UnusedDefinition {
+    ///                 label: "doc".into(),
+    ///             },
+    ///             "[doc]:",
+    ///         ),
+    ///     ],
+    /// );
+    /// ```
+    #[must_use]
+    pub fn diagnostics(mut self) -> Vec<Diagnostic<'s>> {
+        let mut diagnostics = diagnostic::Diagnostics::default();
+        self.used_definitions = Some(Set::new());
+        while let Some((e, range)) = self.next_span() {
+            diagnostics.event(&e, range);
+        }
+        diagnostics.finish(self.used_definitions.as_ref().unwrap())
+    }
+
+    /// Turn the [`Parser`] into an iterator of tuples, each with an [`Event`], its byte range and
+    /// its range of line and column positions.
+    ///
+    /// This is equivalent to [`Parser::into_offset_iter`], with each byte range additionally
+    /// converted via a [`LineIndex`] of the input. The columns are counted in the unit of the
+    /// given [`PositionEncoding`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let pos = |line, column| Position { line, column };
+    /// let input = "# ä\n\n_b_\n";
+    /// let strs: Vec<_> = Parser::new(input)
+    ///     .into_position_iter(PositionEncoding::Utf16)
+    ///     .filter(|(e, _, _)| matches!(e, Event::Str(..)))
+    ///     .map(|(_, r, p)| (&input[r], p))
+    ///     .collect();
+    /// assert_eq!(
+    ///     strs,
+    ///     [("ä", pos(1, 3)..pos(1, 4)), ("b", pos(3, 2)..pos(3, 3))],
+    /// );
+    /// ```
+    pub fn into_position_iter(self, encoding: PositionEncoding) -> PositionIter<'s> {
+        PositionIter {
+            index: LineIndex::new(self.src),
+            encoding,
+            parser: self,
+        }
+    }
+
     fn inline(&mut self) -> Option<(Event<'s>, Range<usize>)> {
         let next = self.inline_parser.next()?;
 
@@ -2443,7 +3355,9 @@ impl<'s> Parser<'s> {
                         inline::Container::Verbatim => Container::Verbatim,
                         inline::Container::InlineMath => Container::Math { display: false },
                         inline::Container::DisplayMath => Container::Math { display: true },
-                        inline::Container::RawFormat { format } => Container::RawInline { format },
+                        inline::Container::RawFormat { format } => Container::RawInline {
+                            format: format.into(),
+                        },
                         inline::Container::Subscript => Container::Subscript,
                         inline::Container::Superscript => Container::Superscript,
                         inline::Container::Insert => Container::Insert,
@@ -2465,21 +3379,46 @@ impl<'s> Parser<'s> {
                             let link_def = self
                                 .pre_pass
                                 .link_definitions
-                                .get::<str>(tag.as_ref())
-                                .cloned();
+                                .get_key_value::<str>(tag.as_ref())
+                                .map(|(label, def)| (*label, def.clone()));
 
-                            let (url_or_tag, ty) = if let Some((url, mut attrs_def)) = link_def {
+                            let mut attrs_len = 0;
+                            let (url_or_tag, ty) = if let Some((label, (url, mut attrs_def))) =
+                                link_def
+                            {
+                                if let Some(used) = &mut self.used_definitions {
+                                    used.insert(label);
+                                }
                                 if enter {
+                                    attrs_len = attrs_def.len();
                                     attrs_def.append(&mut attributes);
                                     attributes = attrs_def;
                                 }
                                 (url, SpanLinkType::Reference)
+                            } else if let Some(id) = self.pre_pass.heading_id_by_tag(tag.as_ref()) {
+                                (format!("#{}", id).into(), SpanLinkType::Reference)
+                            } else if let Some((url, mut attrs_ext)) = self
+                                .link_resolver
+                                .as_ref()
+                                .and_then(|resolver| resolver.resolve(tag.as_ref()))
+                            {
+                                if enter {
+                                    attrs_len = attrs_ext.len();
+                                    attrs_ext.append(&mut attributes);
+                                    attributes = attrs_ext;
+                                }
+                                (url, SpanLinkType::Reference)
                             } else {
-                                self.pre_pass.heading_id_by_tag(tag.as_ref()).map_or_else(
-                                    || (tag.clone(), SpanLinkType::Unresolved),
-                                    |id| (format!("#{}", id).into(), SpanLinkType::Reference),
-                                )
+                                (tag.clone(), SpanLinkType::Unresolved)
                             };
+                            if let Some(references) = &mut self.references {
+                                if enter && ty == SpanLinkType::Reference {
+                                    references.push(Reference {
+                                        tag: tag.clone(),
+                                        definition_attributes: attrs_len,
+                                    });
+                                }
+                            }
 
                             if matches!(c, inline::Container::ReferenceLink(..)) {
                                 Container::Link(url_or_tag, LinkType::Span(ty))
@@ -2502,23 +3441,7 @@ impl<'s> Parser<'s> {
                         Event::End(t)
                     }
                 }
-                inline::EventKind::Atom(a) => match a {
-                    inline::Atom::FootnoteReference { label } => Event::FootnoteReference(label),
-                    inline::Atom::Symbol(sym) => Event::Symbol(sym.into()),
-                    inline::Atom::Quote { ty, left } => match (ty, left) {
-                        (inline::QuoteType::Single, true) => Event::LeftSingleQuote,
-                        (inline::QuoteType::Single, false) => Event::RightSingleQuote,
-                        (inline::QuoteType::Double, true) => Event::LeftDoubleQuote,
-                        (inline::QuoteType::Double, false) => Event::RightDoubleQuote,
-                    },
-                    inline::Atom::Ellipsis => Event::Ellipsis,
-                    inline::Atom::EnDash => Event::EnDash,
-                    inline::Atom::EmDash => Event::EmDash,
-                    inline::Atom::Nbsp => Event::NonBreakingSpace,
-                    inline::Atom::Softbreak => Event::Softbreak,
-                    inline::Atom::Hardbreak => Event::Hardbreak,
-                    inline::Atom::Escape => Event::Escape,
-                },
+                inline::EventKind::Atom(a) => a.into(),
                 inline::EventKind::Empty => {
                     debug_assert!(!attributes.is_empty());
                     Event::Attributes(attributes.take())
@@ -2603,10 +3526,16 @@ impl<'s> Parser<'s> {
                                 block::Leaf::DescriptionTerm => Container::DescriptionTerm,
                                 block::Leaf::CodeBlock { language } => {
                                     self.verbatim = enter;
-                                    if let Some(format) = language.strip_prefix('=') {
-                                        Container::RawBlock { format }
+                                    if let Some(format) =
+                                        language.strip_prefix('=').filter(|_| self.syntax.raw)
+                                    {
+                                        Container::RawBlock {
+                                            format: format.into(),
+                                        }
                                     } else {
-                                        Container::CodeBlock { language }
+                                        Container::CodeBlock {
+                                            language: language.into(),
+                                        }
                                     }
                                 }
                                 block::Leaf::TableCell(alignment) => Container::TableCell {
@@ -2616,14 +3545,20 @@ impl<'s> Parser<'s> {
                                 block::Leaf::Caption => Container::Caption,
                                 block::Leaf::LinkDefinition { label } => {
                                     self.verbatim = enter;
-                                    Container::LinkDefinition { label }
+                                    Container::LinkDefinition {
+                                        label: label.into(),
+                                    }
                                 }
                             }
                         }
                         block::Node::Container(c) => match c {
                             block::Container::Blockquote => Container::Blockquote,
-                            block::Container::Div { class } => Container::Div { class },
-                            block::Container::Footnote { label } => Container::Footnote { label },
+                            block::Container::Div { class } => Container::Div {
+                                class: class.into(),
+                            },
+                            block::Container::Footnote { label } => Container::Footnote {
+                                label: label.into(),
+                            },
                             block::Container::List { ty, tight } => {
                                 if matches!(ty, block::ListType::Description) {
                                     Container::DescriptionList
@@ -2749,6 +3684,27 @@ impl<'s> Iterator for OffsetIter<'s> {
     }
 }
 
+/// An iterator that is identical to an [`OffsetIter`], except that it also emits the line and
+/// column positions of each event within the input.
+///
+/// See the documentation of [`Parser::into_position_iter`] for more information.
+pub struct PositionIter<'s> {
+    parser: Parser<'s>,
+    index: LineIndex<'s>,
+    encoding: PositionEncoding,
+}
+
+impl<'s> Iterator for PositionIter<'s> {
+    type Item = (Event<'s>, Range<usize>, Range<Position>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parser.next_span().map(|(e, r)| {
+            let pos = self.index.range(r.clone(), self.encoding);
+            (e, r, pos)
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::AttributeKind;
@@ -2920,7 +3876,7 @@ mod test {
             (
                 Start(
                     Section { id: "def".into() },
-                    [(AttributeKind::Pair { key: "a" }, "b")]
+                    [(AttributeKind::Pair { key: "a".into() }, "b")]
                         .into_iter()
                         .collect(),
                 ),
@@ -2950,6 +3906,24 @@ mod test {
         );
     }
 
+    #[test]
+    fn heading_attr_id_next() {
+        let ids: Vec<_> = super::Parser::new("{#x}\n# a\n## b\n")
+            .filter_map(|e| match e {
+                Start(Heading { id, .. }, _) => Some(id.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, ["x", "b"]);
+        let ids: Vec<_> = super::Parser::new("{#x}\n[a]: u\n# b\n")
+            .filter_map(|e| match e {
+                Start(Heading { id, .. }, _) => Some(id.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, ["b"]);
+    }
+
     #[test]
     fn heading_ref() {
         test_parse!(
@@ -3102,6 +4076,202 @@ mod test {
         );
     }
 
+    #[test]
+    fn headings() {
+        let src = concat!(
+            "# a\n",      //
+            "b\n",        //
+            "\n",         //
+            "> ## a b\n", //
+            "\n",         //
+            "#\n",        //
+            "\n",         //
+            "### `c`\n",
+        );
+        let headings = super::Parser::new(src)
+            .headings()
+            .into_iter()
+            .map(|h| (h.level, h.text, h.id, &src[h.range]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headings,
+            [
+                (1, "a b".to_string(), "a-b".to_string(), "# a\nb"),
+                (2, "a b".to_string(), "a-b-1".to_string(), "## a b"),
+                (1, "".to_string(), "s-1".to_string(), "#"),
+                (3, "c".to_string(), "c".to_string(), "### `c`"),
+            ],
+        );
+    }
+
+    #[test]
+    fn heading_id_generator() {
+        let src = concat!(
+            "# A *b*\n", //
+            "\n",        //
+            "# c\n",     //
+            "\n",        //
+            "[A b][]\n",
+        );
+        let options = super::ParserOptions::default().heading_ids(|text: &str| {
+            if text == "c" {
+                String::new()
+            } else {
+                text.to_lowercase().replace(' ', "_")
+            }
+        });
+        let ids = super::Parser::with_options(src, options)
+            .filter_map(|e| match e {
+                Start(Section { id }, _) => Some(id),
+                Start(Link(url, _), _) => Some(url),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids, &["a_b", "s-1", "#a_b"]);
+    }
+
+    #[test]
+    fn options_disabled() {
+        use super::ParserOptions;
+        type Disable = fn(ParserOptions) -> ParserOptions;
+        type Matches = fn(&super::Event) -> bool;
+        let disabled: &[(&str, Disable, Matches)] = &[
+            ("|a|", |o| o.tables(false), |e| matches!(e, Start(Table, _))),
+            (
+                "[^a]",
+                |o| o.footnotes(false),
+                |e| matches!(e, FootnoteReference(..)),
+            ),
+            (
+                "[^a]: b",
+                |o| o.footnotes(false),
+                |e| matches!(e, Start(Footnote { .. }, _)),
+            ),
+            (
+                ": a",
+                |o| o.description_lists(false),
+                |e| matches!(e, Start(DescriptionList, _)),
+            ),
+            (
+                "- [x] a",
+                |o| o.task_lists(false),
+                |e| matches!(e, Start(TaskListItem { .. }, _)),
+            ),
+            (
+                "```=html\n<a>\n```",
+                |o| o.raw(false),
+                |e| matches!(e, Start(RawBlock { .. }, _)),
+            ),
+            (
+                "`<a>`{=html}",
+                |o| o.raw(false),
+                |e| matches!(e, Start(RawInline { .. }, _)),
+            ),
+            (
+                "$`x` $$`y`",
+                |o| o.math(false),
+                |e| matches!(e, Start(Math { .. }, _)),
+            ),
+            (
+                "# a",
+                |o| o.sections(false),
+                |e| matches!(e, Start(Section { .. }, _)),
+            ),
+            (
+                "{.a}\nb",
+                |o| o.attributes(false),
+                |e| matches!(e, Start(_, attrs) if !attrs.is_empty()),
+            ),
+            (
+                "[a]{.b} _c_{.d} `e`{.f}",
+                |o| o.attributes(false),
+                |e| matches!(e, Start(_, attrs) if !attrs.is_empty()),
+            ),
+            (":a:", |o| o.symbols(false), |e| matches!(e, Symbol(..))),
+        ];
+        for (src, disable, matches) in disabled {
+            assert!(
+                super::Parser::new(src).any(|e| matches(&e)),
+                "enabled: {:?}",
+                src
+            );
+            let options = disable(ParserOptions::default());
+            let events = super::Parser::with_options(src, options).collect::<Vec<_>>();
+            assert!(
+                !events.iter().any(matches),
+                "disabled: {:?}\n{:?}",
+                src,
+                events
+            );
+        }
+    }
+
+    #[test]
+    fn references() {
+        let src = "[a][b]{.c} [d][]\n\n{.e}\n[b]: /url\n";
+        let mut parser = super::Parser::new(src).with_references();
+        assert_eq!(
+            parser.nth(1),
+            Some(Start(
+                Link("/url".into(), LinkType::Span(SpanLinkType::Reference)),
+                Attributes::try_from("{.e .c}").unwrap(),
+            )),
+        );
+        assert_eq!(
+            parser.take_references(),
+            [super::Reference {
+                tag: "b".into(),
+                definition_attributes: 1,
+            }],
+        );
+        // unresolved references are not recorded
+        while parser.next().is_some() {}
+        assert_eq!(parser.take_references(), []);
+    }
+
+    #[test]
+    fn link_resolver() {
+        let resolver = |label: &str| {
+            Some((
+                format!("ext:{}", label).into(),
+                Attributes::try_from("{.ext title=a}").unwrap(),
+            ))
+        };
+        let src = concat!(
+            "[a][] [b][] ![c][] [d][]{title=b}\n", //
+            "\n",                                  //
+            "# b\n",                               //
+            "\n",                                  //
+            "[a]: url\n",
+        );
+        let links = super::Parser::new(src)
+            .with_link_resolver(resolver)
+            .filter_map(|e| match e {
+                Start(Link(url, LinkType::Span(ty)) | Image(url, ty), attrs) => {
+                    Some((url, ty, attrs))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            &[
+                ("url".into(), SpanLinkType::Reference, Attributes::new()),
+                ("#b".into(), SpanLinkType::Reference, Attributes::new()),
+                (
+                    "ext:c".into(),
+                    SpanLinkType::Reference,
+                    Attributes::try_from("{.ext title=a}").unwrap(),
+                ),
+                (
+                    "ext:d".into(),
+                    SpanLinkType::Reference,
+                    Attributes::try_from("{.ext title=a title=b}").unwrap(),
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn blockquote() {
         test_parse!(
@@ -3170,11 +4340,21 @@ mod test {
             "``raw\nraw``{=format}",
             (Start(Paragraph, Attributes::new()), ""),
             (
-                Start(RawInline { format: "format" }, Attributes::new()),
+                Start(
+                    RawInline {
+                        format: "format".into()
+                    },
+                    Attributes::new()
+                ),
                 "``",
             ),
             (Str("raw\nraw".into()), "raw\nraw"),
-            (End(RawInline { format: "format" }), "``{=format}"),
+            (
+                End(RawInline {
+                    format: "format".into()
+                }),
+                "``{=format}"
+            ),
             (End(Paragraph), ""),
         );
     }
@@ -3184,11 +4364,21 @@ mod test {
         test_parse!(
             "``` =html\n<table>\n```",
             (
-                Start(RawBlock { format: "html" }, Attributes::new()),
+                Start(
+                    RawBlock {
+                        format: "html".into()
+                    },
+                    Attributes::new()
+                ),
                 "``` =html\n",
             ),
             (Str("<table>".into()), "<table>"),
-            (End(RawBlock { format: "html" }), "```"),
+            (
+                End(RawBlock {
+                    format: "html".into()
+                }),
+                "```"
+            ),
         );
     }
 
@@ -3209,24 +4399,44 @@ mod test {
                 "```\n",       //
             ),
             (
-                Start(RawBlock { format: "html" }, Attributes::new()),
+                Start(
+                    RawBlock {
+                        format: "html".into()
+                    },
+                    Attributes::new()
+                ),
                 "```=html\n",
             ),
             (Str("<tag1>\n".into()), "<tag1>\n"),
             (Str("<tag2>".into()), "<tag2>"),
-            (End(RawBlock { format: "html" }), "```\n"),
+            (
+                End(RawBlock {
+                    format: "html".into()
+                }),
+                "```\n"
+            ),
             (Blankline, "\n"),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("paragraph".into()), "paragraph"),
             (End(Paragraph), ""),
             (Blankline, "\n"),
             (
-                Start(RawBlock { format: "html" }, Attributes::new()),
+                Start(
+                    RawBlock {
+                        format: "html".into()
+                    },
+                    Attributes::new()
+                ),
                 "```=html\n",
             ),
             (Str("</tag2>\n".into()), "</tag2>\n"),
             (Str("</tag1>".into()), "</tag1>"),
-            (End(RawBlock { format: "html" }), "```\n"),
+            (
+                End(RawBlock {
+                    format: "html".into()
+                }),
+                "```\n"
+            ),
         );
     }
 
@@ -3336,11 +4546,21 @@ mod test {
             (End(Paragraph), ""),
             (Blankline, "\n"),
             (
-                Start(LinkDefinition { label: "tag" }, Attributes::new()),
+                Start(
+                    LinkDefinition {
+                        label: "tag".into()
+                    },
+                    Attributes::new()
+                ),
                 "[tag]:",
             ),
             (Str("url".into()), "url"),
-            (End(LinkDefinition { label: "tag" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "tag".into()
+                }),
+                ""
+            ),
         );
         test_parse!(
             concat!(
@@ -3361,11 +4581,21 @@ mod test {
             (End(Paragraph), ""),
             (Blankline, "\n"),
             (
-                Start(LinkDefinition { label: "tag" }, Attributes::new()),
+                Start(
+                    LinkDefinition {
+                        label: "tag".into()
+                    },
+                    Attributes::new()
+                ),
                 "[tag]:",
             ),
             (Str("url".into()), "url"),
-            (End(LinkDefinition { label: "tag" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "tag".into()
+                }),
+                ""
+            ),
         );
     }
 
@@ -3431,11 +4661,21 @@ mod test {
             (End(Blockquote), ""),
             (Blankline, "\n"),
             (
-                Start(LinkDefinition { label: "a b" }, Attributes::new()),
+                Start(
+                    LinkDefinition {
+                        label: "a b".into()
+                    },
+                    Attributes::new()
+                ),
                 "[a b]:",
             ),
             (Str("url".into()), "url"),
-            (End(LinkDefinition { label: "a b" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "a b".into()
+                }),
+                ""
+            ),
         );
     }
 
@@ -3486,11 +4726,21 @@ mod test {
             (End(Blockquote), ""),
             (Blankline, "\n"),
             (
-                Start(LinkDefinition { label: "a b" }, Attributes::new()),
+                Start(
+                    LinkDefinition {
+                        label: "a b".into()
+                    },
+                    Attributes::new()
+                ),
                 "[a b]:",
             ),
             (Str("url".into()), "url"),
-            (End(LinkDefinition { label: "a b" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "a b".into()
+                }),
+                ""
+            ),
         );
     }
 
@@ -3519,12 +4769,22 @@ mod test {
             (End(Paragraph), ""),
             (Blankline, "\n"),
             (
-                Start(LinkDefinition { label: "tag" }, Attributes::new()),
+                Start(
+                    LinkDefinition {
+                        label: "tag".into()
+                    },
+                    Attributes::new()
+                ),
                 "[tag]:",
             ),
             (Str("u".into()), "u"),
             (Str("rl".into()), "rl"),
-            (End(LinkDefinition { label: "tag" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "tag".into()
+                }),
+                ""
+            ),
         );
         test_parse!(
             concat!(
@@ -3553,12 +4813,22 @@ mod test {
             (End(Paragraph), ""),
             (Blankline, "\n"),
             (
-                Start(LinkDefinition { label: "tag" }, Attributes::new()),
+                Start(
+                    LinkDefinition {
+                        label: "tag".into()
+                    },
+                    Attributes::new()
+                ),
                 "[tag]:",
             ),
             (Str("url".into()), "url"),
             (Str("cont".into()), "cont"),
-            (End(LinkDefinition { label: "tag" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "tag".into()
+                }),
+                ""
+            ),
         );
     }
 
@@ -3577,8 +4847,8 @@ mod test {
                 Start(
                     Link("url".into(), LinkType::Span(SpanLinkType::Reference)),
                     [
-                        (AttributeKind::Pair { key: "a" }, "b"),
-                        (AttributeKind::Pair { key: "b" }, "c"),
+                        (AttributeKind::Pair { key: "a".into() }, "b"),
+                        (AttributeKind::Pair { key: "b".into() }, "c"),
                     ]
                     .into_iter()
                     .collect(),
@@ -3594,15 +4864,22 @@ mod test {
             (Blankline, "\n"),
             (
                 Start(
-                    LinkDefinition { label: "tag" },
-                    [(AttributeKind::Pair { key: "a" }, "b")]
+                    LinkDefinition {
+                        label: "tag".into()
+                    },
+                    [(AttributeKind::Pair { key: "a".into() }, "b")]
                         .into_iter()
                         .collect(),
                 ),
                 "{a=b}\n[tag]:",
             ),
             (Str("url".into()), "url"),
-            (End(LinkDefinition { label: "tag" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "tag".into()
+                }),
+                ""
+            ),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("para".into()), "para"),
             (End(Paragraph), ""),
@@ -3641,13 +4918,20 @@ mod test {
             (Blankline, "\n"),
             (
                 Start(
-                    LinkDefinition { label: "tag" },
+                    LinkDefinition {
+                        label: "tag".into()
+                    },
                     [(AttributeKind::Class, "def")].into_iter().collect(),
                 ),
                 "{.def}\n[tag]:",
             ),
             (Str("url".into()), "url"),
-            (End(LinkDefinition { label: "tag" }), ""),
+            (
+                End(LinkDefinition {
+                    label: "tag".into()
+                }),
+                ""
+            ),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("para".into()), "para"),
             (End(Paragraph), ""),
@@ -3695,9 +4979,9 @@ mod test {
         test_parse!(
             "[^a][^b][^c]",
             (Start(Paragraph, Attributes::new()), ""),
-            (FootnoteReference("a"), "[^a]"),
-            (FootnoteReference("b"), "[^b]"),
-            (FootnoteReference("c"), "[^c]"),
+            (FootnoteReference("a".into()), "[^a]"),
+            (FootnoteReference("b".into()), "[^b]"),
+            (FootnoteReference("c".into()), "[^c]"),
             (End(Paragraph), ""),
         );
     }
@@ -3707,14 +4991,17 @@ mod test {
         test_parse!(
             "[^a]\n\n[^a]: a\n",
             (Start(Paragraph, Attributes::new()), ""),
-            (FootnoteReference("a"), "[^a]"),
+            (FootnoteReference("a".into()), "[^a]"),
             (End(Paragraph), ""),
             (Blankline, "\n"),
-            (Start(Footnote { label: "a" }, Attributes::new()), "[^a]:"),
+            (
+                Start(Footnote { label: "a".into() }, Attributes::new()),
+                "[^a]:"
+            ),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("a".into()), "a"),
             (End(Paragraph), ""),
-            (End(Footnote { label: "a" }), ""),
+            (End(Footnote { label: "a".into() }), ""),
         );
     }
 
@@ -3729,10 +5016,13 @@ mod test {
                 " def", //
             ),
             (Start(Paragraph, Attributes::new()), ""),
-            (FootnoteReference("a"), "[^a]"),
+            (FootnoteReference("a".into()), "[^a]"),
             (End(Paragraph), ""),
             (Blankline, "\n"),
-            (Start(Footnote { label: "a" }, Attributes::new()), "[^a]:"),
+            (
+                Start(Footnote { label: "a".into() }, Attributes::new()),
+                "[^a]:"
+            ),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("abc".into()), "abc"),
             (End(Paragraph), ""),
@@ -3740,7 +5030,7 @@ mod test {
             (Start(Paragraph, Attributes::new()), ""),
             (Str("def".into()), "def"),
             (End(Paragraph), ""),
-            (End(Footnote { label: "a" }), ""),
+            (End(Footnote { label: "a".into() }), ""),
         );
     }
 
@@ -3756,17 +5046,20 @@ mod test {
                 "para\n", //
             ),
             (Start(Paragraph, Attributes::new()), ""),
-            (FootnoteReference("a"), "[^a]"),
+            (FootnoteReference("a".into()), "[^a]"),
             (End(Paragraph), ""),
             (Blankline, "\n"),
-            (Start(Footnote { label: "a" }, Attributes::new()), "[^a]:"),
+            (
+                Start(Footnote { label: "a".into() }, Attributes::new()),
+                "[^a]:"
+            ),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("note".into()), "note"),
             (Softbreak, "\n"),
             (Str("cont".into()), "cont"),
             (End(Paragraph), ""),
             (Blankline, "\n"),
-            (End(Footnote { label: "a" }), ""),
+            (End(Footnote { label: "a".into() }), ""),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("para".into()), "para"),
             (End(Paragraph), ""),
@@ -3779,16 +5072,19 @@ mod test {
                 ":::\n",        //
             ),
             (Start(Paragraph, Attributes::new()), ""),
-            (FootnoteReference("a"), "[^a]"),
+            (FootnoteReference("a".into()), "[^a]"),
             (End(Paragraph), ""),
             (Blankline, "\n"),
-            (Start(Footnote { label: "a" }, Attributes::new()), "[^a]:"),
+            (
+                Start(Footnote { label: "a".into() }, Attributes::new()),
+                "[^a]:"
+            ),
             (Start(Paragraph, Attributes::new()), ""),
             (Str("note".into()), "note"),
             (End(Paragraph), ""),
-            (End(Footnote { label: "a" }), ""),
-            (Start(Div { class: "" }, Attributes::new()), ":::\n"),
-            (End(Div { class: "" }), ""),
+            (End(Footnote { label: "a".into() }), ""),
+            (Start(Div { class: "".into() }, Attributes::new()), ":::\n"),
+            (End(Div { class: "".into() }), ""),
         );
     }
 
@@ -3901,12 +5197,12 @@ mod test {
                 "{%cmt}\n", //
                 ":::\n",    //
             ),
-            (Start(Div { class: "" }, Attributes::new()), ":::\n"),
+            (Start(Div { class: "".into() }, Attributes::new()), ":::\n"),
             (
                 Attributes([(AttributeKind::Comment, "cmt")].into_iter().collect()),
                 "{%cmt}\n"
             ),
-            (End(Div { class: "" }), ":::\n"),
+            (End(Div { class: "".into() }), ":::\n"),
         );
     }
 
@@ -4084,8 +5380,8 @@ mod test {
                 Start(
                     Emphasis,
                     [
-                        (AttributeKind::Pair { key: "a" }, "b"),
-                        (AttributeKind::Pair { key: "c" }, "d"),
+                        (AttributeKind::Pair { key: "a".into() }, "b"),
+                        (AttributeKind::Pair { key: "c".into() }, "d"),
                     ]
                     .into_iter()
                     .collect(),
@@ -4110,7 +5406,7 @@ mod test {
                     Span,
                     [
                         (AttributeKind::Comment, ""),
-                        (AttributeKind::Pair { key: "a" }, "a"),
+                        (AttributeKind::Pair { key: "a".into() }, "a"),
                     ]
                     .into_iter()
                     .collect(),
@@ -4133,7 +5429,7 @@ mod test {
             (
                 Start(
                     Span,
-                    [(AttributeKind::Pair { key: "a" }, "a b c")]
+                    [(AttributeKind::Pair { key: "a".into() }, "a b c")]
                         .into_iter()
                         .collect(),
                 ),
@@ -4154,7 +5450,7 @@ mod test {
             (
                 Start(
                     Span,
-                    [(AttributeKind::Pair { key: "a" }, "b")]
+                    [(AttributeKind::Pair { key: "a".into() }, "b")]
                         .into_iter()
                         .collect(),
                 ),
@@ -4404,4 +5700,63 @@ mod test {
         assert_eq!(AlphaUpper.parse_number("Z"), 26);
         assert_eq!(AlphaLower.parse_number("aa"), 27);
     }
+
+    #[test]
+    fn into_owned() {
+        let src = concat!(
+            "{.a key=val}\n",         //
+            "::: div\n",              //
+            "``` lang\n",             //
+            "code\n",                 //
+            "```\n",                  //
+            "``` =raw\n",             //
+            "raw\n",                  //
+            "```\n",                  //
+            "[^f] [x][] `r`{=raw}\n", //
+            ":::\n",                  //
+            "\n",                     //
+            "[^f]: note\n",           //
+            "\n",                     //
+            "[x]: url\n",
+        );
+        let borrowed: Vec<_> = super::Parser::new(src).collect();
+        let owned: Vec<super::Event<'static>> = {
+            let src = src.to_string();
+            super::Parser::new(&src)
+                .map(super::Event::into_owned)
+                .collect()
+        };
+        assert_eq!(borrowed, owned);
+        assert!(owned.iter().all(|e| match e {
+            Str(s) | FootnoteReference(s) => matches!(s, super::CowStr::Owned(_)),
+            _ => true,
+        }));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let src = concat!(
+            "{#i .c k=\"v\\\"w\"}\n",     //
+            "# a\n",                      //
+            "\n",                         //
+            "1) [b](u) `r`{=html}[^f]\n", //
+            "\n",                         //
+            "[^f]: c\n",                  //
+            "\n",                         //
+            "| d |\n",                    //
+            "|:-:|\n",
+        );
+        let events: Vec<_> = super::Parser::new(src).collect();
+        let json = serde_json::to_string(&events).unwrap();
+        let deserialized: Vec<super::Event> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, events);
+        assert_eq!(
+            serde_json::to_string(&events[0]).unwrap(),
+            concat!(
+                r#"{"Start":[{"Section":{"id":"i"}},"#,
+                r#"[["Id","i"],["Class","c"],[{"Pair":{"key":"k"}},"v\\\"w"]]]}"#,
+            ),
+        );
+    }
 }
@@ -0,0 +1,117 @@
+//! State shared by the renderers that write block containers as prefixed lines of markup.
+
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use crate::Alignment;
+use crate::Attributes;
+use crate::Container;
+
+/// Line prefix of an open block container.
+pub(crate) struct Prefix {
+    /// Prefix of the first line of the container, e.g. a list item marker, if not yet written.
+    pub(crate) first: Option<String>,
+    /// Prefix of subsequent lines.
+    pub(crate) rest: String,
+}
+
+/// Line prefixes of all open block containers, outermost first.
+#[derive(Default)]
+pub(crate) struct Prefixes(Vec<Prefix>);
+
+impl Prefixes {
+    /// Write lines with the prefixes of all open containers.
+    ///
+    /// The prefix of the first line of a container is only written once, on the first line that
+    /// is written within it.
+    pub(crate) fn line<W>(&mut self, s: &str, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        for l in s.split('\n') {
+            let mut prefix = String::new();
+            for p in &mut self.0 {
+                prefix.push_str(&p.first.take().unwrap_or_else(|| p.rest.clone()));
+            }
+            if l.is_empty() {
+                out.write_str(prefix.trim_end())?;
+            } else {
+                out.write_str(&prefix)?;
+                out.write_str(l)?;
+            }
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    /// Write a blank line that separates blocks within the open containers.
+    pub(crate) fn blankline<W>(&self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let prefix: String = self.0.iter().map(|p| p.rest.as_str()).collect();
+        out.write_str(prefix.trim_end())?;
+        out.write_char('\n')
+    }
+}
+
+impl Deref for Prefixes {
+    type Target = Vec<Prefix>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Prefixes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Separation state of blocks within a block container.
+#[derive(Default)]
+pub(crate) struct Level<T> {
+    /// A block has been written at this level.
+    pub(crate) blocks: bool,
+    /// The blocks are items of a tight list.
+    pub(crate) tight: bool,
+    /// Renderer specific state of the last block written at this level.
+    pub(crate) last: T,
+}
+
+/// An open inline container, with its content buffered.
+pub(crate) struct Inline<'s, T> {
+    pub(crate) container: Container<'s>,
+    pub(crate) attrs: Attributes<'s>,
+    pub(crate) text: String,
+    /// Renderer specific state of the container.
+    pub(crate) state: T,
+}
+
+/// A table, buffered in order to determine the layout of its columns.
+#[derive(Default)]
+pub(crate) struct Table {
+    pub(crate) rows: Vec<Row>,
+    pub(crate) caption: Option<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct Row {
+    pub(crate) head: bool,
+    pub(crate) cells: Vec<String>,
+    pub(crate) alignments: Vec<Alignment>,
+}
+
+/// Fence of a code block with the given content, longer than any backtick fence at the start of
+/// its lines.
+pub(crate) fn code_fence(content: &str) -> String {
+    let n = content
+        .split('\n')
+        .map(|l| l.trim_start().bytes().take_while(|c| *c == b'`').count())
+        .max()
+        .unwrap_or(0)
+        .max(2)
+        + 1;
+    "`".repeat(n)
+}
@@ -1,7 +1,11 @@
 use std::ffi::OsString;
 use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
 use std::io::BufWriter;
 use std::io::Read;
+#[cfg(feature = "djot")]
+use std::io::Write;
 use std::process::exit;
 
 use jotdown::Render;
@@ -13,15 +17,29 @@ struct App {
     minified: bool,
     start_indent: usize,
     indent_string: String,
+    sanitize: bool,
+    standalone: bool,
+    template: Option<OsString>,
+    toc: bool,
+    stream: bool,
 }
 
 fn parse_args() -> App {
     let mut app = App::default();
 
     let mut args = std::env::args_os().skip(1).peekable();
+    let mut files_only = false;
 
     while let Some(arg) = args.next() {
         match (arg.to_string_lossy().as_ref(), args.peek()) {
+            (file, _) if files_only || !file.starts_with('-') => {
+                if app.input.is_some() {
+                    eprint!("too many arguments\n\n{}", include_str!("./help.txt"));
+                    exit(1)
+                }
+                app.input = Some(file.into());
+            }
+            ("--", _) => files_only = true,
             ("-h" | "--help", _) => {
                 eprint!("{}", include_str!("./help.txt"));
                 exit(0);
@@ -41,6 +59,20 @@ fn parse_args() -> App {
                 }
             },
             ("--minified", _) => app.minified = true,
+            ("--sanitize", _) => app.sanitize = true,
+            ("--standalone", _) => app.standalone = true,
+            ("--toc", _) => app.toc = true,
+            ("--stream", _) => app.stream = true,
+            (flag @ "--template", t) => match t {
+                Some(t) => {
+                    app.template = Some(t.into());
+                    args.next();
+                }
+                None => {
+                    eprintln!("please supply an argument to {}", flag);
+                    exit(1);
+                }
+            },
             (flag @ "--indent-string", s) => {
                 if let Some(s) = s {
                     app.indent_string = s.to_string_lossy().into_owned();
@@ -69,13 +101,6 @@ fn parse_args() -> App {
                 }
             }
             ("-", _) => {}
-            (file, _) if !file.starts_with('-') => {
-                if app.input.is_some() {
-                    eprint!("too many arguments\n\n{}", include_str!("./help.txt"));
-                    exit(1)
-                }
-                app.input = Some(file.into());
-            }
             (flag, _) => {
                 eprint!("unknown flag: {}\n\n{}", flag, include_str!("./help.txt"));
                 exit(1)
@@ -89,17 +114,7 @@ fn parse_args() -> App {
 fn run() -> Result<(), std::io::Error> {
     let app = parse_args();
 
-    let content = match app.input {
-        Some(path) => std::fs::read_to_string(path)?,
-        None => {
-            let mut s = String::new();
-            std::io::stdin().read_to_string(&mut s)?;
-            s
-        }
-    };
-
-    let parser = jotdown::Parser::new(&content);
-    let renderer = if app.minified {
+    let mut renderer = if app.minified {
         jotdown::html::Renderer::minified()
     } else {
         jotdown::html::Renderer::indented(jotdown::html::Indentation {
@@ -107,17 +122,151 @@ fn run() -> Result<(), std::io::Error> {
             initial_level: app.start_indent,
         })
     };
+    if app.sanitize {
+        renderer = renderer.with_sanitize(jotdown::html::Sanitize::default());
+    }
+    if app.standalone || app.template.is_some() {
+        let template = app.template.map(std::fs::read_to_string).transpose()?;
+        renderer = renderer.with_standalone(jotdown::html::Standalone {
+            template,
+            ..jotdown::html::Standalone::default()
+        });
+    }
+    if app.toc {
+        renderer = renderer.with_toc();
+    }
+
+    if app.stream {
+        let input: Box<dyn BufRead> = match app.input {
+            Some(path) => Box::new(BufReader::new(File::open(path)?)),
+            None => Box::new(BufReader::new(std::io::stdin())),
+        };
+        let mut result = Ok(());
+        let events = jotdown::StreamParser::new(input).scan(&mut result, |result, e| {
+            e.map_err(|e| **result = Err(e)).ok()
+        });
+        write(&renderer, events, app.output)?;
+        result
+    } else {
+        let content = match app.input {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => {
+                let mut s = String::new();
+                std::io::stdin().read_to_string(&mut s)?;
+                s
+            }
+        };
+        write(&renderer, jotdown::Parser::new(&content), app.output)
+    }
+}
 
-    match app.output {
-        Some(path) => renderer.write(parser, File::create(path)?)?,
-        None => renderer.write(parser, BufWriter::new(std::io::stdout()))?,
+fn write<'s, I>(
+    renderer: &jotdown::html::Renderer,
+    events: I,
+    output: Option<OsString>,
+) -> Result<(), std::io::Error>
+where
+    I: Iterator<Item = jotdown::Event<'s>>,
+{
+    match output {
+        Some(path) => renderer.write(events, File::create(path)?),
+        None => renderer.write(events, BufWriter::new(std::io::stdout())),
     }
+}
 
+#[cfg(feature = "djot")]
+fn fmt() -> Result<(), std::io::Error> {
+    let mut files = Vec::new();
+    let mut check = false;
+    let mut options = jotdown::djot::FormatOptions::default();
+
+    let mut args = std::env::args_os().skip(2).peekable();
+    let mut files_only = false;
+    while let Some(arg) = args.next() {
+        match (arg.to_string_lossy().as_ref(), args.peek()) {
+            (file, _) if files_only || !file.starts_with('-') => files.push(Some(arg)),
+            ("--", _) => files_only = true,
+            ("-h" | "--help", _) => {
+                eprint!("{}", include_str!("./help.txt"));
+                exit(0);
+            }
+            ("--check", _) => check = true,
+            (flag @ "--width", w) => {
+                if let Some(w) = w {
+                    if let Ok(n) = w.to_string_lossy().parse() {
+                        options.width = Some(n);
+                    } else {
+                        eprintln!(
+                            "{} expected a non-negative integer, got '{}'",
+                            flag,
+                            w.to_string_lossy(),
+                        );
+                        exit(1);
+                    }
+                    args.next();
+                } else {
+                    eprintln!("please supply an argument to {}", flag);
+                    exit(1);
+                }
+            }
+            ("-", _) => files.push(None),
+            (flag, _) => {
+                eprint!("unknown flag: {}\n\n{}", flag, include_str!("./help.txt"));
+                exit(1)
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push(None);
+    }
+
+    let mut unformatted = false;
+    for file in files {
+        let content = match &file {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => {
+                let mut s = String::new();
+                std::io::stdin().read_to_string(&mut s)?;
+                s
+            }
+        };
+        let formatted = jotdown::djot::format(&content, &options);
+        if check {
+            if formatted != content {
+                unformatted = true;
+                let name = file
+                    .as_ref()
+                    .map_or("<stdin>".into(), |p| p.to_string_lossy());
+                eprintln!("not formatted: {}", name);
+            }
+        } else if let Some(path) = file {
+            if formatted != content {
+                std::fs::write(path, formatted)?;
+            }
+        } else {
+            std::io::stdout().write_all(formatted.as_bytes())?;
+        }
+    }
+
+    if unformatted {
+        exit(1);
+    }
     Ok(())
 }
 
+#[cfg(not(feature = "djot"))]
+fn fmt() -> Result<(), std::io::Error> {
+    eprintln!("the fmt subcommand requires the djot feature");
+    exit(1)
+}
+
 fn main() {
-    match run() {
+    let result = if std::env::args_os().nth(1).map_or(false, |a| a == "fmt") {
+        fmt()
+    } else {
+        run()
+    };
+    match result {
         Ok(()) => {}
         Err(e) => {
             eprintln!("{}", e);
@@ -0,0 +1,1215 @@
+//! A Markdown renderer that takes an iterator of [`Event`]s and emits CommonMark, with GitHub
+//! Flavored Markdown extensions for tables, task lists, strikethrough and footnotes.
+//!
+//! Elements that have no Markdown equivalent are written according to the renderer's
+//! [`Fallback`] strategy.
+
+use crate::lines;
+use crate::lines::Prefix;
+use crate::lines::Prefixes;
+use crate::lines::Row;
+use crate::lines::Table;
+use crate::Alignment;
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::ListKind;
+use crate::OrderedListStyle;
+use crate::Render;
+use crate::RenderRef;
+use crate::SpanLinkType;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("hello _world_");
+/// assert_eq!(
+///     jotdown::markdown::render_to_string(events),
+///     "hello *world*\n",
+/// );
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer::default().push(events, &mut s).unwrap();
+    s
+}
+
+/// Strategy for elements that cannot be expressed in Markdown.
+///
+/// This applies to e.g. [`Container::Mark`], [`Container::Insert`], [`Container::Subscript`],
+/// [`Container::Superscript`], [`Container::DescriptionList`], spans with attributes and divs
+/// with classes or attributes. Other attributes are always omitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+    /// Write the element as HTML, with its content as Markdown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::markdown::*;
+    /// let src = "{=marked=} [span]{.cls}";
+    /// let mut actual = String::new();
+    /// Renderer::with_fallback(Fallback::Html)
+    ///     .push(Parser::new(src), &mut actual)
+    ///     .unwrap();
+    /// assert_eq!(actual, "<mark>marked</mark> <span class=\"cls\">span</span>\n");
+    /// ```
+    Html,
+    /// Write only the content of the element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::markdown::*;
+    /// let src = "{=marked=} [span]{.cls}";
+    /// let mut actual = String::new();
+    /// Renderer::with_fallback(Fallback::Strip)
+    ///     .push(Parser::new(src), &mut actual)
+    ///     .unwrap();
+    /// assert_eq!(actual, "marked span\n");
+    /// ```
+    Strip,
+}
+
+impl Default for Fallback {
+    fn default() -> Self {
+        Self::Html
+    }
+}
+
+/// [`Render`] implementor that writes Markdown output.
+///
+/// By default, elements without a Markdown equivalent are written as HTML, see
+/// [`Renderer::with_fallback`] for other options.
+#[derive(Clone, Default)]
+pub struct Renderer {
+    fallback: Fallback,
+}
+
+impl Renderer {
+    /// Create a renderer that uses the given strategy for elements without a Markdown equivalent.
+    #[must_use]
+    pub fn with_fallback(fallback: Fallback) -> Self {
+        Self { fallback }
+    }
+}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::new(self.fallback);
+        events.try_for_each(|e| w.render_event(&e, &mut out))
+    }
+}
+
+impl RenderRef for Renderer {
+    fn push_ref<'s, E, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        E: AsRef<Event<'s>>,
+        I: Iterator<Item = E>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::new(self.fallback);
+        events.try_for_each(|e| w.render_event(e.as_ref(), &mut out))
+    }
+}
+
+/// Separation state of blocks within a block container, with whether the last block is a
+/// paragraph.
+type Level = lines::Level<bool>;
+
+/// A block that contains inline elements.
+enum Leaf {
+    Paragraph,
+    Heading { level: u16 },
+    Cell,
+    Caption,
+    Code { fence: String },
+    Raw,
+}
+
+/// An open inline container, with its content buffered.
+type Inline<'s> = lines::Inline<'s, ()>;
+
+struct Writer<'s> {
+    fallback: Fallback,
+    prefixes: Prefixes,
+    levels: Vec<Level>,
+    /// Kind and next number of open lists.
+    lists: Vec<(ListKind, u64)>,
+    /// Whether each open div is written as HTML.
+    divs: Vec<bool>,
+    leaf: Option<Leaf>,
+    /// Buffered content of the current leaf block.
+    text: String,
+    inlines: Vec<Inline<'s>>,
+    table: Option<Table>,
+    /// Within an element that is not rendered, e.g. a link definition.
+    ignore: bool,
+}
+
+impl<'s> Writer<'s> {
+    fn new(fallback: Fallback) -> Self {
+        Self {
+            fallback,
+            prefixes: Prefixes::default(),
+            levels: vec![Level::default()],
+            lists: Vec::new(),
+            divs: Vec::new(),
+            leaf: None,
+            text: String::new(),
+            inlines: Vec::new(),
+            table: None,
+            ignore: false,
+        }
+    }
+
+    fn html(&self) -> bool {
+        matches!(self.fallback, Fallback::Html)
+    }
+
+    fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.ignore {
+            if let Event::End(
+                Container::LinkDefinition { .. }
+                | Container::RawBlock { .. }
+                | Container::RawInline { .. },
+            ) = e
+            {
+                self.ignore = false;
+            }
+            return Ok(());
+        }
+
+        match e {
+            Event::Start(c, attrs) => self.enter(c, attrs, &mut out)?,
+            Event::End(c) => self.exit(c, &mut out)?,
+            Event::Str(s) => {
+                let raw = matches!(self.leaf, Some(Leaf::Code { .. } | Leaf::Raw))
+                    || self.inlines.last().map_or(false, |i| {
+                        matches!(
+                            i.container,
+                            Container::Verbatim
+                                | Container::Math { .. }
+                                | Container::RawInline { .. }
+                        )
+                    });
+                let cell = matches!(self.leaf, Some(Leaf::Cell));
+                if raw && cell {
+                    self.buf().push_str(&s.replace('|', "\\|"));
+                } else if raw {
+                    self.buf().push_str(s);
+                } else {
+                    write_text(s, cell, self.buf());
+                }
+            }
+            Event::FootnoteReference(label) => {
+                let buf = self.buf();
+                buf.push_str("[^");
+                buf.push_str(label);
+                buf.push(']');
+            }
+            Event::Symbol(sym) => {
+                let buf = self.buf();
+                buf.push(':');
+                buf.push_str(sym);
+                buf.push(':');
+            }
+            Event::LeftSingleQuote => self.buf().push('‘'),
+            Event::RightSingleQuote => self.buf().push('’'),
+            Event::LeftDoubleQuote => self.buf().push('“'),
+            Event::RightDoubleQuote => self.buf().push('”'),
+            Event::Ellipsis => self.buf().push('…'),
+            Event::EnDash => self.buf().push('–'),
+            Event::EmDash => self.buf().push('—'),
+            Event::NonBreakingSpace => self.buf().push_str("&nbsp;"),
+            Event::Hardbreak => self.buf().push_str("\\\n"),
+            Event::Softbreak => self.buf().push('\n'),
+            Event::Escape | Event::Blankline | Event::Attributes(..) => {}
+            Event::ThematicBreak(..) => {
+                self.block_start(true, &mut out)?;
+                self.prefixes.line("* * *", &mut out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn enter<W>(
+        &mut self,
+        c: &Container<'s>,
+        attrs: &Attributes<'s>,
+        mut out: W,
+    ) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Section { .. } => {}
+            Container::TableRow { head } => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Row {
+                        head: *head,
+                        ..Row::default()
+                    });
+                }
+            }
+            Container::LinkDefinition { .. } => self.ignore = true,
+            Container::RawBlock { format } | Container::RawInline { format }
+                if !matches!(format.as_ref(), "html" | "markdown") =>
+            {
+                self.ignore = true;
+            }
+            Container::ListItem | Container::TaskListItem { .. } => {
+                let marker = if let Some((kind, n)) = self.lists.last_mut() {
+                    *n += 1;
+                    list_marker(*kind, *n - 1, c)
+                } else {
+                    "-".to_string()
+                };
+                self.item_start(marker, &mut out)?;
+            }
+            Container::DescriptionList => {
+                if self.html() {
+                    self.block_start(true, &mut out)?;
+                    self.prefixes.line("<dl>", &mut out)?;
+                }
+            }
+            Container::DescriptionTerm => {
+                if self.html() {
+                    self.prefixes.line("<dt>", &mut out)?;
+                    self.prefixes.blankline(&mut out)?;
+                } else {
+                    self.block_start(false, &mut out)?;
+                }
+                self.leaf_start(Leaf::Paragraph);
+            }
+            Container::DescriptionDetails => {
+                if self.html() {
+                    self.prefixes.line("<dd>", &mut out)?;
+                    self.levels.push(Level {
+                        blocks: true,
+                        ..Level::default()
+                    });
+                }
+            }
+            Container::TableCell { alignment, .. } => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.alignments.push(*alignment);
+                }
+                self.leaf_start(Leaf::Cell);
+            }
+            Container::Caption => self.leaf_start(Leaf::Caption),
+            Container::Blockquote => {
+                self.block_start(true, &mut out)?;
+                self.container_start(Some("> "), "> ");
+            }
+            Container::List { kind, tight } => {
+                let start = if let ListKind::Ordered { start, .. } = kind {
+                    *start
+                } else {
+                    1
+                };
+                // only bullet lists and lists starting with 1 may interrupt a paragraph
+                self.block_start(start == 1, &mut out)?;
+                self.lists.push((*kind, start));
+                self.levels.push(Level {
+                    tight: *tight,
+                    ..Level::default()
+                });
+            }
+            Container::Footnote { label } => {
+                self.block_start(true, &mut out)?;
+                self.container_start(Some(&format!("[^{}]: ", label)), "    ");
+            }
+            Container::Table => {
+                self.block_start(false, &mut out)?;
+                self.table = Some(Table::default());
+            }
+            Container::Div { class } => {
+                let html = self.html() && (!class.is_empty() || !attrs.is_empty());
+                if html {
+                    self.block_start(false, &mut out)?;
+                    let mut line = "<div".to_string();
+                    write_html_attributes(attrs, class, &mut line);
+                    line.push('>');
+                    self.prefixes.line(&line, &mut out)?;
+                    self.levels.push(Level {
+                        blocks: true,
+                        ..Level::default()
+                    });
+                }
+                self.divs.push(html);
+            }
+            Container::Paragraph => {
+                self.block_start(false, &mut out)?;
+                self.leaf_start(Leaf::Paragraph);
+            }
+            Container::Heading { level, .. } => {
+                self.block_start(true, &mut out)?;
+                self.leaf_start(Leaf::Heading { level: *level });
+            }
+            Container::CodeBlock { language } => {
+                self.block_start(true, &mut out)?;
+                self.leaf_start(Leaf::Code {
+                    fence: language.to_string(),
+                });
+            }
+            Container::RawBlock { .. } => {
+                self.block_start(false, &mut out)?;
+                self.leaf_start(Leaf::Raw);
+            }
+            Container::Image(..)
+            | Container::Link(..)
+            | Container::Span
+            | Container::Verbatim
+            | Container::Math { .. }
+            | Container::RawInline { .. }
+            | Container::Subscript
+            | Container::Superscript
+            | Container::Insert
+            | Container::Delete
+            | Container::Strong
+            | Container::Emphasis
+            | Container::Mark => {
+                if matches!(c, Container::Link(..)) {
+                    let buf = self.buf();
+                    if buf.ends_with('!') && !buf[..buf.len() - 1].ends_with('\\') {
+                        buf.insert(buf.len() - 1, '\\');
+                    }
+                }
+                self.inlines.push(Inline {
+                    container: c.clone(),
+                    attrs: attrs.clone(),
+                    text: String::new(),
+                    state: (),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn exit<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Section { .. } | Container::LinkDefinition { .. } => {}
+            Container::Blockquote
+            | Container::Footnote { .. }
+            | Container::ListItem
+            | Container::TaskListItem { .. } => self.container_end(&mut out)?,
+            Container::List { .. } => {
+                self.lists.pop();
+                self.levels.pop();
+            }
+            Container::DescriptionList => {
+                if self.html() {
+                    self.prefixes.line("</dl>", &mut out)?;
+                }
+            }
+            Container::DescriptionTerm => {
+                self.leaf_end(&mut out)?;
+                if self.html() {
+                    self.prefixes.blankline(&mut out)?;
+                    self.prefixes.line("</dt>", &mut out)?;
+                }
+            }
+            Container::DescriptionDetails => {
+                if self.html() {
+                    self.levels.pop();
+                    self.prefixes.blankline(&mut out)?;
+                    self.prefixes.line("</dd>", &mut out)?;
+                }
+            }
+            Container::Div { .. } => {
+                if self.divs.pop().unwrap_or(false) {
+                    self.levels.pop();
+                    self.prefixes.blankline(&mut out)?;
+                    self.prefixes.line("</div>", &mut out)?;
+                }
+            }
+            Container::TableRow { .. } => {}
+            Container::Table => self.table_end(&mut out)?,
+            Container::Paragraph
+            | Container::Heading { .. }
+            | Container::TableCell { .. }
+            | Container::Caption
+            | Container::CodeBlock { .. }
+            | Container::RawBlock { .. } => self.leaf_end(&mut out)?,
+            _ => self.inline_end(),
+        }
+
+        Ok(())
+    }
+
+    /// Separate a new block from its preceding siblings.
+    ///
+    /// Within tight lists, blocks are only separated if the new block would otherwise be a
+    /// continuation of a preceding paragraph.
+    fn block_start<W>(&mut self, interrupts: bool, out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let level = self.levels.last_mut().unwrap();
+        let blank = level.blocks && (!level.tight || (level.last && !interrupts));
+        level.blocks = true;
+        level.last = false;
+        if blank {
+            self.prefixes.blankline(out)?;
+        }
+        Ok(())
+    }
+
+    fn item_start<W>(&mut self, marker: String, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let level = self.levels.last_mut().unwrap();
+        let blank = level.blocks && !level.tight;
+        let tight = level.tight;
+        level.blocks = true;
+        if blank {
+            self.prefixes.blankline(&mut out)?;
+        }
+        let width = marker.find(" [").unwrap_or(marker.len());
+        self.prefixes.push(Prefix {
+            first: Some(marker + " "),
+            rest: " ".repeat(width + 1),
+        });
+        self.levels.push(Level {
+            tight,
+            ..Level::default()
+        });
+        Ok(())
+    }
+
+    fn container_start(&mut self, first: Option<&str>, rest: &str) {
+        self.prefixes.push(Prefix {
+            first: first.map(ToString::to_string),
+            rest: rest.to_string(),
+        });
+        self.levels.push(Level::default());
+    }
+
+    fn container_end<W>(&mut self, out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.prefixes.last().map_or(false, |p| p.first.is_some()) {
+            // empty container, marker must still be written
+            self.prefixes.line("", out)?;
+        }
+        self.prefixes.pop();
+        self.levels.pop();
+        Ok(())
+    }
+
+    fn leaf_start(&mut self, leaf: Leaf) {
+        if matches!(leaf, Leaf::Paragraph) {
+            if let Some(level) = self.levels.last_mut() {
+                level.last = true;
+            }
+        }
+        if matches!(leaf, Leaf::Cell) {
+            if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                row.cells.push(String::new());
+            }
+        }
+        self.leaf = Some(leaf);
+        self.text.clear();
+    }
+
+    fn leaf_end<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let text = std::mem::take(&mut self.text);
+        match self.leaf.take() {
+            Some(Leaf::Paragraph) => {
+                let text = text
+                    .split('\n')
+                    .map(escape_line_start)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.prefixes.line(&text, &mut out)?;
+            }
+            Some(Leaf::Heading { level }) => {
+                let mut text = escape_line_start(&text.replace('\n', " "));
+                if text.ends_with('#') {
+                    text.insert(text.len() - 1, '\\');
+                }
+                let hashes = "#".repeat(level.min(6).into());
+                if text.is_empty() {
+                    self.prefixes.line(&hashes, &mut out)?;
+                } else {
+                    self.prefixes
+                        .line(&format!("{} {}", hashes, text), &mut out)?;
+                }
+            }
+            Some(Leaf::Cell) => {
+                if let Some(cell) = self
+                    .table
+                    .as_mut()
+                    .and_then(|t| t.rows.last_mut())
+                    .and_then(|r| r.cells.last_mut())
+                {
+                    *cell = text.replace('\n', " ");
+                }
+            }
+            Some(Leaf::Caption) => {
+                if let Some(table) = &mut self.table {
+                    table.caption = Some(text);
+                }
+            }
+            Some(Leaf::Code { fence: language }) => {
+                let content = text.strip_suffix('\n').unwrap_or(&text);
+                let fence = lines::code_fence(content);
+                self.prefixes
+                    .line(&format!("{}{}", fence, language), &mut out)?;
+                if !text.is_empty() {
+                    self.prefixes.line(content, &mut out)?;
+                }
+                self.prefixes.line(&fence, &mut out)?;
+            }
+            Some(Leaf::Raw) => {
+                let content = text.strip_suffix('\n').unwrap_or(&text);
+                self.prefixes.line(content, &mut out)?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn table_end<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let Table { mut rows, caption } = if let Some(table) = self.table.take() {
+            table
+        } else {
+            return Ok(());
+        };
+
+        let columns = rows.iter().map(|r| r.cells.len()).max().unwrap_or(0);
+        if !rows.first().map_or(false, |r| r.head) {
+            // a header row is required, use an empty one
+            rows.insert(
+                0,
+                Row {
+                    head: true,
+                    cells: Vec::new(),
+                    alignments: rows.first().map_or_else(Vec::new, |r| r.alignments.clone()),
+                },
+            );
+        }
+
+        for (i, row) in rows.iter().enumerate() {
+            let cells = (0..columns)
+                .map(|j| row.cells.get(j).map_or("", String::as_str))
+                .collect::<Vec<_>>();
+            self.prefixes
+                .line(&format!("| {} |", cells.join(" | ")), &mut out)?;
+            if i == 0 {
+                let separator = (0..columns)
+                    .map(|j| match row.alignments.get(j) {
+                        Some(Alignment::Left) => ":--",
+                        Some(Alignment::Center) => ":-:",
+                        Some(Alignment::Right) => "--:",
+                        _ => "---",
+                    })
+                    .collect::<Vec<_>>();
+                self.prefixes
+                    .line(&format!("| {} |", separator.join(" | ")), &mut out)?;
+            }
+        }
+
+        if let Some(caption) = caption {
+            // no caption in markdown, place it in a paragraph after the table
+            self.block_start(false, &mut out)?;
+            let text = caption
+                .split('\n')
+                .map(escape_line_start)
+                .collect::<Vec<_>>()
+                .join("\n");
+            self.prefixes.line(&text, &mut out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Buffer of the innermost open inline element.
+    fn buf(&mut self) -> &mut String {
+        if let Some(i) = self.inlines.last_mut() {
+            &mut i.text
+        } else {
+            &mut self.text
+        }
+    }
+
+    fn inline_end(&mut self) {
+        let Inline {
+            container,
+            attrs,
+            text,
+            state: (),
+        } = if let Some(i) = self.inlines.pop() {
+            i
+        } else {
+            return;
+        };
+        let html = self.html();
+        let buf = self.buf();
+
+        match &container {
+            Container::Link(dst, LinkType::AutoLink | LinkType::Email) => {
+                buf.push('<');
+                buf.push_str(dst);
+                buf.push('>');
+            }
+            Container::Link(dst, LinkType::Span(ty)) | Container::Image(dst, ty) => {
+                if matches!(container, Container::Image(..)) {
+                    buf.push('!');
+                }
+                buf.push('[');
+                buf.push_str(&text);
+                buf.push(']');
+                if matches!(ty, SpanLinkType::Unresolved) {
+                    buf.push('[');
+                    buf.push_str(dst);
+                    buf.push(']');
+                } else {
+                    buf.push('(');
+                    write_destination(dst, buf);
+                    buf.push(')');
+                }
+            }
+            Container::Verbatim => write_code_span(&text, buf),
+            Container::Math { display } => {
+                let delim = if *display { "$$" } else { "$" };
+                buf.push_str(delim);
+                buf.push_str(&text);
+                buf.push_str(delim);
+            }
+            Container::RawInline { .. } => buf.push_str(&text),
+            Container::Span if attrs.is_empty() || !html => buf.push_str(&text),
+            Container::Span => {
+                buf.push_str("<span");
+                write_html_attributes(&attrs, "", buf);
+                buf.push('>');
+                buf.push_str(&text);
+                buf.push_str("</span>");
+            }
+            Container::Strong | Container::Emphasis | Container::Delete => {
+                let (delim, tag) = match container {
+                    Container::Strong => ("**", "strong"),
+                    Container::Emphasis => ("*", "em"),
+                    _ => ("~~", "del"),
+                };
+                let inner = text.trim_matches(char::is_whitespace);
+                if inner.is_empty() {
+                    buf.push_str(&text);
+                    return;
+                }
+                let leading = &text[..text.len() - text.trim_start().len()];
+                let trailing = &text[text.trim_end().len()..];
+                // an opening delimiter followed by punctuation must not follow a word
+                let flanking = !(inner.starts_with(|c: char| c.is_ascii_punctuation())
+                    && buf.ends_with(char::is_alphanumeric)
+                    && leading.is_empty());
+                buf.push_str(leading);
+                if flanking || !html {
+                    buf.push_str(delim);
+                    buf.push_str(inner);
+                    buf.push_str(delim);
+                } else {
+                    buf.push('<');
+                    buf.push_str(tag);
+                    buf.push('>');
+                    buf.push_str(inner);
+                    buf.push_str("</");
+                    buf.push_str(tag);
+                    buf.push('>');
+                }
+                buf.push_str(trailing);
+            }
+            _ => {
+                let tag = match container {
+                    Container::Subscript => "sub",
+                    Container::Superscript => "sup",
+                    Container::Insert => "ins",
+                    Container::Mark => "mark",
+                    _ => unreachable!(),
+                };
+                if html {
+                    buf.push('<');
+                    buf.push_str(tag);
+                    buf.push('>');
+                    buf.push_str(&text);
+                    buf.push_str("</");
+                    buf.push_str(tag);
+                    buf.push('>');
+                } else {
+                    buf.push_str(&text);
+                }
+            }
+        }
+    }
+}
+
+fn list_marker(kind: ListKind, n: u64, c: &Container) -> String {
+    match kind {
+        ListKind::Unordered(b) => char::from(b).to_string(),
+        ListKind::Task(b) => {
+            let checked = matches!(c, Container::TaskListItem { checked: true });
+            format!("{} [{}]", char::from(b), if checked { 'x' } else { ' ' })
+        }
+        ListKind::Ordered { style, .. } => {
+            // markdown only has decimal numbering
+            let delim = if matches!(style, OrderedListStyle::Period) {
+                '.'
+            } else {
+                ')'
+            };
+            format!("{}{}", n, delim)
+        }
+    }
+}
+
+fn write_text(s: &str, cell: bool, out: &mut String) {
+    for (i, c) in s.char_indices() {
+        let next = &s[i + c.len_utf8()..];
+        let escape = match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~' | '$' => true,
+            '&' => next.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '#'),
+            '|' => cell,
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// Escape the first character of a line if it would otherwise start a block or interrupt a
+/// paragraph.
+fn escape_line_start(line: &str) -> String {
+    let mut s = line.to_string();
+    let ends_marker = |s: &str| s.is_empty() || s.starts_with(char::is_whitespace);
+    let mut chars = line.chars();
+    let escape = match chars.next() {
+        Some('#') => ends_marker(line.trim_start_matches('#')),
+        Some('>' | '=') => true,
+        Some('-' | '+') => {
+            ends_marker(chars.as_str()) || line.trim_end().bytes().all(|c| c == b'-')
+        }
+        _ => false,
+    };
+    if escape {
+        s.insert(0, '\\');
+        return s;
+    }
+
+    // ordered list item markers, e.g. `1.` or `1)`
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if 0 < digits
+        && matches!(line[digits..].chars().next(), Some('.' | ')'))
+        && ends_marker(&line[digits + 1..])
+    {
+        s.insert(digits, '\\');
+    }
+    s
+}
+
+fn write_destination(dst: &str, out: &mut String) {
+    if dst.is_empty() || dst.contains(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '<')) {
+        out.push('<');
+        for c in dst.chars() {
+            if matches!(c, '<' | '>' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push('>');
+    } else {
+        out.push_str(dst);
+    }
+}
+
+fn write_code_span(text: &str, out: &mut String) {
+    let n = text.split(|c| c != '`').map(str::len).max().unwrap_or(0) + 1;
+    let fence = "`".repeat(n);
+    let pad = text.starts_with('`')
+        || text.ends_with('`')
+        || (text.starts_with(' ') && text.ends_with(' ') && !text.trim().is_empty());
+    out.push_str(&fence);
+    if pad {
+        out.push(' ');
+    }
+    out.push_str(text);
+    if pad {
+        out.push(' ');
+    }
+    out.push_str(&fence);
+}
+
+fn write_html_attributes(attrs: &Attributes, class: &str, out: &mut String) {
+    let mut class_written = false;
+    for (a, v) in attrs.unique_pairs() {
+        out.push(' ');
+        out.push_str(a);
+        out.push_str("=\"");
+        if a == "class" {
+            class_written = true;
+            if !class.is_empty() {
+                write_html_escape(class, out);
+                out.push(' ');
+            }
+        }
+        for part in v.parts() {
+            write_html_escape(part, out);
+        }
+        out.push('"');
+    }
+    if !class.is_empty() && !class_written {
+        out.push_str(" class=\"");
+        write_html_escape(class, out);
+        out.push('"');
+    }
+}
+
+fn write_html_escape(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fallback;
+    use crate::Render;
+
+    macro_rules! test_markdown {
+        ($src:expr, $expected:expr $(,$fallback:expr)? $(,)?) => {
+            #[allow(unused)]
+            let mut fallback = Fallback::Html;
+            $(fallback = $fallback;)?
+            let mut actual = String::new();
+            super::Renderer { fallback }
+                .push(crate::Parser::new($src), &mut actual)
+                .unwrap();
+            assert_eq!(actual, $expected);
+        };
+    }
+
+    #[test]
+    fn para() {
+        test_markdown!(
+            concat!(
+                "a\n", //
+                "b\\\n", "c\n", "\n", "d\n",
+            ),
+            concat!(
+                "a\n", //
+                "b\\\n", "c\n", "\n", "d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn heading() {
+        test_markdown!(
+            concat!(
+                "## a\n", //
+                "b #\n",
+                "\n",
+                "######## c\n",
+            ),
+            concat!(
+                "## a b \\#\n", //
+                "\n",
+                "###### c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_tight() {
+        test_markdown!(
+            concat!(
+                "- a\n", //
+                "\n", "  + b\n", "  + c\n", "- d\n",
+            ),
+            concat!(
+                "- a\n", //
+                "  + b\n", "  + c\n", "- d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_loose() {
+        test_markdown!(
+            concat!(
+                "a) a\n", //
+                "\n", "   b\n", "b) c\n",
+            ),
+            concat!(
+                "1) a\n", //
+                "\n", "   b\n", "\n", "2) c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_ordered_start() {
+        test_markdown!(
+            concat!(
+                "a\n", //
+                "\n", "3. b\n", "4. c\n",
+            ),
+            concat!(
+                "a\n", //
+                "\n", "3. b\n", "4. c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_task() {
+        test_markdown!(
+            concat!(
+                "- [ ] a\n", //
+                "- [x] b\n",
+            ),
+            concat!(
+                "- [ ] a\n", //
+                "- [x] b\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn blockquote() {
+        test_markdown!(
+            concat!(
+                "> a\n", //
+                ">\n", "> > b\n",
+            ),
+            concat!(
+                "> a\n", //
+                ">\n", "> > b\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn code_block() {
+        test_markdown!(
+            concat!(
+                "````rust\n", //
+                "```\n",
+                "````\n",
+                "\n",
+                "``` =html\n",
+                "<br>\n",
+                "```\n",
+                "\n",
+                "``` =latex\n",
+                "\\LaTeX\n",
+                "```\n",
+            ),
+            concat!(
+                "````rust\n", //
+                "```\n",
+                "````\n",
+                "\n",
+                "<br>\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn table() {
+        test_markdown!(
+            concat!(
+                "|a|b|\n", //
+                "|:-|-:|\n",
+                "|`|`|c|\n",
+                "^ caption\n",
+            ),
+            concat!(
+                "| a | b |\n", //
+                "| :-- | --: |\n",
+                "| `\\|` | c |\n",
+                "\n",
+                "caption\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn table_headless() {
+        test_markdown!(
+            "|a|b|\n",
+            concat!(
+                "|  |  |\n", //
+                "| --- | --- |\n",
+                "| a | b |\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn footnote() {
+        test_markdown!(
+            concat!(
+                "a[^b]\n", //
+                "\n",
+                "[^b]: c\n",
+                "\n",
+                "    d\n",
+            ),
+            concat!(
+                "a[^b]\n", //
+                "\n",
+                "[^b]: c\n",
+                "\n",
+                "    d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn links() {
+        test_markdown!(
+            concat!(
+                "[a](b) [c][d] ![e *f*](g h) <https://i> [j][k]\n", //
+                "\n",
+                "[d]: /url\n",
+            ),
+            "[a](b) [c](/url) ![e **f**](<g h>) <https://i> [j][k]\n",
+        );
+    }
+
+    #[test]
+    fn inline_containers() {
+        test_markdown!("*a* _b_ {-c-} a{* b *}c", "**a** *b* ~~c~~ a **b** c\n",);
+    }
+
+    #[test]
+    fn inline_fallback() {
+        test_markdown!(
+            "{+a+} {=b=} ^c^ ~d~ [e]{#f}",
+            "<ins>a</ins> <mark>b</mark> <sup>c</sup> <sub>d</sub> <span id=\"f\">e</span>\n",
+        );
+        test_markdown!(
+            "{+a+} {=b=} ^c^ ~d~ [e]{#f}",
+            "a b c d e\n",
+            Fallback::Strip,
+        );
+    }
+
+    #[test]
+    fn div() {
+        test_markdown!(
+            concat!(
+                "::: a\n", //
+                "b\n", ":::\n", "\n", ":::\n", "c\n", ":::\n",
+            ),
+            concat!(
+                "<div class=\"a\">\n", //
+                "\n",
+                "b\n",
+                "\n",
+                "</div>\n",
+                "\n",
+                "c\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn description_list() {
+        test_markdown!(
+            concat!(
+                ": a\n", //
+                "\n", "  b\n",
+            ),
+            concat!(
+                "<dl>\n", //
+                "<dt>\n", "\n", "a\n", "\n", "</dt>\n", "<dd>\n", "\n", "b\n", "\n", "</dd>\n",
+                "</dl>\n",
+            ),
+        );
+        test_markdown!(
+            concat!(
+                ": a\n", //
+                "\n", "  b\n",
+            ),
+            concat!(
+                "a\n", //
+                "\n", "b\n",
+            ),
+            Fallback::Strip,
+        );
+    }
+
+    #[test]
+    fn verbatim_math() {
+        test_markdown!(
+            "`a` `` `b` `` $`c` $$`d` `<e>`{=html} `f`{=latex}",
+            "`a` `` `b` `` $c$ $$d$$ <e> \n",
+        );
+    }
+
+    #[test]
+    fn smart_punctuation() {
+        test_markdown!("\"a\" 'b' don't -- --- ...", "“a” ‘b’ don’t – — …\n",);
+    }
+
+    #[test]
+    fn escapes() {
+        test_markdown!(
+            concat!(
+                "\\*a\\_ <b & &amp; 1 < 2\n", //
+                "\\- c\n",
+                "1\\. d\n",
+                "\\# e\n",
+                "\\> f\n",
+                "===\n",
+            ),
+            concat!(
+                "\\*a\\_ \\<b & \\&amp; 1 \\< 2\n", //
+                "\\- c\n",
+                "1\\. d\n",
+                "\\# e\n",
+                "\\> f\n",
+                "\\===\n",
+            ),
+        );
+    }
+}
@@ -0,0 +1,840 @@
+//! Conversion of TeX math to [MathML](https://www.w3.org/TR/mathml-core/).
+//!
+//! The converter supports the commonly used subset of LaTeX math: identifiers, numbers and
+//! operators, sub- and superscripts, fractions and binomials, roots, Greek letters and other
+//! symbols, functions such as `\sin`, large operators such as `\sum` and `\int`, accents,
+//! `\left` and `\right` delimiters, font commands such as `\mathbf`, `\text`, spacing and
+//! environments for matrices, cases and aligned equations. Unsupported commands are written as
+//! `<merror>` elements.
+//!
+//! The converted math is annotated with the original TeX source, which can be used by e.g. screen
+//! readers or for copying.
+//!
+//! # Examples
+//!
+//! ```
+//! let mathml = jotdown::mathml::to_string(r"\frac{1}{x^2}", false);
+//! assert_eq!(
+//!     mathml,
+//!     concat!(
+//!         "<math><semantics>",
+//!         "<mfrac><mn>1</mn><msup><mi>x</mi><mn>2</mn></msup></mfrac>",
+//!         r#"<annotation encoding="application/x-tex">\frac{1}{x^2}</annotation>"#,
+//!         "</semantics></math>",
+//!     ),
+//! );
+//! ```
+//!
+//! With the `html` feature, the HTML renderer can emit MathML for math, see
+//! `html::Renderer::with_mathml`.
+
+use std::fmt;
+
+/// Convert TeX math to a MathML `<math>` element.
+///
+/// Display math is rendered as a block with `display="block"`, inline math is rendered inline.
+#[must_use]
+pub fn to_string(tex: &str, display: bool) -> String {
+    let mut s = String::new();
+    s.push_str(if display {
+        r#"<math display="block">"#
+    } else {
+        "<math>"
+    });
+    write_content(tex, &mut s).unwrap();
+    s.push_str("</math>");
+    s
+}
+
+/// Write the content of the MathML `<math>` element for TeX math, i.e. without the `<math>`
+/// element itself.
+///
+/// This can be used to add attributes to the `<math>` element.
+pub fn write_content<W>(tex: &str, mut out: W) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    let mut c = Converter {
+        src: tex,
+        pos: 0,
+        variant: None,
+    };
+    let mut items = Vec::new();
+    loop {
+        items.extend(c.items(None));
+        match c.next() {
+            None => break,
+            Some(Token::Command("\\")) | Some(Token::Char('&')) => {}
+            Some(t) => items.push(error(&t.to_string())),
+        }
+    }
+    out.write_str("<semantics>")?;
+    out.write_str(&row(items))?;
+    out.write_str(r#"<annotation encoding="application/x-tex">"#)?;
+    write_escape(tex, &mut out)?;
+    out.write_str("</annotation></semantics>")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Token<'s> {
+    /// A command, e.g. `\alpha`, without the backslash.
+    Command(&'s str),
+    Char(char),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command(name) => write!(f, "\\{}", name),
+            Self::Char(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+/// Class of a symbol, i.e. the element it is written as.
+#[derive(Clone, Copy)]
+enum Class {
+    /// An identifier, `<mi>`.
+    Ident,
+    /// An operator, `<mo>`.
+    Op,
+    /// A large operator with limits above and below in display math, `<mo>`.
+    Limits,
+}
+
+struct Converter<'s> {
+    src: &'s str,
+    pos: usize,
+    /// Current `mathvariant` of identifiers, set by font commands.
+    variant: Option<&'static str>,
+}
+
+impl<'s> Converter<'s> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<Token<'s>> {
+        self.skip_whitespace();
+        self.token().map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token<'s>> {
+        self.skip_whitespace();
+        self.token().map(|(t, len)| {
+            self.pos += len;
+            t
+        })
+    }
+
+    /// Read the token at the current position, and its length.
+    fn token(&self) -> Option<(Token<'s>, usize)> {
+        let rest = &self.src[self.pos..];
+        let c = rest.chars().next()?;
+        if c == '\\' {
+            let name = &rest[1..];
+            let len = name
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(name.len());
+            let len = if len == 0 {
+                name.chars().next().map_or(0, char::len_utf8)
+            } else {
+                len
+            };
+            Some((Token::Command(&name[..len]), 1 + len))
+        } else {
+            Some((Token::Char(c), c.len_utf8()))
+        }
+    }
+
+    /// Read a group of raw text, e.g. the argument of `\text`, without the braces.
+    fn raw_group(&mut self) -> &'s str {
+        self.skip_whitespace();
+        let rest = &self.src[self.pos..];
+        if !rest.starts_with('{') {
+            let len = rest.chars().next().map_or(0, char::len_utf8);
+            self.pos += len;
+            return &rest[..len];
+        }
+        let mut depth = 0;
+        let mut escaped = false;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '{' if !escaped => depth += 1,
+                '}' if !escaped => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += i + 1;
+                        return &rest[1..i];
+                    }
+                }
+                _ => {}
+            }
+            escaped = !escaped && c == '\\';
+        }
+        self.pos = self.src.len();
+        &rest[1..]
+    }
+
+    fn at_end(&mut self, close: Option<char>) -> bool {
+        match self.peek() {
+            None | Some(Token::Char('}' | '&')) => true,
+            Some(Token::Command("\\" | "end" | "right")) => true,
+            Some(Token::Char(c)) => Some(c) == close,
+            _ => false,
+        }
+    }
+
+    /// Parse a sequence of items until a closing brace, column or row separator, end of an
+    /// environment or `\right`, or the given closing character.
+    fn items(&mut self, close: Option<char>) -> Vec<String> {
+        let mut items = Vec::new();
+        while !self.at_end(close) {
+            let item = self.item();
+            if !item.is_empty() {
+                items.push(item);
+            }
+        }
+        items
+    }
+
+    /// Parse an atom with its scripts.
+    fn item(&mut self) -> String {
+        let (base, class) = if matches!(self.peek(), Some(Token::Char('^' | '_'))) {
+            ("<mrow></mrow>".to_string(), Class::Ident)
+        } else {
+            self.atom(false)
+        };
+        let mut sub = None;
+        let mut sup = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Char('_')) if sub.is_none() => {
+                    self.next();
+                    sub = Some(self.arg());
+                }
+                Some(Token::Char('^')) if sup.iter().all(|s| s == "<mo>′</mo>") => {
+                    self.next();
+                    sup.push(self.arg());
+                }
+                Some(Token::Char('\'')) if sup.iter().all(|s| s == "<mo>′</mo>") => {
+                    self.next();
+                    sup.push("<mo>′</mo>".to_string());
+                }
+                _ => break,
+            }
+        }
+        let limits = matches!(class, Class::Limits);
+        match (sub, sup.is_empty()) {
+            (None, true) => base,
+            (Some(sub), true) => {
+                let tag = if limits { "munder" } else { "msub" };
+                format!("<{}>{}{}</{}>", tag, base, sub, tag)
+            }
+            (None, false) => {
+                let tag = if limits { "mover" } else { "msup" };
+                format!("<{}>{}{}</{}>", tag, base, row(sup), tag)
+            }
+            (Some(sub), false) => {
+                let tag = if limits { "munderover" } else { "msubsup" };
+                format!("<{}>{}{}{}</{}>", tag, base, sub, row(sup), tag)
+            }
+        }
+    }
+
+    /// Parse the argument of a command or script, a group or a single token.
+    fn arg(&mut self) -> String {
+        if self.at_end(None) {
+            "<mrow></mrow>".to_string()
+        } else {
+            self.atom(true).0
+        }
+    }
+
+    /// Parse a single atom, without scripts. If `single`, numbers are only one digit long.
+    fn atom(&mut self, single: bool) -> (String, Class) {
+        let t = if let Some(t) = self.next() {
+            t
+        } else {
+            return (String::new(), Class::Ident);
+        };
+        let s = match t {
+            Token::Char('{') => {
+                let items = self.items(None);
+                if self.peek() == Some(Token::Char('}')) {
+                    self.next();
+                }
+                if items.len() == 1 {
+                    items.into_iter().next().unwrap()
+                } else {
+                    format!("<mrow>{}</mrow>", items.concat())
+                }
+            }
+            Token::Char(c) if c.is_ascii_digit() => {
+                let start = self.pos - 1;
+                if !single {
+                    let rest = &self.src[self.pos..];
+                    let mut len = rest
+                        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                        .unwrap_or(rest.len());
+                    while rest[..len].ends_with('.') {
+                        len -= 1;
+                    }
+                    self.pos += len;
+                }
+                element("mn", None, &self.src[start..self.pos])
+            }
+            Token::Char(c) if c.is_alphabetic() => {
+                element("mi", self.variant, c.encode_utf8(&mut [0; 4]))
+            }
+            Token::Char('~') => element("mtext", None, "\u{a0}"),
+            Token::Char('\'') => "<mo>′</mo>".to_string(),
+            Token::Char(c) => element("mo", None, c.encode_utf8(&mut [0; 4])),
+            Token::Command(name) => return self.command(name),
+        };
+        (s, Class::Ident)
+    }
+
+    fn command(&mut self, name: &'s str) -> (String, Class) {
+        let s = match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let num = self.arg();
+                let den = self.arg();
+                format!("<mfrac>{}{}</mfrac>", num, den)
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.arg();
+                let k = self.arg();
+                format!(
+                    r#"<mrow><mo>(</mo><mfrac linethickness="0">{}{}</mfrac><mo>)</mo></mrow>"#,
+                    n, k
+                )
+            }
+            "sqrt" => {
+                if self.peek() == Some(Token::Char('[')) {
+                    self.next();
+                    let index = row(self.items(Some(']')));
+                    if self.peek() == Some(Token::Char(']')) {
+                        self.next();
+                    }
+                    let base = self.arg();
+                    format!("<mroot>{}{}</mroot>", base, index)
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.arg())
+                }
+            }
+            "text" | "textrm" | "textnormal" | "mbox" => element("mtext", None, self.raw_group()),
+            "textbf" => element("mtext", Some("bold"), self.raw_group()),
+            "textit" => element("mtext", Some("italic"), self.raw_group()),
+            "operatorname" => element("mi", None, self.raw_group()),
+            "mathrm" | "mathbf" | "mathit" | "mathbb" | "mathcal" | "mathfrak" | "mathsf"
+            | "mathtt" | "boldsymbol" => {
+                let variant = self.variant;
+                self.variant = Some(match name {
+                    "mathrm" => "normal",
+                    "mathbf" => "bold",
+                    "mathit" => "italic",
+                    "mathbb" => "double-struck",
+                    "mathcal" => "script",
+                    "mathfrak" => "fraktur",
+                    "mathsf" => "sans-serif",
+                    "mathtt" => "monospace",
+                    _ => "bold-italic",
+                });
+                let arg = self.arg();
+                self.variant = variant;
+                arg
+            }
+            "hat" | "widehat" | "bar" | "overline" | "vec" | "overrightarrow" | "dot" | "ddot"
+            | "tilde" | "widetilde" => {
+                let accent = match name {
+                    "hat" | "widehat" => "^",
+                    "bar" | "overline" => "‾",
+                    "vec" | "overrightarrow" => "→",
+                    "dot" => "˙",
+                    "ddot" => "¨",
+                    _ => "~",
+                };
+                format!(
+                    r#"<mover accent="true">{}<mo>{}</mo></mover>"#,
+                    self.arg(),
+                    accent
+                )
+            }
+            "underline" => format!(
+                r#"<munder accentunder="true">{}<mo>_</mo></munder>"#,
+                self.arg()
+            ),
+            "left" => {
+                let open = self.delimiter();
+                let items = self.items(None);
+                let close = if self.peek() == Some(Token::Command("right")) {
+                    self.next();
+                    self.delimiter()
+                } else {
+                    None
+                };
+                fenced(open, &items.concat(), close)
+            }
+            "begin" => self.environment(),
+            "big" | "Big" | "bigg" | "Bigg" | "bigl" | "Bigl" | "biggl" | "Biggl" | "bigr"
+            | "Bigr" | "biggr" | "Biggr" | "displaystyle" | "textstyle" | "limits" | "nolimits" => {
+                String::new()
+            }
+            "," => r#"<mspace width="0.1667em"/>"#.to_string(),
+            ":" | ">" => r#"<mspace width="0.2222em"/>"#.to_string(),
+            ";" => r#"<mspace width="0.2778em"/>"#.to_string(),
+            "!" => r#"<mspace width="-0.1667em"/>"#.to_string(),
+            " " => element("mtext", None, "\u{a0}"),
+            "quad" => r#"<mspace width="1em"/>"#.to_string(),
+            "qquad" => r#"<mspace width="2em"/>"#.to_string(),
+            _ => {
+                return if let Some((class, s)) = symbol(name) {
+                    let s = match class {
+                        Class::Ident if self.variant.is_none() && is_upper_greek(s) => {
+                            element("mi", Some("normal"), s)
+                        }
+                        Class::Ident => element("mi", self.variant, s),
+                        Class::Op => element("mo", None, s),
+                        Class::Limits if s.chars().count() > 1 => {
+                            format!(r#"<mo movablelimits="true" form="prefix">{}</mo>"#, s)
+                        }
+                        Class::Limits => element("mo", None, s),
+                    };
+                    (s, class)
+                } else {
+                    (error(&Token::Command(name).to_string()), Class::Ident)
+                };
+            }
+        };
+        (s, Class::Ident)
+    }
+
+    /// Parse the delimiter after `\left` or `\right`, `None` for the empty delimiter `.`.
+    fn delimiter(&mut self) -> Option<&'s str> {
+        match self.next()? {
+            Token::Char('.') => None,
+            Token::Char(c) => DELIMITERS.iter().find(|d| d.starts_with(c)).copied(),
+            Token::Command(name) => symbol(name).map(|(_, s)| s),
+        }
+    }
+
+    /// Parse an environment after `\begin`.
+    fn environment(&mut self) -> String {
+        let name = self.raw_group();
+        if name == "array" {
+            // column specification
+            self.raw_group();
+        }
+        let mut rows = Vec::new();
+        let mut cells = Vec::new();
+        loop {
+            cells.push(row(self.items(None)));
+            match self.peek() {
+                Some(Token::Char('&')) => {}
+                Some(Token::Command("\\")) => rows.push(std::mem::take(&mut cells)),
+                Some(Token::Command("end")) => {
+                    self.next();
+                    self.raw_group();
+                    break;
+                }
+                _ => break,
+            }
+            self.next();
+        }
+        if cells.len() > 1 || cells.first().map_or(false, |c| c != "<mrow></mrow>") {
+            rows.push(cells);
+        }
+
+        let (open, close, align) = match name {
+            "pmatrix" => (Some("("), Some(")"), None),
+            "bmatrix" => (Some("["), Some("]"), None),
+            "Bmatrix" => (Some("{"), Some("}"), None),
+            "vmatrix" => (Some("|"), Some("|"), None),
+            "Vmatrix" => (Some("‖"), Some("‖"), None),
+            "cases" => (Some("{"), None, Some("left")),
+            "aligned" | "align" | "align*" | "split" => (None, None, Some("right left")),
+            _ => (None, None, None),
+        };
+        let mut table = String::new();
+        if let Some(align) = align {
+            table.push_str(&format!(r#"<mtable columnalign="{}">"#, align));
+        } else {
+            table.push_str("<mtable>");
+        }
+        for cells in rows {
+            table.push_str("<mtr>");
+            for cell in cells {
+                table.push_str("<mtd>");
+                table.push_str(&cell);
+                table.push_str("</mtd>");
+            }
+            table.push_str("</mtr>");
+        }
+        table.push_str("</mtable>");
+        if open.is_some() || close.is_some() {
+            fenced(open, &table, close)
+        } else {
+            table
+        }
+    }
+}
+
+/// Combine items into a single element.
+fn row(items: Vec<String>) -> String {
+    if items.len() == 1 {
+        items.into_iter().next().unwrap()
+    } else {
+        format!("<mrow>{}</mrow>", items.concat())
+    }
+}
+
+fn fenced(open: Option<&str>, content: &str, close: Option<&str>) -> String {
+    let mut s = "<mrow>".to_string();
+    if let Some(open) = open {
+        s.push_str(r#"<mo fence="true" form="prefix">"#);
+        write_escape(open, &mut s).unwrap();
+        s.push_str("</mo>");
+    }
+    s.push_str(content);
+    if let Some(close) = close {
+        s.push_str(r#"<mo fence="true" form="postfix">"#);
+        write_escape(close, &mut s).unwrap();
+        s.push_str("</mo>");
+    }
+    s.push_str("</mrow>");
+    s
+}
+
+fn element(tag: &str, variant: Option<&str>, text: &str) -> String {
+    let mut s = format!("<{}", tag);
+    if let Some(variant) = variant {
+        s.push_str(&format!(r#" mathvariant="{}""#, variant));
+    }
+    s.push('>');
+    write_escape(text, &mut s).unwrap();
+    s.push_str(&format!("</{}>", tag));
+    s
+}
+
+fn error(text: &str) -> String {
+    format!("<merror>{}</merror>", element("mtext", None, text))
+}
+
+fn write_escape<W>(mut s: &str, mut out: W) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    while let Some(i) = s.find(|c| matches!(c, '<' | '>' | '&')) {
+        out.write_str(&s[..i])?;
+        out.write_str(match &s[i..=i] {
+            "<" => "&lt;",
+            ">" => "&gt;",
+            _ => "&amp;",
+        })?;
+        s = &s[i + 1..];
+    }
+    out.write_str(s)
+}
+
+const DELIMITERS: &[&str] = &["(", ")", "[", "]", "|", "/", "<", ">"];
+
+/// Look up the symbol of a command.
+fn symbol(name: &str) -> Option<(Class, &str)> {
+    use Class::*;
+    Some(match name {
+        "alpha" => (Ident, "α"),
+        "beta" => (Ident, "β"),
+        "gamma" => (Ident, "γ"),
+        "delta" => (Ident, "δ"),
+        "epsilon" => (Ident, "ϵ"),
+        "varepsilon" => (Ident, "ε"),
+        "zeta" => (Ident, "ζ"),
+        "eta" => (Ident, "η"),
+        "theta" => (Ident, "θ"),
+        "vartheta" => (Ident, "ϑ"),
+        "iota" => (Ident, "ι"),
+        "kappa" => (Ident, "κ"),
+        "lambda" => (Ident, "λ"),
+        "mu" => (Ident, "μ"),
+        "nu" => (Ident, "ν"),
+        "xi" => (Ident, "ξ"),
+        "omicron" => (Ident, "ο"),
+        "pi" => (Ident, "π"),
+        "varpi" => (Ident, "ϖ"),
+        "rho" => (Ident, "ρ"),
+        "varrho" => (Ident, "ϱ"),
+        "sigma" => (Ident, "σ"),
+        "varsigma" => (Ident, "ς"),
+        "tau" => (Ident, "τ"),
+        "upsilon" => (Ident, "υ"),
+        "phi" => (Ident, "ϕ"),
+        "varphi" => (Ident, "φ"),
+        "chi" => (Ident, "χ"),
+        "psi" => (Ident, "ψ"),
+        "omega" => (Ident, "ω"),
+        "Gamma" => (Ident, "Γ"),
+        "Delta" => (Ident, "Δ"),
+        "Theta" => (Ident, "Θ"),
+        "Lambda" => (Ident, "Λ"),
+        "Xi" => (Ident, "Ξ"),
+        "Pi" => (Ident, "Π"),
+        "Sigma" => (Ident, "Σ"),
+        "Upsilon" => (Ident, "Υ"),
+        "Phi" => (Ident, "Φ"),
+        "Psi" => (Ident, "Ψ"),
+        "Omega" => (Ident, "Ω"),
+        "infty" => (Ident, "∞"),
+        "partial" => (Ident, "∂"),
+        "nabla" => (Ident, "∇"),
+        "emptyset" | "varnothing" => (Ident, "∅"),
+        "ell" => (Ident, "ℓ"),
+        "hbar" => (Ident, "ℏ"),
+        "Re" => (Ident, "ℜ"),
+        "Im" => (Ident, "ℑ"),
+        "aleph" => (Ident, "ℵ"),
+        "angle" => (Ident, "∠"),
+        "top" => (Ident, "⊤"),
+        "bot" => (Ident, "⊥"),
+        "arccos" | "arcsin" | "arctan" | "arg" | "cos" | "cosh" | "cot" | "coth" | "csc"
+        | "deg" | "det" | "dim" | "exp" | "gcd" | "hom" | "ker" | "lg" | "ln" | "log" | "Pr"
+        | "sec" | "sin" | "sinh" | "tan" | "tanh" => (Ident, name),
+        "lim" | "liminf" | "limsup" | "max" | "min" | "sup" | "inf" => (Limits, name),
+        "sum" => (Limits, "∑"),
+        "prod" => (Limits, "∏"),
+        "coprod" => (Limits, "∐"),
+        "bigcup" => (Limits, "⋃"),
+        "bigcap" => (Limits, "⋂"),
+        "bigoplus" => (Limits, "⨁"),
+        "bigotimes" => (Limits, "⨂"),
+        "int" => (Op, "∫"),
+        "iint" => (Op, "∬"),
+        "iiint" => (Op, "∭"),
+        "oint" => (Op, "∮"),
+        "times" => (Op, "×"),
+        "cdot" | "cdotp" => (Op, "⋅"),
+        "pm" => (Op, "±"),
+        "mp" => (Op, "∓"),
+        "div" => (Op, "÷"),
+        "ast" => (Op, "∗"),
+        "star" => (Op, "⋆"),
+        "circ" => (Op, "∘"),
+        "bullet" => (Op, "∙"),
+        "leq" | "le" => (Op, "≤"),
+        "geq" | "ge" => (Op, "≥"),
+        "neq" | "ne" => (Op, "≠"),
+        "approx" => (Op, "≈"),
+        "equiv" => (Op, "≡"),
+        "sim" => (Op, "∼"),
+        "simeq" => (Op, "≃"),
+        "cong" => (Op, "≅"),
+        "propto" => (Op, "∝"),
+        "ll" => (Op, "≪"),
+        "gg" => (Op, "≫"),
+        "in" => (Op, "∈"),
+        "notin" => (Op, "∉"),
+        "ni" => (Op, "∋"),
+        "subset" => (Op, "⊂"),
+        "supset" => (Op, "⊃"),
+        "subseteq" => (Op, "⊆"),
+        "supseteq" => (Op, "⊇"),
+        "cup" => (Op, "∪"),
+        "cap" => (Op, "∩"),
+        "setminus" => (Op, "∖"),
+        "wedge" | "land" => (Op, "∧"),
+        "vee" | "lor" => (Op, "∨"),
+        "neg" | "lnot" => (Op, "¬"),
+        "forall" => (Op, "∀"),
+        "exists" => (Op, "∃"),
+        "oplus" => (Op, "⊕"),
+        "otimes" => (Op, "⊗"),
+        "mid" => (Op, "∣"),
+        "parallel" => (Op, "∥"),
+        "perp" => (Op, "⟂"),
+        "to" | "rightarrow" => (Op, "→"),
+        "leftarrow" | "gets" => (Op, "←"),
+        "leftrightarrow" => (Op, "↔"),
+        "Rightarrow" => (Op, "⇒"),
+        "Leftarrow" => (Op, "⇐"),
+        "Leftrightarrow" => (Op, "⇔"),
+        "implies" => (Op, "⟹"),
+        "iff" => (Op, "⟺"),
+        "mapsto" => (Op, "↦"),
+        "uparrow" => (Op, "↑"),
+        "downarrow" => (Op, "↓"),
+        "ldots" | "dots" => (Op, "…"),
+        "cdots" => (Op, "⋯"),
+        "vdots" => (Op, "⋮"),
+        "ddots" => (Op, "⋱"),
+        "langle" => (Op, "⟨"),
+        "rangle" => (Op, "⟩"),
+        "lfloor" => (Op, "⌊"),
+        "rfloor" => (Op, "⌋"),
+        "lceil" => (Op, "⌈"),
+        "rceil" => (Op, "⌉"),
+        "{" | "lbrace" => (Op, "{"),
+        "}" | "rbrace" => (Op, "}"),
+        "|" | "Vert" => (Op, "‖"),
+        "vert" | "lvert" | "rvert" => (Op, "|"),
+        "colon" => (Op, ":"),
+        "%" => (Op, "%"),
+        "$" => (Op, "$"),
+        "#" => (Op, "#"),
+        "&" => (Op, "&"),
+        "_" => (Op, "_"),
+        _ => return None,
+    })
+}
+
+/// Uppercase Greek letters are upright in TeX, unlike other single letter identifiers.
+fn is_upper_greek(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!((chars.next(), chars.next()), (Some('Α'..='Ω'), None))
+}
+
+#[cfg(test)]
+mod test {
+    macro_rules! test_mathml {
+        ($src:expr, $expected:expr $(,)?) => {
+            let mut actual = String::new();
+            super::write_content($src, &mut actual).unwrap();
+            let end = actual.find("<annotation").unwrap();
+            assert_eq!(
+                &actual["<semantics>".len()..end],
+                $expected,
+                "\n\n{}\n\n",
+                $src
+            );
+        };
+    }
+
+    #[test]
+    fn empty() {
+        test_mathml!("", "<mrow></mrow>");
+    }
+
+    #[test]
+    fn tokens() {
+        test_mathml!(
+            "x1 + 3.14 < \\alpha\\Gamma",
+            concat!(
+                "<mrow>",
+                "<mi>x</mi><mn>1</mn><mo>+</mo><mn>3.14</mn><mo>&lt;</mo>",
+                r#"<mi>α</mi><mi mathvariant="normal">Γ</mi>"#,
+                "</mrow>",
+            ),
+        );
+    }
+
+    #[test]
+    fn scripts() {
+        test_mathml!(
+            "x_i^2 y'^{10} z^12",
+            concat!(
+                "<mrow>",
+                "<msubsup><mi>x</mi><mi>i</mi><mn>2</mn></msubsup>",
+                "<msup><mi>y</mi><mrow><mo>′</mo><mn>10</mn></mrow></msup>",
+                "<msup><mi>z</mi><mn>1</mn></msup><mn>2</mn>",
+                "</mrow>",
+            ),
+        );
+    }
+
+    #[test]
+    fn limits() {
+        test_mathml!(
+            r"\sum_{i=0}^n \int_0^1 \lim_{x \to 0}",
+            concat!(
+                "<mrow>",
+                "<munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>0</mn></mrow><mi>n</mi>",
+                "</munderover>",
+                "<msubsup><mo>∫</mo><mn>0</mn><mn>1</mn></msubsup>",
+                r#"<munder><mo movablelimits="true" form="prefix">lim</mo>"#,
+                "<mrow><mi>x</mi><mo>→</mo><mn>0</mn></mrow></munder>",
+                "</mrow>",
+            ),
+        );
+    }
+
+    #[test]
+    fn fractions_roots() {
+        test_mathml!(
+            r"\frac12 \binom{n}{k} \sqrt[3]{x} \sqrt{2}",
+            concat!(
+                "<mrow>",
+                "<mfrac><mn>1</mn><mn>2</mn></mfrac>",
+                r#"<mrow><mo>(</mo><mfrac linethickness="0"><mi>n</mi><mi>k</mi></mfrac>"#,
+                "<mo>)</mo></mrow>",
+                "<mroot><mi>x</mi><mn>3</mn></mroot>",
+                "<msqrt><mn>2</mn></msqrt>",
+                "</mrow>",
+            ),
+        );
+    }
+
+    #[test]
+    fn fonts_text() {
+        test_mathml!(
+            r"\mathbf{v} \hat x \text{if } x \operatorname{sgn}",
+            concat!(
+                "<mrow>",
+                r#"<mi mathvariant="bold">v</mi>"#,
+                r#"<mover accent="true"><mi>x</mi><mo>^</mo></mover>"#,
+                "<mtext>if </mtext><mi>x</mi><mi>sgn</mi>",
+                "</mrow>",
+            ),
+        );
+    }
+
+    #[test]
+    fn delimiters() {
+        test_mathml!(
+            r"\left\{ a \right.",
+            r#"<mrow><mo fence="true" form="prefix">{</mo><mi>a</mi></mrow>"#,
+        );
+    }
+
+    #[test]
+    fn matrix() {
+        test_mathml!(
+            r"\begin{pmatrix} a & b \\ c & d \\ \end{pmatrix}",
+            concat!(
+                r#"<mrow><mo fence="true" form="prefix">(</mo>"#,
+                "<mtable>",
+                "<mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr>",
+                "<mtr><mtd><mi>c</mi></mtd><mtd><mi>d</mi></mtd></mtr>",
+                "</mtable>",
+                r#"<mo fence="true" form="postfix">)</mo></mrow>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn cases() {
+        test_mathml!(
+            r"\begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \end{cases}",
+            concat!(
+                r#"<mrow><mo fence="true" form="prefix">{</mo>"#,
+                r#"<mtable columnalign="left">"#,
+                "<mtr><mtd><mn>1</mn></mtd><mtd><mrow><mi>x</mi><mo>&gt;</mo><mn>0</mn></mrow>",
+                "</mtd></mtr>",
+                "<mtr><mtd><mn>0</mn></mtd><mtd><mtext>otherwise</mtext></mtd></mtr>",
+                "</mtable></mrow>",
+            ),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        test_mathml!(
+            r"\foo x}",
+            concat!(
+                "<mrow>",
+                r"<merror><mtext>\foo</mtext></merror><mi>x</mi>",
+                "<merror><mtext>}</mtext></merror>",
+                "</mrow>",
+            ),
+        );
+    }
+}
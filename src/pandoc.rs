@@ -0,0 +1,889 @@
+//! A renderer that takes an iterator of [`Event`]s and emits the JSON representation of the
+//! [Pandoc](https://pandoc.org) abstract syntax tree, as read by `pandoc -f json`.
+//!
+//! The output targets version 1.23 of the `pandoc-types` API, used by Pandoc 3. Djot elements
+//! without a Pandoc counterpart are represented the same way as by Pandoc's own Djot reader, e.g.
+//! highlighted text becomes a `Span` with the `mark` class and inserted text becomes `Underline`.
+//! Sections are not represented, their ids are placed on the headings instead.
+
+use crate::Alignment;
+use crate::Attributes;
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+use crate::LinkType;
+use crate::ListKind;
+use crate::Map;
+use crate::OrderedListNumbering;
+use crate::OrderedListStyle;
+use crate::Render;
+use crate::RenderRef;
+
+/// Version of the Pandoc API that the output adheres to.
+const API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("hello _world_");
+/// assert_eq!(
+///     jotdown::pandoc::render_to_string(events),
+///     concat!(
+///         r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":["#,
+///         r#"{"t":"Para","c":[{"t":"Str","c":"hello"},{"t":"Space"},"#,
+///         r#"{"t":"Emph","c":[{"t":"Str","c":"world"}]}]}]}"#,
+///     ),
+/// );
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer.push(events, &mut s).unwrap();
+    s
+}
+
+/// [`Render`] implementor that writes the Pandoc JSON AST.
+///
+/// NOTE: Footnotes are placed at their references but may be defined after them, so the whole
+/// document is built before any output is written.
+#[derive(Clone, Default)]
+pub struct Renderer;
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut b = Builder::new();
+        events.for_each(|e| b.event(&e));
+        b.finish(&mut out)
+    }
+}
+
+impl RenderRef for Renderer {
+    fn push_ref<'s, E, I, W>(&self, events: I, mut out: W) -> std::fmt::Result
+    where
+        E: AsRef<Event<'s>>,
+        I: Iterator<Item = E>,
+        W: std::fmt::Write,
+    {
+        let mut b = Builder::new();
+        events.for_each(|e| b.event(e.as_ref()));
+        b.finish(&mut out)
+    }
+}
+
+/// A JSON value of the Pandoc AST.
+enum Json<'s> {
+    Null,
+    Int(u64),
+    Str(String),
+    Array(Vec<Json<'s>>),
+    /// An element, `{"t":tag}` or `{"t":tag,"c":content}`.
+    Elem(&'static str, Option<Box<Json<'s>>>),
+    /// A note with the content of the footnote with the given label.
+    Note(CowStr<'s>),
+    /// A left quote that has not yet been matched by a right quote.
+    LeftQuote(&'static str),
+}
+
+fn tag<'s>(t: &'static str) -> Json<'s> {
+    Json::Elem(t, None)
+}
+
+fn elem<'s>(t: &'static str, c: Json<'s>) -> Json<'s> {
+    Json::Elem(t, Some(Box::new(c)))
+}
+
+fn string<'s>(s: &str) -> Json<'s> {
+    Json::Str(s.to_string())
+}
+
+/// An open container and its content.
+struct Frame<'s> {
+    container: Container<'s>,
+    attrs: Attributes<'s>,
+    children: Vec<Json<'s>>,
+    /// Verbatim text content, e.g. of code blocks.
+    text: String,
+}
+
+struct Builder<'s> {
+    /// Top-level blocks.
+    blocks: Vec<Json<'s>>,
+    stack: Vec<Frame<'s>>,
+    /// Tightness of each open list.
+    lists: Vec<bool>,
+    footnotes: Map<CowStr<'s>, Vec<Json<'s>>>,
+}
+
+impl<'s> Builder<'s> {
+    fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            stack: Vec::new(),
+            lists: Vec::new(),
+            footnotes: Map::new(),
+        }
+    }
+
+    fn children(&mut self) -> &mut Vec<Json<'s>> {
+        if let Some(frame) = self.stack.last_mut() {
+            &mut frame.children
+        } else {
+            &mut self.blocks
+        }
+    }
+
+    /// Whether the text of the current container is kept verbatim.
+    fn verbatim(&self) -> bool {
+        self.stack.last().map_or(false, |f| {
+            matches!(
+                f.container,
+                Container::Verbatim
+                    | Container::Math { .. }
+                    | Container::RawInline { .. }
+                    | Container::RawBlock { .. }
+                    | Container::CodeBlock { .. }
+                    | Container::LinkDefinition { .. }
+                    | Container::Link(_, LinkType::AutoLink | LinkType::Email)
+            )
+        })
+    }
+
+    /// Add text, split into words and spaces.
+    fn text(&mut self, s: &str) {
+        if self.verbatim() {
+            self.stack.last_mut().unwrap().text.push_str(s);
+            return;
+        }
+
+        let children = self.children();
+        let mut s = s;
+        while !s.is_empty() {
+            let n = s.find(' ').unwrap_or(s.len());
+            if n == 0 {
+                if !matches!(children.last(), Some(Json::Elem("Space", _))) {
+                    children.push(tag("Space"));
+                }
+                s = &s[1..];
+            } else {
+                if let Some(Json::Elem("Str", Some(c))) = children.last_mut() {
+                    if let Json::Str(prev) = c.as_mut() {
+                        prev.push_str(&s[..n]);
+                        s = &s[n..];
+                        continue;
+                    }
+                }
+                children.push(elem("Str", string(&s[..n])));
+                s = &s[n..];
+            }
+        }
+    }
+
+    fn event(&mut self, e: &Event<'s>) {
+        match e {
+            Event::Start(c, attrs) => {
+                if let Container::List { tight, .. } = c {
+                    self.lists.push(*tight);
+                }
+                self.stack.push(Frame {
+                    container: c.clone(),
+                    attrs: attrs.clone(),
+                    children: Vec::new(),
+                    text: String::new(),
+                });
+            }
+            Event::End(..) => self.exit(),
+            Event::Str(s) => self.text(s),
+            Event::FootnoteReference(label) => self.children().push(Json::Note(label.clone())),
+            Event::Symbol(sym) => self.text(&format!(":{}:", sym)),
+            Event::LeftSingleQuote => self.children().push(Json::LeftQuote("‘")),
+            Event::RightSingleQuote => self.close_quote("‘", "’"),
+            Event::LeftDoubleQuote => self.children().push(Json::LeftQuote("“")),
+            Event::RightDoubleQuote => self.close_quote("“", "”"),
+            Event::Ellipsis => self.text("…"),
+            Event::EnDash => self.text("–"),
+            Event::EmDash => self.text("—"),
+            Event::NonBreakingSpace => self.text("\u{a0}"),
+            Event::Hardbreak => self.children().push(tag("LineBreak")),
+            Event::Softbreak => self.children().push(tag("SoftBreak")),
+            Event::ThematicBreak(..) => self.children().push(tag("HorizontalRule")),
+            Event::Escape | Event::Blankline | Event::Attributes(..) => {}
+        }
+    }
+
+    /// Wrap the content since a matching left quote in a `Quoted` element, or add an unmatched
+    /// right quote.
+    fn close_quote(&mut self, left: &'static str, right: &str) {
+        let children = self.children();
+        let open = children
+            .iter()
+            .rposition(|c| matches!(c, Json::LeftQuote(l) if *l == left));
+        if let Some(i) = open {
+            let inner = children.split_off(i + 1);
+            children.pop();
+            let ty = if left == "“" {
+                "DoubleQuote"
+            } else {
+                "SingleQuote"
+            };
+            children.push(elem(
+                "Quoted",
+                Json::Array(vec![tag(ty), Json::Array(inner)]),
+            ));
+        } else {
+            self.text(right);
+        }
+    }
+
+    fn exit(&mut self) {
+        let Frame {
+            container,
+            attrs,
+            mut children,
+            text,
+        } = self.stack.pop().unwrap();
+
+        // unmatched left quotes are kept as text
+        for c in &mut children {
+            if let Json::LeftQuote(q) = c {
+                *c = elem("Str", string(q));
+            }
+        }
+
+        let in_tight_item = self.stack.last().map_or(false, |f| {
+            matches!(
+                f.container,
+                Container::ListItem | Container::TaskListItem { .. }
+            )
+        }) && self.lists.last().copied().unwrap_or(false);
+
+        // attributes of inline elements without an Attr are kept by wrapping them in a span
+        let wrap = !attrs.is_empty()
+            && !container.is_block()
+            && !matches!(
+                container,
+                Container::Span
+                    | Container::Mark
+                    | Container::Link(..)
+                    | Container::Image(..)
+                    | Container::Verbatim
+            );
+
+        let node = match container {
+            Container::Blockquote => elem("BlockQuote", Json::Array(children)),
+            Container::List { kind, .. } => {
+                self.lists.pop();
+                let items = Json::Array(children);
+                match kind {
+                    ListKind::Unordered(..) | ListKind::Task(..) => elem("BulletList", items),
+                    ListKind::Ordered {
+                        numbering,
+                        style,
+                        start,
+                    } => {
+                        let numbering = match numbering {
+                            OrderedListNumbering::Decimal => "Decimal",
+                            OrderedListNumbering::AlphaLower => "LowerAlpha",
+                            OrderedListNumbering::AlphaUpper => "UpperAlpha",
+                            OrderedListNumbering::RomanLower => "LowerRoman",
+                            OrderedListNumbering::RomanUpper => "UpperRoman",
+                        };
+                        let delim = match style {
+                            OrderedListStyle::Period => "Period",
+                            OrderedListStyle::Paren => "OneParen",
+                            OrderedListStyle::ParenParen => "TwoParens",
+                        };
+                        let attrs = Json::Array(vec![Json::Int(start), tag(numbering), tag(delim)]);
+                        elem("OrderedList", Json::Array(vec![attrs, items]))
+                    }
+                }
+            }
+            Container::ListItem => Json::Array(children),
+            Container::TaskListItem { checked } => {
+                let checkbox = elem("Str", string(if checked { "☒" } else { "☐" }));
+                match children.first_mut() {
+                    Some(Json::Elem("Plain" | "Para", Some(inlines))) => {
+                        if let Json::Array(inlines) = inlines.as_mut() {
+                            inlines.splice(0..0, [checkbox, tag("Space")]);
+                        }
+                    }
+                    _ => children.insert(0, elem("Plain", Json::Array(vec![checkbox]))),
+                }
+                Json::Array(children)
+            }
+            Container::DescriptionList => elem("DefinitionList", Json::Array(children)),
+            Container::DescriptionTerm => {
+                Json::Array(vec![Json::Array(children), Json::Array(Vec::new())])
+            }
+            Container::DescriptionDetails => {
+                // add the definition to the preceding term
+                if let Some(Json::Array(item)) = self.children().last_mut() {
+                    if let Some(Json::Array(definitions)) = item.get_mut(1) {
+                        definitions.push(Json::Array(children));
+                    }
+                }
+                return;
+            }
+            Container::Footnote { label } => {
+                self.footnotes.entry(label).or_insert(children);
+                return;
+            }
+            Container::Table => table(&attrs, children),
+            Container::TableRow { head } => {
+                Json::Array(vec![Json::Int(u64::from(head)), Json::Array(children)])
+            }
+            Container::TableCell { alignment, .. } => Json::Array(vec![
+                tag(align(alignment)),
+                Json::Array(vec![elem("Plain", Json::Array(children))]),
+            ]),
+            Container::Caption => elem("Caption", Json::Array(children)),
+            Container::Section { .. } => {
+                // sections are not represented, place the contents directly in the parent
+                self.children().append(&mut children);
+                return;
+            }
+            Container::Div { class } => elem(
+                "Div",
+                Json::Array(vec![
+                    attr_triple(&attrs, Some(&class)),
+                    Json::Array(children),
+                ]),
+            ),
+            Container::Paragraph => elem(
+                if in_tight_item { "Plain" } else { "Para" },
+                Json::Array(children),
+            ),
+            Container::Heading {
+                level,
+                has_section,
+                id,
+            } => {
+                // the attributes of a heading are placed on its section
+                let section = self.stack.last().filter(|_| has_section);
+                let mut attr = attr_triple(section.map_or(&attrs, |s| &s.attrs), None);
+                if let Json::Array(a) = &mut attr {
+                    a[0] = Json::Str(id.to_string());
+                }
+                elem(
+                    "Header",
+                    Json::Array(vec![
+                        Json::Int(u64::from(level)),
+                        attr,
+                        Json::Array(children),
+                    ]),
+                )
+            }
+            Container::LinkDefinition { .. } => return,
+            Container::RawBlock { ref format } | Container::RawInline { ref format } => elem(
+                if matches!(container, Container::RawBlock { .. }) {
+                    "RawBlock"
+                } else {
+                    "RawInline"
+                },
+                Json::Array(vec![string(format), Json::Str(text)]),
+            ),
+            Container::CodeBlock { language } => {
+                let mut attr = attr_triple(&attrs, None);
+                if !language.is_empty() {
+                    if let Json::Array(a) = &mut attr {
+                        if let Some(Json::Array(classes)) = a.get_mut(1) {
+                            classes.insert(0, string(&language));
+                        }
+                    }
+                }
+                elem("CodeBlock", Json::Array(vec![attr, Json::Str(text)]))
+            }
+            Container::Span => elem(
+                "Span",
+                Json::Array(vec![attr_triple(&attrs, None), Json::Array(children)]),
+            ),
+            Container::Link(dst, ty) => {
+                let (dst, class, children) = match ty {
+                    LinkType::AutoLink => (
+                        dst.to_string(),
+                        Some("uri"),
+                        vec![elem("Str", Json::Str(text))],
+                    ),
+                    LinkType::Email => (
+                        format!("mailto:{}", dst),
+                        Some("email"),
+                        vec![elem("Str", Json::Str(text))],
+                    ),
+                    LinkType::Span(..) => (dst.to_string(), None, children),
+                };
+                link("Link", &attrs, class, children, dst)
+            }
+            Container::Image(src, ..) => link("Image", &attrs, None, children, src.to_string()),
+            Container::Verbatim => elem(
+                "Code",
+                Json::Array(vec![attr_triple(&attrs, None), Json::Str(text)]),
+            ),
+            Container::Math { display } => elem(
+                "Math",
+                Json::Array(vec![
+                    tag(if display { "DisplayMath" } else { "InlineMath" }),
+                    Json::Str(text),
+                ]),
+            ),
+            Container::Subscript => elem("Subscript", Json::Array(children)),
+            Container::Superscript => elem("Superscript", Json::Array(children)),
+            Container::Insert => elem("Underline", Json::Array(children)),
+            Container::Delete => elem("Strikeout", Json::Array(children)),
+            Container::Strong => elem("Strong", Json::Array(children)),
+            Container::Emphasis => elem("Emph", Json::Array(children)),
+            Container::Mark => elem(
+                "Span",
+                Json::Array(vec![
+                    attr_triple(&attrs, Some("mark")),
+                    Json::Array(children),
+                ]),
+            ),
+        };
+
+        if wrap {
+            let span = Json::Array(vec![attr_triple(&attrs, None), Json::Array(vec![node])]);
+            self.children().push(elem("Span", span));
+        } else {
+            self.children().push(node);
+        }
+    }
+
+    fn finish<W>(mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        while !self.stack.is_empty() {
+            self.exit();
+        }
+        write!(
+            out,
+            r#"{{"pandoc-api-version":[{},{},{}],"meta":{{}},"blocks":"#,
+            API_VERSION[0], API_VERSION[1], API_VERSION[2],
+        )?;
+        let mut w = Writer {
+            footnotes: &self.footnotes,
+            open_footnotes: Vec::new(),
+        };
+        w.write_array(&self.blocks, out)?;
+        out.write_char('}')
+    }
+}
+
+fn align(alignment: Alignment) -> &'static str {
+    match alignment {
+        Alignment::Unspecified => "AlignDefault",
+        Alignment::Left => "AlignLeft",
+        Alignment::Center => "AlignCenter",
+        Alignment::Right => "AlignRight",
+    }
+}
+
+/// Create an `Attr` triple of id, classes and key-value pairs.
+fn attr_triple<'s>(attrs: &Attributes, class: Option<&str>) -> Json<'s> {
+    let mut id = String::new();
+    let mut classes = Vec::new();
+    if let Some(class) = class {
+        classes.extend(class.split_whitespace().map(string));
+    }
+    let mut pairs = Vec::new();
+    for (k, v) in attrs.unique_pairs() {
+        match k {
+            "id" => id = v.to_string(),
+            "class" => classes.extend(v.to_string().split_whitespace().map(string)),
+            _ => pairs.push(Json::Array(vec![string(k), Json::Str(v.to_string())])),
+        }
+    }
+    Json::Array(vec![
+        Json::Str(id),
+        Json::Array(classes),
+        Json::Array(pairs),
+    ])
+}
+
+/// Create a link or image, with the title attribute placed in the target.
+fn link<'s>(
+    t: &'static str,
+    attrs: &Attributes,
+    class: Option<&str>,
+    children: Vec<Json<'s>>,
+    dst: String,
+) -> Json<'s> {
+    let title = attrs
+        .get_value("title")
+        .map_or_else(String::new, |v| v.to_string());
+    let attrs: Attributes = attrs
+        .iter()
+        .filter(|(k, _)| k.key() != Some("title"))
+        .cloned()
+        .collect();
+    elem(
+        t,
+        Json::Array(vec![
+            attr_triple(&attrs, class),
+            Json::Array(children),
+            Json::Array(vec![Json::Str(dst), Json::Str(title)]),
+        ]),
+    )
+}
+
+/// Create a table from its rows and caption.
+fn table<'s>(attrs: &Attributes, children: Vec<Json<'s>>) -> Json<'s> {
+    let mut caption = Vec::new();
+    let mut head = Vec::new();
+    let mut body = Vec::new();
+    let mut alignments: Vec<&'static str> = Vec::new();
+    for c in children {
+        match c {
+            Json::Elem("Caption", Some(inlines)) => {
+                caption.push(elem("Plain", *inlines));
+            }
+            Json::Array(mut row) => {
+                let cells = if let Some(Json::Array(cells)) = row.pop() {
+                    cells
+                } else {
+                    continue;
+                };
+                let is_head = matches!(row.first(), Some(Json::Int(1)));
+                let cells: Vec<Json> = cells
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        let mut cell = if let Json::Array(cell) = cell {
+                            cell
+                        } else {
+                            unreachable!()
+                        };
+                        let content = cell.pop().unwrap();
+                        let alignment = cell.pop().unwrap();
+                        if let Json::Elem(a, _) = alignment {
+                            if alignments.len() <= i {
+                                alignments.push(a);
+                            } else if alignments[i] == "AlignDefault" {
+                                alignments[i] = a;
+                            }
+                        }
+                        Json::Array(vec![
+                            attr_triple(&Attributes::new(), None),
+                            tag("AlignDefault"),
+                            Json::Int(1),
+                            Json::Int(1),
+                            content,
+                        ])
+                    })
+                    .collect();
+                let row = Json::Array(vec![
+                    attr_triple(&Attributes::new(), None),
+                    Json::Array(cells),
+                ]);
+                if is_head && body.is_empty() {
+                    head.push(row);
+                } else {
+                    body.push(row);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let colspecs = alignments
+        .iter()
+        .map(|a| Json::Array(vec![tag(a), tag("ColWidthDefault")]))
+        .collect();
+    let empty_attr = || attr_triple(&Attributes::new(), None);
+    elem(
+        "Table",
+        Json::Array(vec![
+            attr_triple(attrs, None),
+            Json::Array(vec![Json::Null, Json::Array(caption)]),
+            Json::Array(colspecs),
+            Json::Array(vec![empty_attr(), Json::Array(head)]),
+            Json::Array(vec![Json::Array(vec![
+                empty_attr(),
+                Json::Int(0),
+                Json::Array(Vec::new()),
+                Json::Array(body),
+            ])]),
+            Json::Array(vec![empty_attr(), Json::Array(Vec::new())]),
+        ]),
+    )
+}
+
+struct Writer<'a, 's> {
+    footnotes: &'a Map<CowStr<'s>, Vec<Json<'s>>>,
+    /// Footnotes currently being written, to avoid infinite recursion.
+    open_footnotes: Vec<CowStr<'s>>,
+}
+
+impl<'a, 's> Writer<'a, 's> {
+    fn write<W>(&mut self, json: &Json<'s>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match json {
+            Json::Null => out.write_str("null"),
+            Json::Int(n) => write!(out, "{}", n),
+            Json::Str(s) => write_str(s, out),
+            Json::Array(a) => self.write_array(a, out),
+            Json::Elem(t, c) => {
+                write!(out, r#"{{"t":"{}""#, t)?;
+                if let Some(c) = c {
+                    out.write_str(r#","c":"#)?;
+                    self.write(c, out)?;
+                }
+                out.write_char('}')
+            }
+            Json::LeftQuote(q) => write!(out, r#"{{"t":"Str","c":"{}"}}"#, q),
+            Json::Note(label) => {
+                out.write_str(r#"{"t":"Note","c":"#)?;
+                match self.footnotes.get(label) {
+                    Some(blocks) if !self.open_footnotes.contains(label) => {
+                        self.open_footnotes.push(label.clone());
+                        self.write_array(blocks, out)?;
+                        self.open_footnotes.pop();
+                    }
+                    _ => out.write_str("[]")?,
+                }
+                out.write_char('}')
+            }
+        }
+    }
+
+    fn write_array<W>(&mut self, a: &[Json<'s>], out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        out.write_char('[')?;
+        for (i, json) in a.iter().enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            self.write(json, out)?;
+        }
+        out.write_char(']')
+    }
+}
+
+fn write_str<W>(s: &str, out: &mut W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Render;
+
+    macro_rules! test_pandoc {
+        ($src:expr, $expected:expr $(,)?) => {
+            let mut actual = String::new();
+            super::Renderer
+                .push(crate::Parser::new($src), &mut actual)
+                .unwrap();
+            let expected = format!(
+                r#"{{"pandoc-api-version":[1,23,1],"meta":{{}},"blocks":[{}]}}"#,
+                $expected
+            );
+            assert_eq!(actual, expected);
+        };
+    }
+
+    #[test]
+    fn space() {
+        test_pandoc!(
+            "a  b\\*c\\\nd",
+            concat!(
+                r#"{"t":"Para","c":["#,
+                r#"{"t":"Str","c":"a"},{"t":"Space"},{"t":"Str","c":"b*c"},"#,
+                r#"{"t":"LineBreak"},{"t":"Str","c":"d"}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn quoted() {
+        test_pandoc!(
+            "\"a 'b'\" c' \"d",
+            concat!(
+                r#"{"t":"Para","c":["#,
+                r#"{"t":"Quoted","c":[{"t":"DoubleQuote"},["#,
+                r#"{"t":"Str","c":"a"},{"t":"Space"},"#,
+                r#"{"t":"Quoted","c":[{"t":"SingleQuote"},[{"t":"Str","c":"b"}]]}"#,
+                r#"]]},"#,
+                r#"{"t":"Space"},{"t":"Str","c":"c’"},{"t":"Space"},{"t":"Str","c":"“"},"#,
+                r#"{"t":"Str","c":"d"}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn heading() {
+        test_pandoc!(
+            concat!(
+                "{.a k=v}\n", //
+                "# b\n",
+                "\n",
+                "> # c\n",
+            ),
+            concat!(
+                r#"{"t":"Header","c":[1,["b",["a"],[["k","v"]]],[{"t":"Str","c":"b"}]]},"#,
+                r#"{"t":"BlockQuote","c":["#,
+                r#"{"t":"Header","c":[1,["c",[],[]],[{"t":"Str","c":"c"}]]}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn list_ordered() {
+        test_pandoc!(
+            concat!(
+                "(iv) a\n", //
+                "(v) b\n",
+            ),
+            concat!(
+                r#"{"t":"OrderedList","c":["#,
+                r#"[4,{"t":"LowerRoman"},{"t":"TwoParens"}],"#,
+                r#"[[{"t":"Plain","c":[{"t":"Str","c":"a"}]}],"#,
+                r#"[{"t":"Plain","c":[{"t":"Str","c":"b"}]}]]"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn list_task() {
+        test_pandoc!(
+            concat!(
+                "- [x] a\n", //
+                "\n",
+                "- [ ] b\n",
+            ),
+            concat!(
+                r#"{"t":"BulletList","c":["#,
+                r#"[{"t":"Para","c":[{"t":"Str","c":"☒"},{"t":"Space"},{"t":"Str","c":"a"}]}],"#,
+                r#"[{"t":"Para","c":[{"t":"Str","c":"☐"},{"t":"Space"},{"t":"Str","c":"b"}]}]"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn definition_list() {
+        test_pandoc!(
+            concat!(
+                ": a\n", //
+                "\n", "  b\n",
+            ),
+            concat!(
+                r#"{"t":"DefinitionList","c":["#,
+                r#"[[{"t":"Str","c":"a"}],[[{"t":"Para","c":[{"t":"Str","c":"b"}]}]]]"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn table() {
+        test_pandoc!(
+            concat!(
+                "|a|\n", //
+                "|:-|\n", "|b|\n", "^ c\n",
+            ),
+            concat!(
+                r#"{"t":"Table","c":["#,
+                r#"["",[],[]],"#,
+                r#"[null,[{"t":"Plain","c":[{"t":"Str","c":"c"}]}]],"#,
+                r#"[[{"t":"AlignLeft"},{"t":"ColWidthDefault"}]],"#,
+                r#"[["",[],[]],[[["",[],[]],[[["",[],[]],{"t":"AlignDefault"},1,1,"#,
+                r#"[{"t":"Plain","c":[{"t":"Str","c":"a"}]}]]]]]],"#,
+                r#"[[["",[],[]],0,[],[[["",[],[]],[[["",[],[]],{"t":"AlignDefault"},1,1,"#,
+                r#"[{"t":"Plain","c":[{"t":"Str","c":"b"}]}]]]]]]],"#,
+                r#"[["",[],[]],[]]"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn code_math_raw() {
+        test_pandoc!(
+            concat!(
+                "``` rust\n", //
+                "a\n",
+                "```\n",
+                "\n",
+                "$$`b` `c`{=html}\n",
+            ),
+            concat!(
+                r#"{"t":"CodeBlock","c":[["",["rust"],[]],"a\n"]},"#,
+                r#"{"t":"Para","c":["#,
+                r#"{"t":"Math","c":[{"t":"DisplayMath"},"b"]},{"t":"Space"},"#,
+                r#"{"t":"RawInline","c":["html","c"]}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        test_pandoc!(
+            concat!(
+                "{#a}\n", //
+                "::: b\n",
+                "[c]{.d} {=e=} *f*{g=h}\n",
+                ":::\n",
+            ),
+            concat!(
+                r#"{"t":"Div","c":[["a",["b"],[]],["#,
+                r#"{"t":"Para","c":["#,
+                r#"{"t":"Span","c":[["",["d"],[]],[{"t":"Str","c":"c"}]]},{"t":"Space"},"#,
+                r#"{"t":"Span","c":[["",["mark"],[]],[{"t":"Str","c":"e"}]]},{"t":"Space"},"#,
+                r#"{"t":"Span","c":[["",[],[["g","h"]]],[{"t":"Strong","c":[{"t":"Str","c":"f"}]}]]}"#,
+                r#"]}"#,
+                r#"]]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn footnote() {
+        test_pandoc!(
+            concat!(
+                "a[^b]\n", //
+                "\n",
+                "[^b]: c[^b]\n",
+            ),
+            concat!(
+                r#"{"t":"Para","c":["#,
+                r#"{"t":"Str","c":"a"},"#,
+                r#"{"t":"Note","c":[{"t":"Para","c":[{"t":"Str","c":"c"},{"t":"Note","c":[]}]}]}"#,
+                r#"]}"#,
+            ),
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+use std::ops::Range;
+
+/// Unit used to count the columns of a [`Position`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionEncoding {
+    /// Columns are counted in bytes.
+    Utf8,
+    /// Columns are counted in UTF-16 code units, as in e.g. JavaScript strings and the Language
+    /// Server Protocol.
+    Utf16,
+}
+
+impl Default for PositionEncoding {
+    fn default() -> Self {
+        Self::Utf8
+    }
+}
+
+/// A line and column within the input.
+///
+/// Both the line and the column are counted from 1. The column is counted in the unit of the
+/// [`PositionEncoding`] that was used to obtain the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Column number, starting at 1.
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// An index of the lines of an input, used to convert byte offsets to line and column positions.
+///
+/// The index is created by scanning the input once, after which each conversion only requires a
+/// binary search over the lines, and for UTF-16 columns, a scan of the line up to the offset.
+///
+/// # Examples
+///
+/// Find the positions of the events emitted by a [`crate::Parser`]:
+///
+/// ```
+/// # use jotdown::*;
+/// let src = "a\n\n> _b_\n";
+/// let index = LineIndex::new(src);
+/// let emph = Parser::new(src)
+///     .into_offset_iter()
+///     .find(|(e, _)| matches!(e, Event::Start(Container::Emphasis, ..)))
+///     .map(|(_, r)| index.range(r, PositionEncoding::Utf8));
+/// assert_eq!(
+///     emph,
+///     Some(Position { line: 3, column: 3 }..Position { line: 3, column: 4 }),
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineIndex<'s> {
+    src: &'s str,
+    /// Byte offset of the start of each line.
+    lines: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    /// Create an index of the lines of the input.
+    #[must_use]
+    pub fn new(src: &'s str) -> Self {
+        let lines = std::iter::once(0)
+            .chain(
+                src.bytes()
+                    .enumerate()
+                    .filter(|(_, b)| *b == b'\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+        Self { src, lines }
+    }
+
+    /// Returns the number of lines in the input.
+    ///
+    /// A trailing newline begins an additional, empty line.
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Convert a byte offset to a position.
+    ///
+    /// Offsets beyond the end of the input are clamped to the end of the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let index = LineIndex::new("a\n😀b");
+    /// assert_eq!(
+    ///     index.position(6, PositionEncoding::Utf8),
+    ///     Position { line: 2, column: 5 },
+    /// );
+    /// assert_eq!(
+    ///     index.position(6, PositionEncoding::Utf16),
+    ///     Position { line: 2, column: 3 },
+    /// );
+    /// ```
+    #[must_use]
+    pub fn position(&self, offset: usize, encoding: PositionEncoding) -> Position {
+        let offset = offset.min(self.src.len());
+        let line = self.lines.partition_point(|start| *start <= offset) - 1;
+        let start = self.lines[line];
+        let column = match encoding {
+            PositionEncoding::Utf8 => offset - start,
+            PositionEncoding::Utf16 => self.src[start..]
+                .char_indices()
+                .take_while(|(i, _)| start + i < offset)
+                .map(|(_, c)| c.len_utf16())
+                .sum(),
+        };
+        Position {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    /// Convert a byte range, e.g. of an event, to a range of positions.
+    #[must_use]
+    pub fn range(&self, range: Range<usize>, encoding: PositionEncoding) -> Range<Position> {
+        self.position(range.start, encoding)..self.position(range.end, encoding)
+    }
+
+    /// Convert a position to a byte offset.
+    ///
+    /// Positions beyond the end of a line are clamped to the end of that line, and positions
+    /// beyond the last line are clamped to the end of the input. Columns that point inside a
+    /// character are moved to the start of that character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let index = LineIndex::new("a\n😀b\n");
+    /// let pos = Position { line: 2, column: 3 };
+    /// assert_eq!(index.offset(pos, PositionEncoding::Utf16), 6);
+    /// assert_eq!(index.offset(pos, PositionEncoding::Utf8), 2);
+    /// ```
+    #[must_use]
+    pub fn offset(&self, position: Position, encoding: PositionEncoding) -> usize {
+        let line = position.line.max(1) - 1;
+        let start = if let Some(start) = self.lines.get(line) {
+            *start
+        } else {
+            return self.src.len();
+        };
+        let end = self
+            .lines
+            .get(line + 1)
+            .map_or(self.src.len(), |next| next - 1);
+        let text = &self.src[start..end];
+        let column = position.column.max(1) - 1;
+        let mut n = 0;
+        for (i, c) in text.char_indices() {
+            n += match encoding {
+                PositionEncoding::Utf8 => c.len_utf8(),
+                PositionEncoding::Utf16 => c.len_utf16(),
+            };
+            if n > column {
+                return start + i;
+            }
+        }
+        end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LineIndex;
+    use super::Position;
+    use super::PositionEncoding::*;
+
+    fn pos(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn empty() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.position(0, Utf8), pos(1, 1));
+        assert_eq!(index.position(5, Utf16), pos(1, 1));
+        assert_eq!(index.offset(pos(3, 3), Utf8), 0);
+    }
+
+    #[test]
+    fn lines() {
+        let src = concat!(
+            "ab\n", //
+            "\n",   //
+            "c\n",
+        );
+        let index = LineIndex::new(src);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.position(0, Utf8), pos(1, 1));
+        assert_eq!(index.position(2, Utf8), pos(1, 3));
+        assert_eq!(index.position(3, Utf8), pos(2, 1));
+        assert_eq!(index.position(4, Utf8), pos(3, 1));
+        assert_eq!(index.position(6, Utf8), pos(4, 1));
+        assert_eq!(index.range(1..5, Utf8), pos(1, 2)..pos(3, 2));
+    }
+
+    #[test]
+    fn multibyte() {
+        let src = "å😀b\nä";
+        let index = LineIndex::new(src);
+        assert_eq!(index.position(2, Utf8), pos(1, 3));
+        assert_eq!(index.position(2, Utf16), pos(1, 2));
+        assert_eq!(index.position(6, Utf8), pos(1, 7));
+        assert_eq!(index.position(6, Utf16), pos(1, 4));
+        assert_eq!(index.position(10, Utf16), pos(2, 2));
+    }
+
+    #[test]
+    fn offset() {
+        let src = "å😀b\nä";
+        let index = LineIndex::new(src);
+        for i in (0..=src.len()).filter(|i| src.is_char_boundary(*i)) {
+            for enc in [Utf8, Utf16] {
+                assert_eq!(
+                    index.offset(index.position(i, enc), enc),
+                    i,
+                    "{} {:?}",
+                    i,
+                    enc
+                );
+            }
+        }
+        assert_eq!(index.offset(pos(1, 2), Utf8), 0);
+        assert_eq!(index.offset(pos(1, 3), Utf16), 2);
+        assert_eq!(index.offset(pos(1, 100), Utf8), 7);
+        assert_eq!(index.offset(pos(100, 1), Utf8), src.len());
+    }
+}
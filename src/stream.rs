@@ -0,0 +1,590 @@
+use std::collections::VecDeque;
+use std::io;
+use std::ops::Range;
+
+use crate::block;
+use crate::unique_id;
+use crate::Attributes;
+use crate::Container;
+use crate::CowStr;
+use crate::DefaultHeadingIds;
+use crate::Event;
+use crate::HeadingIdGenerator;
+use crate::LinkResolver;
+use crate::Map;
+use crate::Parser;
+use crate::ParserOptions;
+use crate::Set;
+use crate::Syntax;
+
+/// A parser that reads its input incrementally from a reader, with bounded memory.
+///
+/// The input is read line by line and split into chunks at the start of top-level blocks. Each
+/// chunk is parsed separately once the following top-level block has started, so only the
+/// current top-level block, e.g. a single list or a single paragraph, has to be kept in memory.
+/// The events are emitted as owned events, see [`Event::into_owned`].
+///
+/// Automatic sections span across chunks and are emitted as by [`Parser`]. Heading ids are unique
+/// across the whole input. References are resolved by link definitions and headings that appear
+/// earlier in the input, or later in the same chunk, but forward references to link definitions
+/// and headings in later chunks are given up and emitted as unresolved, as resolving them would
+/// require reading the whole input first. Footnotes are emitted where they appear in the input,
+/// as by [`Parser`]. Apart from the current block, only the ids of headings and the link
+/// definitions are kept in memory, in order to resolve references later in the input.
+///
+/// The reader must provide valid UTF-8, otherwise an error of kind
+/// [`io::ErrorKind::InvalidData`] is returned. After an error, no further events are emitted.
+///
+/// # Examples
+///
+/// Convert a document to HTML while reading it:
+///
+/// ```
+/// # #[cfg(feature = "html")]
+/// # {
+/// # use jotdown::*;
+/// let input: &[u8] = b"# Log\n\n[above]: /a\n\nsee [above][] and [below][]\n\n[below]: /b\n";
+/// let mut error = Ok(());
+/// let events = StreamParser::new(input)
+///     .chunk_size(1)
+///     .scan(&mut error, |error, event| event.map_err(|e| **error = Err(e)).ok());
+/// let mut html = String::new();
+/// html::Renderer::minified().push(events, &mut html).unwrap();
+/// error.unwrap();
+/// assert_eq!(
+///     html,
+///     concat!(
+///         "<section id=\"Log\">",
+///         "<h1>Log</h1>",
+///         "<p>see <a href=\"/a\">above</a> and <a>below</a></p>",
+///         "</section>",
+///     ),
+/// );
+/// # }
+/// ```
+pub struct StreamParser<R, G = DefaultHeadingIds> {
+    reader: R,
+    syntax: Syntax,
+    heading_ids: G,
+    /// Minimum number of buffered bytes before the input is split into a chunk.
+    chunk_size: usize,
+    /// Input that has been read but not yet parsed.
+    buf: String,
+    /// Length of the buffer when the next split should be attempted.
+    next_split: usize,
+    /// Definitions of previous chunks.
+    definitions: Definitions,
+    /// Ids of headings in previous chunks.
+    used_ids: Set<String>,
+    sections: Sections,
+    /// Parsed events that have not yet been emitted.
+    events: VecDeque<Event<'static>>,
+    /// The reader has been exhausted or has failed.
+    done: bool,
+}
+
+impl<R: io::BufRead> StreamParser<R> {
+    /// Create a parser that reads from a buffered reader with the default options.
+    ///
+    /// Wrap an unbuffered reader, e.g. a [`std::fs::File`], in a [`io::BufReader`].
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+}
+
+impl<R: io::BufRead, G: HeadingIdGenerator> StreamParser<R, G> {
+    /// Create a parser that reads from a buffered reader with the given options.
+    #[must_use]
+    pub fn with_options(reader: R, options: ParserOptions<G>) -> Self {
+        let chunk_size = 64 * 1024;
+        Self {
+            reader,
+            syntax: options.syntax,
+            heading_ids: options.heading_ids,
+            chunk_size,
+            buf: String::new(),
+            next_split: chunk_size,
+            definitions: Definitions::default(),
+            used_ids: Set::new(),
+            sections: Sections::new(options.syntax.sections, Vec::new()),
+            events: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Set the minimum number of bytes that are buffered before they are parsed, 64 KiB by
+    /// default.
+    ///
+    /// Larger chunks resolve more forward references, smaller chunks require less memory.
+    #[must_use]
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self.next_split = chunk_size;
+        self
+    }
+
+    /// Read the next line and parse a chunk if possible.
+    fn fill(&mut self) -> io::Result<()> {
+        let start = self.buf.len();
+        if self.reader.read_line(&mut self.buf)? == 0 {
+            self.done = true;
+            self.parse_chunk(self.buf.len());
+            let queue = &mut self.events;
+            self.sections.close(0, |e, _| queue.push_back(e));
+        } else if self.buf.len() >= self.next_split && !self.buf[start..].trim().is_empty() {
+            // a new top-level block may only start on a non-blank line
+            if let Some(split) = self.split() {
+                self.parse_chunk(split);
+                self.next_split = self.buf.len() + self.chunk_size;
+            } else {
+                // a single large block, avoid parsing it again for every line
+                self.next_split = 2 * self.buf.len();
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the start of the last top-level block in the buffer, if it is not the first block.
+    fn split(&self) -> Option<usize> {
+        let syntax = Syntax {
+            sections: false,
+            ..self.syntax
+        };
+        block_starts(&self.buf, syntax).last().copied()
+    }
+
+    /// Parse the start of the buffer up to `end` and queue its events.
+    fn parse_chunk(&mut self, end: usize) {
+        if end == 0 {
+            return;
+        }
+
+        // sections are added afterwards, as they may span multiple chunks
+        let syntax = Syntax {
+            sections: false,
+            ..self.syntax
+        };
+        let heading_ids = &mut self.heading_ids;
+        let used_ids = &mut self.used_ids;
+        let options = ParserOptions {
+            syntax,
+            heading_ids: |text: &str| unique_id(heading_ids.heading_id(text), used_ids),
+        };
+        let parser = Parser::with_options(&self.buf[..end], options)
+            .with_link_resolver(|label: &str| self.definitions.resolve(label));
+        let headings = parser.headings();
+        let links: Vec<_> = parser
+            .pre_pass
+            .link_definitions
+            .iter()
+            .map(|(label, (url, attrs))| {
+                (
+                    label.to_string(),
+                    (url.to_string(), attrs.clone().into_owned()),
+                )
+            })
+            .collect();
+        let events: Vec<_> = parser
+            .into_offset_iter()
+            .map(|(e, r)| (e.into_owned(), r))
+            .collect();
+
+        for h in headings {
+            self.definitions.headings.entry(h.text).or_insert(h.id);
+        }
+        self.definitions.links.extend(links);
+        let queue = &mut self.events;
+        for (e, r) in events {
+            self.sections.push(e, r, |e, _| queue.push_back(e));
+        }
+        self.buf.drain(..end);
+    }
+}
+
+impl<R: io::BufRead, G: HeadingIdGenerator> Iterator for StreamParser<R, G> {
+    type Item = io::Result<Event<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.events.is_empty() && !self.done {
+            if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.events.pop_front().map(Ok)
+    }
+}
+
+/// Link definitions and headings outside of a chunk of the input, used to resolve references
+/// within the chunk.
+#[derive(Default)]
+pub(crate) struct Definitions {
+    pub(crate) links: Map<String, (String, Attributes<'static>)>,
+    /// Heading ids by heading text.
+    pub(crate) headings: Map<String, String>,
+}
+
+impl<'s> LinkResolver<'s> for Definitions {
+    fn resolve(&self, label: &str) -> Option<(CowStr<'s>, Attributes<'s>)> {
+        if let Some((url, attrs)) = self.links.get(label) {
+            Some((url.clone().into(), attrs.clone()))
+        } else {
+            self.headings
+                .get(label)
+                .map(|id| (format!("#{}", id).into(), Attributes::new()))
+        }
+    }
+}
+
+/// Find the starts of the lines of all top-level blocks, including their attributes, except the
+/// first block.
+///
+/// Attributes that are separated from the block by blank lines are included as well, as sections
+/// that are closed by a heading end before them.
+///
+/// The input before each start is not affected by the input after it, so the input can be
+/// parsed separately in chunks between the starts.
+pub(crate) fn block_starts(src: &str, syntax: Syntax) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut depth = 0;
+    let mut attributes = None;
+    let mut push = |pos: usize| {
+        let start = src[..pos].rfind('\n').map_or(0, |i| i + 1);
+        if start > 0 {
+            starts.push(start);
+        }
+    };
+    for e in block::parse(src, syntax) {
+        match e.kind {
+            block::EventKind::Enter(..) => {
+                if depth == 0 {
+                    push(attributes.take().unwrap_or(e.span.start));
+                }
+                depth += 1;
+            }
+            block::EventKind::Exit(..) => depth -= 1,
+            block::EventKind::Atom(block::Atom::Attributes) if depth == 0 => {
+                attributes.get_or_insert(e.span.start);
+            }
+            block::EventKind::Atom(block::Atom::ThematicBreak) if depth == 0 => {
+                push(attributes.take().unwrap_or(e.span.start));
+            }
+            _ => {}
+        }
+    }
+    starts
+}
+
+/// Adds automatic sections around top-level headings to events that were parsed without
+/// sections, e.g. in separate chunks.
+pub(crate) struct Sections {
+    enabled: bool,
+    /// Levels and ids of open sections.
+    open: Vec<(u16, String)>,
+    /// Number of open blocks, excluding sections.
+    depth: usize,
+    /// Top-level block attributes, and the blank lines after them, that are not yet followed by
+    /// a block. Sections that are closed by the following heading end before them.
+    pending: Vec<(Event<'static>, Range<usize>)>,
+}
+
+impl Sections {
+    /// Create with the levels and ids of the sections that are already open, at the top level.
+    pub(crate) fn new(enabled: bool, open: Vec<(u16, String)>) -> Self {
+        Self {
+            enabled,
+            open,
+            depth: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Push an event and its range to `out`, preceded by the section events it causes.
+    pub(crate) fn push<F>(&mut self, event: Event<'static>, range: Range<usize>, mut out: F)
+    where
+        F: FnMut(Event<'static>, Range<usize>),
+    {
+        if self.depth == 0 && self.enabled {
+            match event {
+                Event::Attributes(..) => return self.pending.push((event, range)),
+                Event::Blankline if !self.pending.is_empty() => {
+                    return self.pending.push((event, range));
+                }
+                _ => {}
+            }
+        }
+        match event {
+            Event::Start(Container::Heading { level, id, .. }, attrs)
+                if self.depth == 0 && self.enabled =>
+            {
+                let start = self.pending.first().map_or(range.start, |(_, r)| r.start);
+                while self.open.last().map_or(false, |(l, _)| *l >= level) {
+                    let (_, id) = self.open.pop().unwrap();
+                    out(
+                        Event::End(Container::Section { id: id.into() }),
+                        start..start,
+                    );
+                }
+                self.flush(&mut out);
+                self.open.push((level, id.to_string()));
+                // the attributes belong to the section, the heading only to its marker
+                let marker = range.end - usize::from(level)..range.end;
+                out(
+                    Event::Start(Container::Section { id: id.clone() }, attrs),
+                    range.start..marker.start,
+                );
+                out(
+                    Event::Start(
+                        Container::Heading {
+                            level,
+                            has_section: true,
+                            id,
+                        },
+                        Attributes::new(),
+                    ),
+                    marker,
+                );
+                self.depth += 1;
+            }
+            Event::End(Container::Heading { level, id, .. }) if self.depth == 1 && self.enabled => {
+                self.depth -= 1;
+                out(
+                    Event::End(Container::Heading {
+                        level,
+                        has_section: true,
+                        id,
+                    }),
+                    range,
+                );
+            }
+            Event::Start(c, attrs) => {
+                self.flush(&mut out);
+                if c.is_block() {
+                    self.depth += 1;
+                }
+                out(Event::Start(c, attrs), range);
+            }
+            Event::End(c) => {
+                if c.is_block() {
+                    self.depth -= 1;
+                }
+                out(Event::End(c), range);
+            }
+            e => {
+                self.flush(&mut out);
+                out(e, range);
+            }
+        }
+    }
+
+    /// Push the pending block attributes that are not followed by a heading.
+    pub(crate) fn flush<F>(&mut self, mut out: F)
+    where
+        F: FnMut(Event<'static>, Range<usize>),
+    {
+        for (e, r) in self.pending.drain(..) {
+            out(e, r);
+        }
+    }
+
+    /// Close all open sections at the end of the input, at position `end`.
+    pub(crate) fn close<F>(&mut self, end: usize, mut out: F)
+    where
+        F: FnMut(Event<'static>, Range<usize>),
+    {
+        self.flush(&mut out);
+        while let Some((_, id)) = self.open.pop() {
+            out(Event::End(Container::Section { id: id.into() }), end..end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StreamParser;
+    use crate::Container;
+    use crate::Event;
+    use crate::LinkType;
+    use crate::Parser;
+    use crate::SpanLinkType;
+
+    fn stream(src: &str, chunk_size: usize) -> Vec<Event<'static>> {
+        StreamParser::new(src.as_bytes())
+            .chunk_size(chunk_size)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    macro_rules! test_stream {
+        ($src:expr $(,)?) => {
+            let expected: Vec<_> = Parser::new($src).map(Event::into_owned).collect();
+            for chunk_size in [0, 1, 8, $src.len() + 1] {
+                assert_eq!(
+                    stream($src, chunk_size),
+                    expected,
+                    "\n\n{}\n\nchunk size: {}\n\n",
+                    $src,
+                    chunk_size,
+                );
+            }
+        };
+    }
+
+    #[test]
+    fn empty() {
+        test_stream!("");
+    }
+
+    #[test]
+    fn blocks() {
+        test_stream!(concat!(
+            "para\n",         //
+            "graph\n",        //
+            "\n",             //
+            "- a\n",          //
+            "\n",             //
+            "- b\n",          //
+            "\n",             //
+            "  c\n",          //
+            "\n",             //
+            "{.x}\n",         //
+            "> d\n",          //
+            "e\n",            //
+            "***\n",          //
+            "``` rust\n",     //
+            "f\n",            //
+            "\n",             //
+            "```\n",          //
+            "\n",             //
+            "| g | h |\n",    //
+            "\n",             //
+            "^ caption\n",    //
+            "\n",             //
+            "[^i]: j\n",      //
+            "\n",             //
+            "    k\n",        //
+            "\n",             //
+            "::: div\n",      //
+            "l\n",            //
+            "\n",             //
+            "m\n",            //
+            ":::\n",          //
+            "\n",             //
+            "[link]: /url\n", //
+            "\n",             //
+            "[link][]\n",     //
+        ));
+    }
+
+    #[test]
+    fn sections() {
+        test_stream!(concat!(
+            "# a\n",   //
+            "\n",      //
+            "b\n",     //
+            "\n",      //
+            "{#x}\n",  //
+            "## c\n",  //
+            "\n",      //
+            "> # d\n", //
+            "\n",      //
+            "### e\n", //
+            "\n",      //
+            "# a\n",   //
+            "\n",      //
+            "[c][]\n", //
+            "\n",      //
+            "#\n",     //
+        ));
+    }
+
+    #[test]
+    fn sections_attributes() {
+        test_stream!("# a\n\n{#x}\n\n# b\n");
+        test_stream!("# a\n\n{#x}\n\n{.y}\n\n## b\n\n{#z}\n# c\n");
+        test_stream!("# a\n\n{#x}\n\n> b {.y}\n\n{#z}\n");
+        test_stream!("# a\n\nb {.c}\n\n{#x}\n\n***\n\n# d\n");
+    }
+
+    #[test]
+    fn random_inputs() {
+        // no references, which may be given up when they are resolved in a later chunk
+        let pieces = [
+            "\n",
+            "# a\n",
+            "## b\n",
+            "### c\n",
+            "{#x}\n",
+            "{.y}\n",
+            "d\n",
+            "> ",
+            "- ",
+            "  ",
+            "```\n",
+            ":::\n",
+            "***\n",
+            "| e |\n",
+            "[^f]: g\n",
+            "_h_ {.i}\n",
+        ];
+        let mut seed: u64 = 1;
+        let mut rand = |n: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % n
+        };
+        for _ in 0..500 {
+            let src: String = (0..rand(20)).map(|_| pieces[rand(pieces.len())]).collect();
+            test_stream!(src.as_str());
+        }
+    }
+
+    #[test]
+    fn forward_reference() {
+        let src = concat!(
+            "[a][]\n",   //
+            "\n",        //
+            "[b][]\n",   //
+            "\n",        //
+            "[a]: /a\n", //
+            "\n",        //
+            "# b\n",     //
+        );
+        let links = |chunk_size| {
+            stream(src, chunk_size)
+                .into_iter()
+                .filter_map(|e| match e {
+                    Event::Start(Container::Link(url, LinkType::Span(ty)), _) => Some((url, ty)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            links(1),
+            [
+                ("a".into(), SpanLinkType::Unresolved),
+                ("b".into(), SpanLinkType::Unresolved),
+            ],
+        );
+        assert_eq!(
+            links(src.len() + 1),
+            [
+                ("/a".into(), SpanLinkType::Reference),
+                ("#b".into(), SpanLinkType::Reference),
+            ],
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let input: &[u8] = b"a\n\nb\xff\n";
+        let events: Vec<_> = StreamParser::new(input).chunk_size(1).collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].as_ref().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData,
+        );
+    }
+}
@@ -0,0 +1,756 @@
+//! Resolution of symbols, e.g. `:smile:`, to replacement text or events.
+//!
+//! The parser emits an [`Event::Symbol`] for each symbol without interpreting its name, and the
+//! renderers write unresolved symbols back out as `:name:`. This module provides an adapter,
+//! [`resolve`], that replaces the symbols of an event iterator using a [`SymbolResolver`] before
+//! the events are passed to any renderer.
+//!
+//! With the `emoji` feature, a built-in table of emoji with the names used by
+//! [gemoji](https://github.com/github/gemoji), e.g. `smile` or `+1`, is available via `Emoji`
+//! and `emoji`.
+//!
+//! # Examples
+//!
+//! Replace emoji symbols and render to HTML:
+//!
+//! ```
+//! # #[cfg(all(feature = "emoji", feature = "html"))]
+//! # {
+//! use jotdown::symbol;
+//! let events = jotdown::Parser::new("Nice :+1: :unknown:");
+//! let html = jotdown::html::render_to_string(symbol::resolve(events, symbol::Emoji));
+//! assert_eq!(html, "<p>Nice 👍 :unknown:</p>\n");
+//! # }
+//! ```
+//!
+//! Replace custom symbols with images and fall back to emoji for other symbols:
+//!
+//! ```
+//! # #[cfg(all(feature = "emoji", feature = "html"))]
+//! # {
+//! use jotdown::symbol::{self, Replacement, SymbolResolver};
+//! use jotdown::{Attributes, Container, Event, SpanLinkType};
+//! let resolver = |name: &str| {
+//!     if name == "logo" {
+//!         let image = Container::Image("logo.svg".into(), SpanLinkType::Inline);
+//!         Some(Replacement::Events(vec![
+//!             Event::Start(image.clone(), Attributes::new()),
+//!             Event::Str("Logo".into()),
+//!             Event::End(image),
+//!         ]))
+//!     } else {
+//!         symbol::Emoji.resolve(name)
+//!     }
+//! };
+//! let events = jotdown::Parser::new(":logo: :heart:");
+//! let html = jotdown::html::render_to_string(symbol::resolve(events, resolver));
+//! assert_eq!(html, "<p><img alt=\"Logo\" src=\"logo.svg\"> ❤\u{fe0f}</p>\n");
+//! # }
+//! ```
+
+use crate::CowStr;
+use crate::Event;
+
+/// The replacement of a symbol, returned by a [`SymbolResolver`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Replacement<'s> {
+    /// Replace the symbol with text, emitted as an [`Event::Str`].
+    Text(CowStr<'s>),
+    /// Replace the symbol with a sequence of inline events, e.g. an image.
+    Events(Vec<Event<'s>>),
+}
+
+impl<'s> From<&'s str> for Replacement<'s> {
+    fn from(text: &'s str) -> Self {
+        Self::Text(text.into())
+    }
+}
+
+impl From<String> for Replacement<'_> {
+    fn from(text: String) -> Self {
+        Self::Text(text.into())
+    }
+}
+
+/// A resolver of symbol names, see [`resolve`].
+///
+/// The trait is implemented for closures that take the name of a symbol, and for maps from names
+/// to replacement text.
+pub trait SymbolResolver<'s> {
+    /// Resolve the name of a symbol, without the surrounding colons, to its replacement, or `None`
+    /// to keep the symbol.
+    fn resolve(&self, name: &str) -> Option<Replacement<'s>>;
+}
+
+impl<'s, F> SymbolResolver<'s> for F
+where
+    F: Fn(&str) -> Option<Replacement<'s>>,
+{
+    fn resolve(&self, name: &str) -> Option<Replacement<'s>> {
+        self(name)
+    }
+}
+
+impl<'s, K, V, S> SymbolResolver<'s> for std::collections::HashMap<K, V, S>
+where
+    K: std::borrow::Borrow<str> + std::hash::Hash + Eq,
+    V: AsRef<str>,
+    S: std::hash::BuildHasher,
+{
+    fn resolve(&self, name: &str) -> Option<Replacement<'s>> {
+        self.get(name)
+            .map(|text| Replacement::Text(text.as_ref().to_string().into()))
+    }
+}
+
+impl<'s, K, V> SymbolResolver<'s> for std::collections::BTreeMap<K, V>
+where
+    K: std::borrow::Borrow<str> + Ord,
+    V: AsRef<str>,
+{
+    fn resolve(&self, name: &str) -> Option<Replacement<'s>> {
+        self.get(name)
+            .map(|text| Replacement::Text(text.as_ref().to_string().into()))
+    }
+}
+
+/// Resolver of the built-in emoji table, see [`emoji`].
+#[cfg(feature = "emoji")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Emoji;
+
+#[cfg(feature = "emoji")]
+impl<'s> SymbolResolver<'s> for Emoji {
+    fn resolve(&self, name: &str) -> Option<Replacement<'s>> {
+        emoji(name).map(Replacement::from)
+    }
+}
+
+/// Look up an emoji by its name in the built-in table.
+///
+/// The table contains the most common emoji, with the names used by
+/// [gemoji](https://github.com/github/gemoji). Emoji that are displayed as text by default
+/// include a variation selector to be displayed as emoji.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(jotdown::symbol::emoji("smile"), Some("😄"));
+/// assert_eq!(jotdown::symbol::emoji("nonexistent"), None);
+/// ```
+#[cfg(feature = "emoji")]
+#[must_use]
+pub fn emoji(name: &str) -> Option<&'static str> {
+    EMOJI
+        .binary_search_by_key(&name, |(n, _)| n)
+        .ok()
+        .map(|i| EMOJI[i].1)
+}
+
+/// Replace the symbols of an iterator of events using a resolver.
+///
+/// Symbols that are not resolved are kept as [`Event::Symbol`].
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use std::collections::HashMap;
+/// let mut symbols = HashMap::new();
+/// symbols.insert("tm", "™");
+/// let events: Vec<_> = symbol::resolve(Parser::new("jotdown:tm: :x:"), symbols).collect();
+/// assert_eq!(
+///     events,
+///     [
+///         Event::Start(Container::Paragraph, Attributes::new()),
+///         Event::Str("jotdown".into()),
+///         Event::Str("™".into()),
+///         Event::Str(" ".into()),
+///         Event::Symbol("x".into()),
+///         Event::End(Container::Paragraph),
+///     ],
+/// );
+/// ```
+pub fn resolve<'s, I, R>(events: I, resolver: R) -> Symbols<'s, I::IntoIter, R>
+where
+    I: IntoIterator<Item = Event<'s>>,
+    R: SymbolResolver<'s>,
+{
+    Symbols {
+        events: events.into_iter(),
+        resolver,
+        replacement: Vec::new().into_iter(),
+    }
+}
+
+/// An iterator of events with the symbols replaced, see [`resolve`].
+#[derive(Clone)]
+pub struct Symbols<'s, I, R> {
+    events: I,
+    resolver: R,
+    /// Remaining events of the current replacement.
+    replacement: std::vec::IntoIter<Event<'s>>,
+}
+
+impl<'s, I, R> Iterator for Symbols<'s, I, R>
+where
+    I: Iterator<Item = Event<'s>>,
+    R: SymbolResolver<'s>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(e) = self.replacement.next() {
+                return Some(e);
+            }
+            match self.events.next()? {
+                Event::Symbol(sym) => match self.resolver.resolve(&sym) {
+                    Some(Replacement::Text(text)) => return Some(Event::Str(text)),
+                    Some(Replacement::Events(events)) => self.replacement = events.into_iter(),
+                    None => return Some(Event::Symbol(sym)),
+                },
+                e => return Some(e),
+            }
+        }
+    }
+}
+
+/// Emoji and their names, sorted by name.
+#[cfg(feature = "emoji")]
+const EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("1st_place_medal", "🥇"),
+    ("2nd_place_medal", "🥈"),
+    ("3rd_place_medal", "🥉"),
+    ("airplane", "✈\u{fe0f}"),
+    ("alarm_clock", "⏰"),
+    ("alien", "👽"),
+    ("anger", "💢"),
+    ("angry", "😠"),
+    ("anguished", "😧"),
+    ("ant", "🐜"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇\u{fe0f}"),
+    ("arrow_left", "⬅\u{fe0f}"),
+    ("arrow_right", "➡\u{fe0f}"),
+    ("arrow_up", "⬆\u{fe0f}"),
+    ("arrows_counterclockwise", "🔄"),
+    ("art", "🎨"),
+    ("astonished", "😲"),
+    ("avocado", "🥑"),
+    ("baby", "👶"),
+    ("baby_chick", "🐤"),
+    ("bacon", "🥓"),
+    ("balloon", "🎈"),
+    ("ballot_box_with_check", "☑\u{fe0f}"),
+    ("banana", "🍌"),
+    ("bangbang", "‼\u{fe0f}"),
+    ("bar_chart", "📊"),
+    ("baseball", "⚾"),
+    ("basketball", "🏀"),
+    ("bat", "🦇"),
+    ("battery", "🔋"),
+    ("bear", "🐻"),
+    ("bee", "🐝"),
+    ("beer", "🍺"),
+    ("beers", "🍻"),
+    ("beetle", "🐞"),
+    ("beginner", "🔰"),
+    ("bell", "🔔"),
+    ("bike", "🚲"),
+    ("bird", "🐦"),
+    ("birthday", "🎂"),
+    ("black_circle", "⚫"),
+    ("black_flag", "🏴"),
+    ("black_heart", "🖤"),
+    ("blossom", "🌼"),
+    ("blue_circle", "🔵"),
+    ("blue_heart", "💙"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bouquet", "💐"),
+    ("boy", "👦"),
+    ("brain", "🧠"),
+    ("bread", "🍞"),
+    ("broken_heart", "💔"),
+    ("brown_heart", "🤎"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("burrito", "🌯"),
+    ("bus", "🚌"),
+    ("butterfly", "🦋"),
+    ("cactus", "🌵"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("call_me_hand", "🤙"),
+    ("camera", "📷"),
+    ("candy", "🍬"),
+    ("car", "🚗"),
+    ("carrot", "🥕"),
+    ("cat", "🐱"),
+    ("cd", "💿"),
+    ("champagne", "🍾"),
+    ("chart_with_downwards_trend", "📉"),
+    ("chart_with_upwards_trend", "📈"),
+    ("checkered_flag", "🏁"),
+    ("cheese", "🧀"),
+    ("cherries", "🍒"),
+    ("cherry_blossom", "🌸"),
+    ("chicken", "🐔"),
+    ("chocolate_bar", "🍫"),
+    ("christmas_tree", "🎄"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("cloud", "☁\u{fe0f}"),
+    ("clown_face", "🤡"),
+    ("cocktail", "🍸"),
+    ("coconut", "🥥"),
+    ("coffee", "☕"),
+    ("cold_face", "🥶"),
+    ("cold_sweat", "😰"),
+    ("collision", "💥"),
+    ("computer", "💻"),
+    ("confetti_ball", "🎊"),
+    ("confounded", "😖"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cookie", "🍪"),
+    ("cool", "🆒"),
+    ("copyright", "©\u{fe0f}"),
+    ("corn", "🌽"),
+    ("cow", "🐮"),
+    ("cowboy_hat_face", "🤠"),
+    ("crab", "🦀"),
+    ("credit_card", "💳"),
+    ("crescent_moon", "🌙"),
+    ("crossed_fingers", "🤞"),
+    ("cry", "😢"),
+    ("crying_cat_face", "😿"),
+    ("cupid", "💘"),
+    ("cursing_face", "🤬"),
+    ("dart", "🎯"),
+    ("dash", "💨"),
+    ("date", "📅"),
+    ("deciduous_tree", "🌳"),
+    ("desktop_computer", "🖥\u{fe0f}"),
+    ("disappointed", "😞"),
+    ("disappointed_relieved", "😥"),
+    ("dizzy", "💫"),
+    ("dizzy_face", "😵"),
+    ("dog", "🐶"),
+    ("dollar", "💵"),
+    ("dolphin", "🐬"),
+    ("doughnut", "🍩"),
+    ("dragon", "🐉"),
+    ("drooling_face", "🤤"),
+    ("droplet", "💧"),
+    ("duck", "🦆"),
+    ("e-mail", "📧"),
+    ("eagle", "🦅"),
+    ("earth_africa", "🌍"),
+    ("earth_americas", "🌎"),
+    ("earth_asia", "🌏"),
+    ("egg", "🥚"),
+    ("eggplant", "🍆"),
+    ("electric_plug", "🔌"),
+    ("email", "📧"),
+    ("envelope", "✉\u{fe0f}"),
+    ("evergreen_tree", "🌲"),
+    ("exclamation", "❗"),
+    ("exploding_head", "🤯"),
+    ("expressionless", "😑"),
+    ("eye", "👁\u{fe0f}"),
+    ("eyes", "👀"),
+    ("face_with_head_bandage", "🤕"),
+    ("face_with_thermometer", "🤒"),
+    ("facepunch", "👊"),
+    ("fallen_leaf", "🍂"),
+    ("fearful", "😨"),
+    ("file_folder", "📁"),
+    ("fire", "🔥"),
+    ("fish", "🐟"),
+    ("fist", "✊"),
+    ("fist_left", "🤛"),
+    ("fist_oncoming", "👊"),
+    ("fist_raised", "✊"),
+    ("fist_right", "🤜"),
+    ("flashlight", "🔦"),
+    ("flipper", "🐬"),
+    ("floppy_disk", "💾"),
+    ("flushed", "😳"),
+    ("football", "🏈"),
+    ("four_leaf_clover", "🍀"),
+    ("fox_face", "🦊"),
+    ("free", "🆓"),
+    ("fries", "🍟"),
+    ("frog", "🐸"),
+    ("frowning", "😦"),
+    ("frowning_face", "☹\u{fe0f}"),
+    ("fu", "🖕"),
+    ("full_moon", "🌕"),
+    ("game_die", "🎲"),
+    ("gear", "⚙\u{fe0f}"),
+    ("gem", "💎"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("gift_heart", "💝"),
+    ("girl", "👧"),
+    ("grapes", "🍇"),
+    ("green_apple", "🍏"),
+    ("green_heart", "💚"),
+    ("grey_exclamation", "❕"),
+    ("grey_question", "❔"),
+    ("grimacing", "😬"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("guitar", "🎸"),
+    ("hamburger", "🍔"),
+    ("hammer", "🔨"),
+    ("hamster", "🐹"),
+    ("hand", "✋"),
+    ("hand_over_mouth", "🤭"),
+    ("handshake", "🤝"),
+    ("hankey", "💩"),
+    ("headphones", "🎧"),
+    ("hear_no_evil", "🙉"),
+    ("heart", "❤\u{fe0f}"),
+    ("heart_decoration", "💟"),
+    ("heart_eyes", "😍"),
+    ("heart_eyes_cat", "😻"),
+    ("heartbeat", "💓"),
+    ("heartpulse", "💗"),
+    ("heavy_check_mark", "✔\u{fe0f}"),
+    ("heavy_division_sign", "➗"),
+    ("heavy_exclamation_mark", "❗"),
+    ("heavy_heart_exclamation", "❣\u{fe0f}"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_multiplication_x", "✖\u{fe0f}"),
+    ("heavy_plus_sign", "➕"),
+    ("herb", "🌿"),
+    ("hibiscus", "🌺"),
+    ("hole", "🕳\u{fe0f}"),
+    ("honeybee", "🐝"),
+    ("horse", "🐴"),
+    ("hospital", "🏥"),
+    ("hot_face", "🥵"),
+    ("hot_pepper", "🌶\u{fe0f}"),
+    ("hotdog", "🌭"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("hugs", "🤗"),
+    ("hushed", "😯"),
+    ("icecream", "🍦"),
+    ("imp", "👿"),
+    ("inbox_tray", "📥"),
+    ("information_source", "ℹ\u{fe0f}"),
+    ("innocent", "😇"),
+    ("interrobang", "⁉\u{fe0f}"),
+    ("iphone", "📱"),
+    ("jack_o_lantern", "🎃"),
+    ("japanese_goblin", "👺"),
+    ("japanese_ogre", "👹"),
+    ("joy", "😂"),
+    ("joy_cat", "😹"),
+    ("key", "🔑"),
+    ("keyboard", "⌨\u{fe0f}"),
+    ("kiss", "💋"),
+    ("kissing", "😗"),
+    ("kissing_cat", "😽"),
+    ("kissing_closed_eyes", "😚"),
+    ("kissing_heart", "😘"),
+    ("kissing_smiling_eyes", "😙"),
+    ("kiwi_fruit", "🥝"),
+    ("koala", "🐨"),
+    ("lady_beetle", "🐞"),
+    ("large_blue_circle", "🔵"),
+    ("laughing", "😆"),
+    ("lemon", "🍋"),
+    ("link", "🔗"),
+    ("lion", "🦁"),
+    ("lips", "👄"),
+    ("lock", "🔒"),
+    ("lollipop", "🍭"),
+    ("loudspeaker", "📢"),
+    ("love_letter", "💌"),
+    ("love_you_gesture", "🤟"),
+    ("lying_face", "🤥"),
+    ("mag", "🔍"),
+    ("mag_right", "🔎"),
+    ("mailbox", "📫"),
+    ("man", "👨"),
+    ("mandarin", "🍊"),
+    ("maple_leaf", "🍁"),
+    ("mask", "😷"),
+    ("medal_sports", "🏅"),
+    ("mega", "📣"),
+    ("memo", "📝"),
+    ("metal", "🤘"),
+    ("microphone", "🎤"),
+    ("middle_finger", "🖕"),
+    ("milk_glass", "🥛"),
+    ("money_mouth_face", "🤑"),
+    ("moneybag", "💰"),
+    ("monkey_face", "🐵"),
+    ("monocle_face", "🧐"),
+    ("mouse", "🐭"),
+    ("muscle", "💪"),
+    ("mushroom", "🍄"),
+    ("musical_note", "🎵"),
+    ("nail_care", "💅"),
+    ("nauseated_face", "🤢"),
+    ("nerd_face", "🤓"),
+    ("neutral_face", "😐"),
+    ("new", "🆕"),
+    ("new_moon", "🌑"),
+    ("no_bell", "🔕"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("no_mouth", "😶"),
+    ("notebook", "📓"),
+    ("notes", "🎶"),
+    ("ocean", "🌊"),
+    ("octopus", "🐙"),
+    ("office", "🏢"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("older_man", "👴"),
+    ("older_woman", "👵"),
+    ("open_book", "📖"),
+    ("open_file_folder", "📂"),
+    ("open_hands", "👐"),
+    ("open_mouth", "😮"),
+    ("orange", "🍊"),
+    ("orange_heart", "🧡"),
+    ("outbox_tray", "📤"),
+    ("owl", "🦉"),
+    ("package", "📦"),
+    ("palm_tree", "🌴"),
+    ("palms_up_together", "🤲"),
+    ("panda_face", "🐼"),
+    ("paperclip", "📎"),
+    ("partying_face", "🥳"),
+    ("peach", "🍑"),
+    ("pear", "🍐"),
+    ("pen", "🖊\u{fe0f}"),
+    ("pencil", "📝"),
+    ("pencil2", "✏\u{fe0f}"),
+    ("penguin", "🐧"),
+    ("pensive", "😔"),
+    ("persevere", "😣"),
+    ("phone", "☎\u{fe0f}"),
+    ("pig", "🐷"),
+    ("pinching_hand", "🤏"),
+    ("pineapple", "🍍"),
+    ("pizza", "🍕"),
+    ("pleading_face", "🥺"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝\u{fe0f}"),
+    ("point_up_2", "👆"),
+    ("poop", "💩"),
+    ("popcorn", "🍿"),
+    ("potato", "🥔"),
+    ("pout", "😡"),
+    ("pouting_cat", "😾"),
+    ("pray", "🙏"),
+    ("printer", "🖨\u{fe0f}"),
+    ("punch", "👊"),
+    ("purple_heart", "💜"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rabbit", "🐰"),
+    ("rage", "😡"),
+    ("rainbow", "🌈"),
+    ("raised_back_of_hand", "🤚"),
+    ("raised_eyebrow", "🤨"),
+    ("raised_hand", "✋"),
+    ("raised_hand_with_fingers_splayed", "🖐\u{fe0f}"),
+    ("raised_hands", "🙌"),
+    ("ramen", "🍜"),
+    ("recycle", "♻\u{fe0f}"),
+    ("red_car", "🚗"),
+    ("red_circle", "🔴"),
+    ("registered", "®\u{fe0f}"),
+    ("relaxed", "☺\u{fe0f}"),
+    ("relieved", "😌"),
+    ("revolving_hearts", "💞"),
+    ("ribbon", "🎀"),
+    ("rice", "🍚"),
+    ("robot", "🤖"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("roll_eyes", "🙄"),
+    ("rose", "🌹"),
+    ("rotating_light", "🚨"),
+    ("satisfied", "😆"),
+    ("school", "🏫"),
+    ("scissors", "✂\u{fe0f}"),
+    ("scream", "😱"),
+    ("scream_cat", "🙀"),
+    ("see_no_evil", "🙈"),
+    ("seedling", "🌱"),
+    ("selfie", "🤳"),
+    ("shamrock", "☘\u{fe0f}"),
+    ("shark", "🦈"),
+    ("ship", "🚢"),
+    ("shit", "💩"),
+    ("shushing_face", "🤫"),
+    ("skull", "💀"),
+    ("skull_and_crossbones", "☠\u{fe0f}"),
+    ("sleeping", "😴"),
+    ("sleepy", "😪"),
+    ("slightly_frowning_face", "🙁"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smile_cat", "😸"),
+    ("smiley", "😃"),
+    ("smiley_cat", "😺"),
+    ("smiling_face_with_three_hearts", "🥰"),
+    ("smiling_imp", "😈"),
+    ("smirk", "😏"),
+    ("smirk_cat", "😼"),
+    ("snail", "🐌"),
+    ("snake", "🐍"),
+    ("sneezing_face", "🤧"),
+    ("snowflake", "❄\u{fe0f}"),
+    ("snowman", "⛄"),
+    ("snowman_with_snow", "☃\u{fe0f}"),
+    ("sob", "😭"),
+    ("soccer", "⚽"),
+    ("sos", "🆘"),
+    ("space_invader", "👾"),
+    ("spaghetti", "🍝"),
+    ("sparkles", "✨"),
+    ("sparkling_heart", "💖"),
+    ("speak_no_evil", "🙊"),
+    ("speech_balloon", "💬"),
+    ("spider", "🕷\u{fe0f}"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("star_struck", "🤩"),
+    ("stopwatch", "⏱\u{fe0f}"),
+    ("strawberry", "🍓"),
+    ("stuck_out_tongue", "😛"),
+    ("stuck_out_tongue_closed_eyes", "😝"),
+    ("stuck_out_tongue_winking_eye", "😜"),
+    ("sunflower", "🌻"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀\u{fe0f}"),
+    ("sushi", "🍣"),
+    ("sweat", "😓"),
+    ("sweat_drops", "💦"),
+    ("sweat_smile", "😅"),
+    ("taco", "🌮"),
+    ("tada", "🎉"),
+    ("tangerine", "🍊"),
+    ("taxi", "🚕"),
+    ("tea", "🍵"),
+    ("telephone", "☎\u{fe0f}"),
+    ("tennis", "🎾"),
+    ("tent", "⛺"),
+    ("thinking", "🤔"),
+    ("thought_balloon", "💭"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("tiger", "🐯"),
+    ("tired_face", "😫"),
+    ("tm", "™\u{fe0f}"),
+    ("tomato", "🍅"),
+    ("tongue", "👅"),
+    ("triangular_flag_on_post", "🚩"),
+    ("trident", "🔱"),
+    ("triumph", "😤"),
+    ("trophy", "🏆"),
+    ("tropical_drink", "🍹"),
+    ("tropical_fish", "🐠"),
+    ("tulip", "🌷"),
+    ("turtle", "🐢"),
+    ("tv", "📺"),
+    ("two_hearts", "💕"),
+    ("umbrella", "☔"),
+    ("unamused", "😒"),
+    ("unicorn", "🦄"),
+    ("unlock", "🔓"),
+    ("up", "🆙"),
+    ("upside_down_face", "🙃"),
+    ("v", "✌\u{fe0f}"),
+    ("video_game", "🎮"),
+    ("vomiting_face", "🤮"),
+    ("vulcan_salute", "🖖"),
+    ("warning", "⚠\u{fe0f}"),
+    ("wastebasket", "🗑\u{fe0f}"),
+    ("watch", "⌚"),
+    ("watermelon", "🍉"),
+    ("wave", "👋"),
+    ("weary", "😩"),
+    ("whale", "🐳"),
+    ("white_check_mark", "✅"),
+    ("white_circle", "⚪"),
+    ("white_flag", "🏳\u{fe0f}"),
+    ("white_heart", "🤍"),
+    ("wine_glass", "🍷"),
+    ("wink", "😉"),
+    ("wolf", "🐺"),
+    ("woman", "👩"),
+    ("woozy_face", "🥴"),
+    ("worried", "😟"),
+    ("wrench", "🔧"),
+    ("writing_hand", "✍\u{fe0f}"),
+    ("x", "❌"),
+    ("yawning_face", "🥱"),
+    ("yellow_heart", "💛"),
+    ("yum", "😋"),
+    ("zany_face", "🤪"),
+    ("zap", "⚡"),
+    ("zipper_mouth_face", "🤐"),
+    ("zzz", "💤"),
+];
+
+#[cfg(test)]
+mod test {
+    use super::Replacement;
+    use crate::Attributes;
+    use crate::Container::*;
+    use crate::Event::*;
+    use crate::SpanLinkType;
+
+    #[test]
+    fn replace_events() {
+        let image = Image("a.png".into(), SpanLinkType::Inline);
+        let resolver = |name: &str| match name {
+            "a" => Some(Replacement::Events(vec![
+                Start(image.clone(), Attributes::new()),
+                End(image.clone()),
+            ])),
+            "b" => Some(Replacement::Events(vec![])),
+            _ => None,
+        };
+        let events = super::resolve(crate::Parser::new(":a::b::c:"), resolver).collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            &[
+                Start(Paragraph, Attributes::new()),
+                Start(image.clone(), Attributes::new()),
+                End(image.clone()),
+                Symbol("c".into()),
+                End(Paragraph),
+            ]
+        );
+    }
+
+    #[cfg(feature = "emoji")]
+    #[test]
+    fn emoji_sorted() {
+        assert!(super::EMOJI.windows(2).all(|w| w[0].0 < w[1].0));
+        for (name, e) in super::EMOJI {
+            assert_eq!(super::emoji(name), Some(*e));
+        }
+    }
+}
@@ -0,0 +1,744 @@
+//! A plain text renderer that takes an iterator of [`Event`]s and emits the text without any
+//! markup.
+//!
+//! The output is intended for e.g. search indexing or previews. Blocks are separated by blank
+//! lines, list items are prefixed with their markers and table cells are separated by tabs. Raw
+//! content, link definitions and attributes are omitted.
+
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+use crate::ListKind;
+use crate::Map;
+use crate::Render;
+use crate::RenderRef;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("- \"hello\" _world_...");
+/// assert_eq!(
+///     jotdown::text::render_to_string(events),
+///     "- “hello” world…\n",
+/// );
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer::default().push(events, &mut s).unwrap();
+    s
+}
+
+/// Concatenate the text of inline events, with all formatting stripped.
+///
+/// Only the content of [`Event::Str`] is kept, and soft breaks are replaced by spaces. This is the
+/// same text that is used to generate heading ids and to match implicit heading references.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// let events = Parser::new("# A _heading_ with `code`\n").filter(|e| {
+///     !matches!(
+///         e,
+///         Event::Start(Container::Section { .. }, ..) | Event::End(Container::Section { .. })
+///     )
+/// });
+/// assert_eq!(text::strip_formatting(events), "A heading with code");
+/// ```
+pub fn strip_formatting<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    events.for_each(|e| crate::push_stripped(&mut s, &e));
+    s
+}
+
+/// Placement of footnotes in the plain text output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Footnotes {
+    /// Mark references with their number and append the footnotes to the end of the output, in
+    /// the order they were first referenced.
+    ///
+    /// This is the default behavior.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::text::*;
+    /// let src = "a[^b]\n\n[^b]: c\n";
+    /// let mut actual = String::new();
+    /// let renderer = Renderer::with_footnotes(Footnotes::Append);
+    /// renderer.push(Parser::new(src), &mut actual).unwrap();
+    /// assert_eq!(actual, "a[1]\n\n[1] c\n");
+    /// ```
+    Append,
+    /// Omit both footnote references and footnotes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::text::*;
+    /// let src = "a[^b]\n\n[^b]: c\n";
+    /// let mut actual = String::new();
+    /// let renderer = Renderer::with_footnotes(Footnotes::Omit);
+    /// renderer.push(Parser::new(src), &mut actual).unwrap();
+    /// assert_eq!(actual, "a\n");
+    /// ```
+    Omit,
+}
+
+impl Default for Footnotes {
+    fn default() -> Self {
+        Self::Append
+    }
+}
+
+/// [`Render`] implementor that writes plain text output.
+#[derive(Clone, Default)]
+pub struct Renderer {
+    footnotes: Footnotes,
+}
+
+impl Renderer {
+    /// Create a renderer with the given placement of footnotes.
+    #[must_use]
+    pub fn with_footnotes(footnotes: Footnotes) -> Self {
+        Self { footnotes }
+    }
+}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::new(self.footnotes);
+        events.try_for_each(|e| w.render_event(&e, &mut out))?;
+        w.render_epilogue(&mut out)
+    }
+}
+
+impl RenderRef for Renderer {
+    fn push_ref<'s, E, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        E: AsRef<Event<'s>>,
+        I: Iterator<Item = E>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::new(self.footnotes);
+        events.try_for_each(|e| w.render_event(e.as_ref(), &mut out))?;
+        w.render_epilogue(&mut out)
+    }
+}
+
+/// Prefix of each line within a container, e.g. the marker and indentation of a list item.
+struct Prefix {
+    /// Prefix of the first line, if not yet written.
+    first: Option<String>,
+    /// Prefix of subsequent lines.
+    rest: String,
+}
+
+/// Blocks within a container.
+struct Level {
+    /// Whether any block has been written.
+    blocks: bool,
+    /// Whether blocks are written without blank lines in between, e.g. in a tight list.
+    tight: bool,
+}
+
+struct Writer<'s> {
+    footnotes: Footnotes,
+    prefixes: Vec<Prefix>,
+    levels: Vec<Level>,
+    /// Kind and next number of each open list.
+    lists: Vec<(ListKind, u64)>,
+    /// Whether the next character begins a new line.
+    line_start: bool,
+    /// Depth of elements whose content is omitted.
+    ignore: usize,
+    /// Number of cells written in the current table row.
+    cells: usize,
+    /// Stack of current open footnotes, with label and staging buffer.
+    open_footnotes: Vec<(CowStr<'s>, Vec<Event<'s>>)>,
+    /// Footnote references in the order they were first encountered.
+    references: Vec<CowStr<'s>>,
+    /// Events for each footnote.
+    footnote_events: Map<CowStr<'s>, Vec<Event<'s>>>,
+}
+
+impl<'s> Writer<'s> {
+    fn new(footnotes: Footnotes) -> Self {
+        Self {
+            footnotes,
+            prefixes: Vec::new(),
+            levels: vec![Level {
+                blocks: false,
+                tight: false,
+            }],
+            lists: Vec::new(),
+            line_start: true,
+            ignore: 0,
+            cells: 0,
+            open_footnotes: Vec::new(),
+            references: Vec::new(),
+            footnote_events: Map::new(),
+        }
+    }
+
+    /// Write text, adding prefixes to the beginning of each non-empty line.
+    fn write<W>(&mut self, s: &str, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                out.write_char('\n')?;
+                self.line_start = true;
+            }
+            if !line.is_empty() {
+                if self.line_start {
+                    self.write_prefixes(false, out)?;
+                    self.line_start = false;
+                }
+                out.write_str(line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_prefixes<W>(&mut self, trim: bool, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let mut s = String::new();
+        for p in &mut self.prefixes {
+            if let Some(first) = p.first.take() {
+                s.push_str(&first);
+            } else {
+                s.push_str(&p.rest);
+            }
+        }
+        out.write_str(if trim { s.trim_end() } else { &s })
+    }
+
+    /// End the current line, if anything has been written to it.
+    fn line_end<W>(&mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if !self.line_start {
+            self.write("\n", out)?;
+        }
+        Ok(())
+    }
+
+    /// Separate a new block from a preceding sibling block.
+    fn block<W>(&mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        self.line_end(out)?;
+        let level = self.levels.last_mut().unwrap();
+        let separate = level.blocks && !level.tight;
+        level.blocks = true;
+        if separate {
+            self.write("\n", out)?;
+        }
+        Ok(())
+    }
+
+    /// Enter a container whose lines are prefixed.
+    fn push_prefix(&mut self, first: String, tight: bool) {
+        let rest = " ".repeat(first.chars().count());
+        self.prefixes.push(Prefix {
+            first: Some(first),
+            rest,
+        });
+        self.levels.push(Level {
+            blocks: false,
+            tight,
+        });
+    }
+
+    fn pop_prefix<W>(&mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        self.line_end(out)?;
+        self.levels.pop();
+        if self.prefixes.last().map_or(false, |p| p.first.is_some()) {
+            // empty container, write its marker anyway
+            self.write_prefixes(true, out)?;
+            out.write_char('\n')?;
+        }
+        self.prefixes.pop();
+        Ok(())
+    }
+
+    fn render_event<W>(&mut self, e: &Event<'s>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if let Event::Start(Container::Footnote { label }, ..) = e {
+            self.open_footnotes.push((label.clone(), Vec::new()));
+            return Ok(());
+        } else if let Some((_, events)) = self.open_footnotes.last_mut() {
+            if matches!(e, Event::End(Container::Footnote { .. })) {
+                let (label, events) = self.open_footnotes.pop().unwrap();
+                self.footnote_events.insert(label, events);
+            } else {
+                events.push(e.clone());
+            }
+            return Ok(());
+        }
+
+        if self.ignore > 0 {
+            match e {
+                Event::Start(..) => self.ignore += 1,
+                Event::End(..) => self.ignore -= 1,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match e {
+            Event::Start(c, ..) => self.enter(c, out)?,
+            Event::End(c) => self.exit(c, out)?,
+            Event::Str(s) => self.write(s, out)?,
+            Event::FootnoteReference(label) => {
+                if self.footnotes == Footnotes::Append {
+                    let number = self.reference(label.clone());
+                    self.write(&format!("[{}]", number), out)?;
+                }
+            }
+            Event::Symbol(sym) => self.write(&format!(":{}:", sym), out)?,
+            Event::LeftSingleQuote => self.write("‘", out)?,
+            Event::RightSingleQuote => self.write("’", out)?,
+            Event::LeftDoubleQuote => self.write("“", out)?,
+            Event::RightDoubleQuote => self.write("”", out)?,
+            Event::Ellipsis => self.write("…", out)?,
+            Event::EnDash => self.write("–", out)?,
+            Event::EmDash => self.write("—", out)?,
+            Event::NonBreakingSpace => self.write("\u{a0}", out)?,
+            Event::Hardbreak | Event::Softbreak => self.write("\n", out)?,
+            Event::Escape | Event::Blankline | Event::Attributes(..) => {}
+            Event::ThematicBreak(..) => {}
+        }
+        Ok(())
+    }
+
+    fn enter<W>(&mut self, c: &Container<'s>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::List { kind, tight } => {
+                self.block(out)?;
+                let start = if let ListKind::Ordered { start, .. } = kind {
+                    *start
+                } else {
+                    1
+                };
+                self.lists.push((*kind, start));
+                self.levels.push(Level {
+                    blocks: false,
+                    tight: *tight,
+                });
+            }
+            Container::ListItem | Container::TaskListItem { .. } => {
+                self.block(out)?;
+                let tight = self.levels.last().map_or(false, |l| l.tight);
+                let marker = if let Some((kind, n)) = self.lists.last_mut() {
+                    let marker = list_marker(*kind, *n, c);
+                    *n += 1;
+                    marker
+                } else {
+                    String::new()
+                };
+                self.push_prefix(format!("{} ", marker), tight);
+            }
+            Container::DescriptionList => {
+                self.block(out)?;
+                self.levels.push(Level {
+                    blocks: false,
+                    tight: false,
+                });
+            }
+            Container::DescriptionDetails => {
+                self.line_end(out)?;
+                self.push_prefix("  ".to_string(), false);
+            }
+            Container::Table => {
+                self.block(out)?;
+                self.levels.push(Level {
+                    blocks: false,
+                    tight: true,
+                });
+            }
+            Container::TableRow { .. } => self.cells = 0,
+            Container::TableCell { .. } => {
+                if self.cells > 0 {
+                    self.write("\t", out)?;
+                }
+                self.cells += 1;
+            }
+            Container::Paragraph
+            | Container::Heading { .. }
+            | Container::DescriptionTerm
+            | Container::Caption
+            | Container::CodeBlock { .. } => self.block(out)?,
+            Container::RawBlock { .. }
+            | Container::RawInline { .. }
+            | Container::LinkDefinition { .. } => self.ignore += 1,
+            Container::Blockquote
+            | Container::Footnote { .. }
+            | Container::Section { .. }
+            | Container::Div { .. }
+            | Container::Span
+            | Container::Link(..)
+            | Container::Image(..)
+            | Container::Verbatim
+            | Container::Math { .. }
+            | Container::Subscript
+            | Container::Superscript
+            | Container::Insert
+            | Container::Delete
+            | Container::Strong
+            | Container::Emphasis
+            | Container::Mark => {}
+        }
+        Ok(())
+    }
+
+    fn exit<W>(&mut self, c: &Container<'s>, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::List { .. } => {
+                self.lists.pop();
+                self.levels.pop();
+            }
+            Container::ListItem
+            | Container::TaskListItem { .. }
+            | Container::DescriptionDetails => self.pop_prefix(out)?,
+            Container::DescriptionList | Container::Table => {
+                self.line_end(out)?;
+                self.levels.pop();
+            }
+            Container::TableRow { .. }
+            | Container::Paragraph
+            | Container::Heading { .. }
+            | Container::DescriptionTerm
+            | Container::Caption
+            | Container::CodeBlock { .. } => self.line_end(out)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Add a footnote reference and obtain its number.
+    fn reference(&mut self, label: CowStr<'s>) -> usize {
+        self.references
+            .iter()
+            .position(|t| *t == label)
+            .map_or_else(
+                || {
+                    self.references.push(label);
+                    self.references.len()
+                },
+                |i| i + 1,
+            )
+    }
+
+    fn render_epilogue<W>(&mut self, out: &mut W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        // footnotes may reference other footnotes, adding more references while rendering
+        let mut number = 0;
+        while let Some(label) = self.references.get(number).cloned() {
+            number += 1;
+            if let Some(events) = self.footnote_events.remove(&label) {
+                self.block(out)?;
+                self.push_prefix(format!("[{}] ", number), false);
+                events.iter().try_for_each(|e| self.render_event(e, out))?;
+                self.pop_prefix(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn list_marker(kind: ListKind, n: u64, c: &Container) -> String {
+    match kind {
+        ListKind::Unordered(b) => char::from(b).to_string(),
+        ListKind::Task(b) => {
+            let checked = matches!(c, Container::TaskListItem { checked: true });
+            format!("{} [{}]", char::from(b), if checked { 'x' } else { ' ' })
+        }
+        ListKind::Ordered {
+            numbering, style, ..
+        } => style.marker(&numbering.format_number(n)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Footnotes;
+    use crate::Render;
+
+    macro_rules! test_text {
+        ($src:expr, $expected:expr $(,$footnotes:expr)? $(,)?) => {
+            #[allow(unused)]
+            let mut footnotes = Footnotes::default();
+            $(footnotes = $footnotes;)?
+            let mut actual = String::new();
+            super::Renderer::with_footnotes(footnotes)
+                .push(crate::Parser::new($src), &mut actual)
+                .unwrap();
+            assert_eq!(actual, $expected);
+        };
+    }
+
+    #[test]
+    fn para() {
+        test_text!(
+            concat!(
+                "# *a* b\n", //
+                "c\n",
+                "\n",
+                "d\\\n",
+                "e\n",
+                "\n",
+                "> f\n",
+                "\n",
+                "***\n",
+                "\n",
+                "g\n",
+            ),
+            concat!(
+                "a b\n", //
+                "c\n", "\n",  //
+                "d\n", //
+                "e\n", //
+                "\n",  //
+                "f\n", //
+                "\n",  //
+                "g\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn smart_punctuation() {
+        test_text!("\"a\" 'b' c--d---e... f\\ g", "“a” ‘b’ c–d—e… f\u{a0}g\n",);
+    }
+
+    #[test]
+    fn list() {
+        test_text!(
+            concat!(
+                "- a\n", //
+                "- b\n",
+                "\n",
+                "  c\n",
+                "\n",
+                "  iv. d\n",
+                "  v. e\n",
+                "- f\n",
+            ),
+            concat!(
+                "- a\n", //
+                "\n",
+                "- b\n",
+                "\n",
+                "  c\n",
+                "\n",
+                "  iv. d\n",
+                "  v. e\n",
+                "\n",
+                "- f\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_ordered() {
+        test_text!(
+            concat!(
+                "(b) a\n", //
+                "(c) b\n", //
+                "\n",      //
+                "9) c\n",  //
+                "10) d\n",
+            ),
+            concat!(
+                "(b) a\n", //
+                "(c) b\n", //
+                "\n",      //
+                "9) c\n",  //
+                "10) d\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn list_task() {
+        test_text!(
+            concat!(
+                "- [ ] a\n", //
+                "- [x] b\n",
+                "- [ ]\n",
+            ),
+            concat!(
+                "- [ ] a\n", //
+                "- [x] b\n",
+                "- [ ]\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn description_list() {
+        test_text!(
+            concat!(
+                ": a\n", //
+                "\n",    //
+                "  b\n", //
+                "\n",    //
+                "  c\n", //
+                "\n",    //
+                ": d\n", //
+                "\n",    //
+                "  e\n",
+            ),
+            concat!(
+                "a\n",   //
+                "  b\n", //
+                "\n",    //
+                "  c\n", //
+                "\n",    //
+                "d\n",   //
+                "  e\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn table() {
+        test_text!(
+            concat!(
+                "|a|b|\n", //
+                "|-|-|\n", //
+                "|c|d|\n", //
+                "^ e\n",
+            ),
+            concat!(
+                "e\n", //
+                "a\tb\n", "c\td\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn code_raw() {
+        test_text!(
+            concat!(
+                "```\n", //
+                "a\n",
+                "\n",
+                "b\n",
+                "```\n",
+                "\n",
+                "``` =html\n",
+                "<br>\n",
+                "```\n",
+                "\n",
+                "`c` $`d` `<e>`{=html} [f][]\n",
+                "\n",
+                "[f]: g\n",
+            ),
+            concat!(
+                "a\n", //
+                "\n",  //
+                "b\n", //
+                "\n",  //
+                "c d  f\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn footnote() {
+        test_text!(
+            concat!(
+                "a[^b] c[^d] e[^b]\n", //
+                "\n",
+                "[^d]: f[^g]\n",
+                "\n",
+                "    h\n",
+                "\n",
+                "[^b]: - i\n",
+                "[^g]: j\n",
+            ),
+            concat!(
+                "a[1] c[2] e[1]\n", //
+                "\n",
+                "[1] - i\n",
+                "\n",
+                "[2] f[3]\n",
+                "\n",
+                "    h\n",
+                "\n",
+                "[3] j\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn footnote_omit() {
+        test_text!(
+            concat!(
+                "a[^b]\n", //
+                "\n",
+                "[^b]: c\n",
+                "\n",
+                "d\n",
+            ),
+            concat!(
+                "a\n", //
+                "\n",  //
+                "d\n",
+            ),
+            Footnotes::Omit,
+        );
+    }
+
+    #[test]
+    fn strip_formatting_heading_reference() {
+        let src = "# *a* `b`\n\n[a b][]\n";
+        let heading: Vec<_> = crate::Parser::new(src)
+            .skip_while(|e| !matches!(e, crate::Event::Start(crate::Container::Heading { .. }, ..)))
+            .take_while(|e| !matches!(e, crate::Event::End(crate::Container::Heading { .. })))
+            .collect();
+        assert_eq!(super::strip_formatting(heading.into_iter()), "a b");
+        assert!(crate::Parser::new(src).any(|e| matches!(
+            e,
+            crate::Event::Start(crate::Container::Link(dst, _), _) if dst == "#a-b"
+        )));
+    }
+}
@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+/// A heading of the input, see [`crate::Parser::headings`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heading {
+    /// Level of the heading, from 1 for `#`.
+    pub level: u16,
+    /// Text of the heading, with formatting removed.
+    pub text: String,
+    /// Id of the heading, either from an explicit `{#id}` attribute or generated from the text.
+    pub id: String,
+    /// Byte range of the heading in the input, from the start of the heading marker to the end of
+    /// the text.
+    pub range: Range<usize>,
+}
+
+/// A nested table of contents, see [`crate::Parser::toc`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Toc {
+    /// Entries for the headings that are not nested below any other heading.
+    pub entries: Vec<TocEntry>,
+}
+
+/// An entry of a [`Toc`], for a single heading.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TocEntry {
+    /// The heading of the entry.
+    pub heading: Heading,
+    /// Entries for the headings nested below the heading.
+    pub children: Vec<TocEntry>,
+}
+
+impl Toc {
+    /// Create a table of contents from headings in the order they appear in the input.
+    ///
+    /// Each heading is nested below the closest preceding heading with a lower level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let heading = |level, text: &str| Heading {
+    ///     level,
+    ///     text: text.to_string(),
+    ///     id: text.to_string(),
+    ///     range: 0..0,
+    /// };
+    /// let toc = Toc::new(vec![heading(2, "a"), heading(3, "b"), heading(1, "c")]);
+    /// let tree: Vec<_> = toc
+    ///     .entries
+    ///     .iter()
+    ///     .map(|e| {
+    ///         let children: Vec<_> = e.children.iter().map(|c| c.heading.text.as_str()).collect();
+    ///         (e.heading.text.as_str(), children)
+    ///     })
+    ///     .collect();
+    /// assert_eq!(tree, [("a", vec!["b"]), ("c", vec![])]);
+    /// ```
+    #[must_use]
+    pub fn new<I>(headings: I) -> Self
+    where
+        I: IntoIterator<Item = Heading>,
+    {
+        let mut entries = Vec::new();
+        let mut open: Vec<TocEntry> = Vec::new();
+        for heading in headings {
+            while open
+                .last()
+                .map_or(false, |e| e.heading.level >= heading.level)
+            {
+                close(&mut open, &mut entries);
+            }
+            open.push(TocEntry {
+                heading,
+                children: Vec::new(),
+            });
+        }
+        while !open.is_empty() {
+            close(&mut open, &mut entries);
+        }
+        Self { entries }
+    }
+}
+
+/// Close the innermost open entry and add it to its parent.
+fn close(open: &mut Vec<TocEntry>, entries: &mut Vec<TocEntry>) {
+    let entry = open.pop().unwrap();
+    if let Some(parent) = open.last_mut() {
+        parent.children.push(entry);
+    } else {
+        entries.push(entry);
+    }
+}
+
+impl std::iter::FromIterator<Heading> for Toc {
+    fn from_iter<I>(headings: I) -> Self
+    where
+        I: IntoIterator<Item = Heading>,
+    {
+        Self::new(headings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Heading;
+    use super::Toc;
+    use super::TocEntry;
+
+    fn tree(entries: &[TocEntry]) -> String {
+        entries
+            .iter()
+            .map(|e| format!("{}[{}]", e.heading.text, tree(&e.children)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn toc(levels: &[u16]) -> String {
+        let headings = levels.iter().enumerate().map(|(i, level)| Heading {
+            level: *level,
+            text: i.to_string(),
+            id: i.to_string(),
+            range: 0..0,
+        });
+        tree(&Toc::new(headings).entries)
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(toc(&[]), "");
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(toc(&[1, 2, 3, 2, 1]), "0[1[2[]] 3[]] 4[]");
+        assert_eq!(toc(&[1, 3, 2]), "0[1[] 2[]]");
+    }
+
+    #[test]
+    fn decreasing() {
+        assert_eq!(toc(&[3, 2, 1, 2]), "0[] 1[] 2[3[]]");
+    }
+}
@@ -0,0 +1,677 @@
+//! An owned document tree that is built from an iterator of [`Event`]s.
+//!
+//! The [`Parser`] only allows forward-only processing of the document. A [`Document`] instead
+//! holds all elements of the document as a tree of [`Node`]s, which allows random access and
+//! modification of the document, e.g. moving or reordering elements. The tree can be flattened
+//! back into [`Event`]s to e.g. render it via the [`crate::Render`] trait.
+//!
+//! # Examples
+//!
+//! Move all footnotes to the beginning of the document:
+//!
+//! ```
+//! # #[cfg(feature = "djot")]
+//! # {
+//! # use jotdown::*;
+//! # use jotdown::tree::*;
+//! let mut doc = Document::parse("text[^a]\n\n[^a]: note\n");
+//! let (footnotes, mut rest): (Vec<_>, Vec<_>) = doc.children.drain(..).partition(|n| {
+//!     matches!(n, Node::Container { container: Container::Footnote { .. }, .. })
+//! });
+//! doc.children = footnotes;
+//! doc.children.append(&mut rest);
+//! assert_eq!(
+//!     djot::render_to_string(doc.into_events()),
+//!     "[^a]: note\n\ntext[^a]\n",
+//! );
+//! # }
+//! ```
+
+use std::ops::Range;
+
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::Parser;
+
+/// A document, consisting of a sequence of top-level nodes.
+///
+/// A document can be created from any iterator of [`Event`]s, see [`Document::from_events`] and
+/// [`Document::from_offset_iter`], or directly from a Djot string using [`Document::parse`].
+///
+/// Unbalanced events are handled leniently: an [`Event::End`] without a matching
+/// [`Event::Start`] is ignored, and containers that are never ended are closed at the end of the
+/// input.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Document<'s> {
+    /// The top-level nodes of the document.
+    pub children: Vec<Node<'s>>,
+}
+
+/// A node in a [`Document`] tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Node<'s> {
+    /// A container element, represented by an [`Event::Start`], the events of its children and
+    /// an [`Event::End`].
+    Container {
+        /// The container type.
+        container: Container<'s>,
+        /// The attributes of the [`Event::Start`].
+        attributes: Attributes<'s>,
+        /// The nodes inside the container.
+        children: Vec<Node<'s>>,
+        /// Byte range of the [`Event::Start`] in the input.
+        start: Range<usize>,
+        /// Byte range of the [`Event::End`] in the input.
+        end: Range<usize>,
+    },
+    /// An atomic element, represented by a single event.
+    ///
+    /// The event is never an [`Event::Start`] or [`Event::End`] for nodes created from events.
+    Atom {
+        /// The event of the element.
+        event: Event<'s>,
+        /// Byte range of the event in the input.
+        range: Range<usize>,
+    },
+}
+
+impl<'s> Document<'s> {
+    /// Create an empty document.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a Djot string into a document, with the byte ranges of all events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::tree::*;
+    /// let src = "_a_";
+    /// let doc = Document::parse(src);
+    /// let emph = &doc.children[0].children()[0];
+    /// assert_eq!(&src[emph.range()], "_a_");
+    /// ```
+    #[must_use]
+    pub fn parse(src: &'s str) -> Self {
+        Self::from_offset_iter(Parser::new(src).into_offset_iter())
+    }
+
+    /// Build a document from events without byte ranges.
+    ///
+    /// All ranges in the tree will be empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::tree::*;
+    /// let doc = Document::from_events(Parser::new("a"));
+    /// assert_eq!(
+    ///     doc.children,
+    ///     [Node::container(
+    ///         Container::Paragraph,
+    ///         Attributes::new(),
+    ///         vec![Node::atom(Event::Str("a".into()))],
+    ///     )],
+    /// );
+    /// ```
+    pub fn from_events<I>(events: I) -> Self
+    where
+        I: Iterator<Item = Event<'s>>,
+    {
+        Self::from_offset_iter(events.map(|e| (e, 0..0)))
+    }
+
+    /// Build a document from events paired with their byte ranges, e.g. from
+    /// [`Parser::into_offset_iter`].
+    pub fn from_offset_iter<I>(events: I) -> Self
+    where
+        I: Iterator<Item = (Event<'s>, Range<usize>)>,
+    {
+        let mut builder = Builder::default();
+        events.for_each(|(e, range)| builder.event(e, range));
+        builder.finish()
+    }
+
+    /// Returns an iterator over all nodes in the document, in pre-order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::tree::*;
+    /// let doc = Document::parse("# a\n\nb *c*\n");
+    /// let strs: Vec<_> = doc
+    ///     .nodes()
+    ///     .filter_map(|n| match n {
+    ///         Node::Atom {
+    ///             event: Event::Str(s),
+    ///             ..
+    ///         } => Some(s.as_ref()),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(strs, ["a", "b ", "c"]);
+    /// ```
+    #[must_use]
+    pub fn nodes(&self) -> Nodes<'_, 's> {
+        Nodes::new(&self.children)
+    }
+
+    /// Visit all nodes in the document, in pre-order, with mutable access.
+    ///
+    /// The children of a node are visited after the node itself, so children that are added or
+    /// removed by the callback are also taken into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "html")]
+    /// # {
+    /// # use jotdown::*;
+    /// # use jotdown::tree::*;
+    /// let mut doc = Document::parse("a _b_");
+    /// doc.walk_mut(|n| {
+    ///     if let Node::Atom {
+    ///         event: Event::Str(s),
+    ///         ..
+    ///     } = n
+    ///     {
+    ///         *s = s.to_uppercase().into();
+    ///     }
+    /// });
+    /// assert_eq!(
+    ///     html::render_to_string(doc.into_events()),
+    ///     "<p>A <em>B</em></p>\n",
+    /// );
+    /// # }
+    /// ```
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Node<'s>),
+    {
+        fn walk<'s, F>(nodes: &mut [Node<'s>], f: &mut F)
+        where
+            F: FnMut(&mut Node<'s>),
+        {
+            for n in nodes {
+                f(n);
+                if let Node::Container { children, .. } = n {
+                    walk(children, f);
+                }
+            }
+        }
+        walk(&mut self.children, &mut f);
+    }
+
+    /// Returns an iterator over the events of the document, cloned from the tree.
+    #[must_use]
+    pub fn events(&self) -> Events<'_, 's> {
+        Events {
+            stack: vec![(self.children.iter(), None)],
+        }
+    }
+
+    /// Consume the document and return an iterator over its events.
+    #[must_use]
+    pub fn into_events(self) -> IntoEvents<'s> {
+        IntoEvents(self.into_offset_iter())
+    }
+
+    /// Consume the document and return an iterator over its events together with their byte
+    /// ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::tree::*;
+    /// let src = "> a\n";
+    /// let events: Vec<_> = Parser::new(src).into_offset_iter().collect();
+    /// let doc = Document::from_offset_iter(events.clone().into_iter());
+    /// assert_eq!(doc.into_offset_iter().collect::<Vec<_>>(), events);
+    /// ```
+    #[must_use]
+    pub fn into_offset_iter(self) -> IntoOffsetIter<'s> {
+        IntoOffsetIter {
+            stack: vec![(self.children.into_iter(), None)],
+        }
+    }
+}
+
+impl<'s> FromIterator<Event<'s>> for Document<'s> {
+    fn from_iter<I: IntoIterator<Item = Event<'s>>>(iter: I) -> Self {
+        Self::from_events(iter.into_iter())
+    }
+}
+
+impl<'s> IntoIterator for Document<'s> {
+    type Item = Event<'s>;
+    type IntoIter = IntoEvents<'s>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_events()
+    }
+}
+
+impl<'s> Node<'s> {
+    /// Create a container node with empty byte ranges.
+    #[must_use]
+    pub fn container(
+        container: Container<'s>,
+        attributes: Attributes<'s>,
+        children: Vec<Node<'s>>,
+    ) -> Self {
+        Self::Container {
+            container,
+            attributes,
+            children,
+            start: 0..0,
+            end: 0..0,
+        }
+    }
+
+    /// Create an atomic node with an empty byte range.
+    #[must_use]
+    pub fn atom(event: Event<'s>) -> Self {
+        Self::Atom { event, range: 0..0 }
+    }
+
+    /// Returns the children of the node, empty for atomic nodes.
+    #[must_use]
+    pub fn children(&self) -> &[Node<'s>] {
+        match self {
+            Self::Container { children, .. } => children,
+            Self::Atom { .. } => &[],
+        }
+    }
+
+    /// Returns the children of the node, if it is a container.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Node<'s>>> {
+        match self {
+            Self::Container { children, .. } => Some(children),
+            Self::Atom { .. } => None,
+        }
+    }
+
+    /// Returns the byte range of the whole element in the input, from the start of its first
+    /// event to the end of its last event.
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Self::Container { start, end, .. } => start.start..end.end.max(start.end),
+            Self::Atom { range, .. } => range.clone(),
+        }
+    }
+
+    /// Returns an iterator over all nodes below this node, in pre-order.
+    #[must_use]
+    pub fn descendants(&self) -> Nodes<'_, 's> {
+        Nodes::new(self.children())
+    }
+}
+
+/// Iterator over the nodes of a tree, in pre-order.
+///
+/// See [`Document::nodes`] and [`Node::descendants`].
+pub struct Nodes<'a, 's> {
+    stack: Vec<std::slice::Iter<'a, Node<'s>>>,
+}
+
+impl<'a, 's> Nodes<'a, 's> {
+    fn new(nodes: &'a [Node<'s>]) -> Self {
+        Self {
+            stack: vec![nodes.iter()],
+        }
+    }
+}
+
+impl<'a, 's> Iterator for Nodes<'a, 's> {
+    type Item = &'a Node<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(nodes) = self.stack.last_mut() {
+            if let Some(n) = nodes.next() {
+                self.stack.push(n.children().iter());
+                return Some(n);
+            }
+            self.stack.pop();
+        }
+        None
+    }
+}
+
+/// Iterator over the events of a [`Document`], cloned from the tree.
+///
+/// See [`Document::events`].
+pub struct Events<'a, 's> {
+    stack: Vec<(std::slice::Iter<'a, Node<'s>>, Option<&'a Container<'s>>)>,
+}
+
+impl<'a, 's> Iterator for Events<'a, 's> {
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((nodes, container)) = self.stack.last_mut() {
+            if let Some(n) = nodes.next() {
+                match n {
+                    Node::Container {
+                        container,
+                        attributes,
+                        children,
+                        ..
+                    } => {
+                        self.stack.push((children.iter(), Some(container)));
+                        return Some(Event::Start(container.clone(), attributes.clone()));
+                    }
+                    Node::Atom { event, .. } => return Some(event.clone()),
+                }
+            }
+            let container = container.take();
+            self.stack.pop();
+            if let Some(c) = container {
+                return Some(Event::End(c.clone()));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the events of a consumed [`Document`].
+///
+/// See [`Document::into_events`].
+pub struct IntoEvents<'s>(IntoOffsetIter<'s>);
+
+impl<'s> Iterator for IntoEvents<'s> {
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(e, _)| e)
+    }
+}
+
+/// Iterator over the events of a consumed [`Document`], together with their byte ranges.
+///
+/// See [`Document::into_offset_iter`].
+pub struct IntoOffsetIter<'s> {
+    stack: Vec<(std::vec::IntoIter<Node<'s>>, Option<End<'s>>)>,
+}
+
+/// The container and range of a pending [`Event::End`].
+type End<'s> = (Container<'s>, Range<usize>);
+
+impl<'s> Iterator for IntoOffsetIter<'s> {
+    type Item = (Event<'s>, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((nodes, end)) = self.stack.last_mut() {
+            if let Some(n) = nodes.next() {
+                match n {
+                    Node::Container {
+                        container,
+                        attributes,
+                        children,
+                        start,
+                        end,
+                    } => {
+                        let e = Event::Start(container.clone(), attributes);
+                        self.stack
+                            .push((children.into_iter(), Some((container, end))));
+                        return Some((e, start));
+                    }
+                    Node::Atom { event, range } => return Some((event, range)),
+                }
+            }
+            let end = end.take();
+            self.stack.pop();
+            if let Some((c, range)) = end {
+                return Some((Event::End(c), range));
+            }
+        }
+        None
+    }
+}
+
+/// An open container and its children.
+struct Frame<'s> {
+    container: Container<'s>,
+    attributes: Attributes<'s>,
+    children: Vec<Node<'s>>,
+    start: Range<usize>,
+}
+
+#[derive(Default)]
+struct Builder<'s> {
+    stack: Vec<Frame<'s>>,
+    children: Vec<Node<'s>>,
+}
+
+impl<'s> Builder<'s> {
+    fn children(&mut self) -> &mut Vec<Node<'s>> {
+        self.stack
+            .last_mut()
+            .map_or(&mut self.children, |f| &mut f.children)
+    }
+
+    fn event(&mut self, e: Event<'s>, range: Range<usize>) {
+        match e {
+            Event::Start(container, attributes) => self.stack.push(Frame {
+                container,
+                attributes,
+                children: Vec::new(),
+                start: range,
+            }),
+            Event::End(..) => {
+                if let Some(frame) = self.stack.pop() {
+                    self.close(frame, range);
+                }
+            }
+            event => self.children().push(Node::Atom { event, range }),
+        }
+    }
+
+    fn close(&mut self, frame: Frame<'s>, end: Range<usize>) {
+        let Frame {
+            container,
+            attributes,
+            children,
+            start,
+        } = frame;
+        self.children().push(Node::Container {
+            container,
+            attributes,
+            children,
+            start,
+            end,
+        });
+    }
+
+    fn finish(mut self) -> Document<'s> {
+        while let Some(frame) = self.stack.pop() {
+            let end = frame
+                .children
+                .last()
+                .map_or(frame.start.end, |n| n.range().end);
+            self.close(frame, end..end);
+        }
+        Document {
+            children: self.children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Document;
+    use super::Node;
+    use crate::Attributes;
+    use crate::Container::*;
+    use crate::Event::*;
+    use crate::ListBulletType;
+    use crate::ListKind;
+    use crate::Parser;
+
+    const SRC: &str = concat!(
+        "{#s}\n",         //
+        "# heading\n",    //
+        "\n",             //
+        "> - a\n",        //
+        ">   - b[^f]\n",  //
+        "\n",             //
+        "| c | d |\n",    //
+        "^ caption\n",    //
+        "\n",             //
+        "[^f]: *note*\n", //
+        "\n",             //
+        "```\n",          //
+        "code\n",         //
+        "```\n",
+    );
+
+    #[test]
+    fn roundtrip_events() {
+        let events: Vec<_> = Parser::new(SRC).collect();
+        let doc = Document::from_events(events.clone().into_iter());
+        assert_eq!(doc.events().collect::<Vec<_>>(), events);
+        assert_eq!(doc.into_events().collect::<Vec<_>>(), events);
+    }
+
+    #[test]
+    fn roundtrip_offsets() {
+        let events: Vec<_> = Parser::new(SRC).into_offset_iter().collect();
+        let doc = Document::parse(SRC);
+        assert_eq!(doc.into_offset_iter().collect::<Vec<_>>(), events);
+    }
+
+    #[test]
+    fn structure() {
+        let doc = Document::parse("a\n\n> b\n");
+        assert_eq!(
+            doc.children,
+            [
+                Node::Container {
+                    container: Paragraph,
+                    attributes: Attributes::new(),
+                    children: vec![Node::Atom {
+                        event: Str("a".into()),
+                        range: 0..1,
+                    }],
+                    start: 0..0,
+                    end: 2..2,
+                },
+                Node::Atom {
+                    event: Blankline,
+                    range: 2..3,
+                },
+                Node::Container {
+                    container: Blockquote,
+                    attributes: Attributes::new(),
+                    children: vec![Node::Container {
+                        container: Paragraph,
+                        attributes: Attributes::new(),
+                        children: vec![Node::Atom {
+                            event: Str("b".into()),
+                            range: 5..6,
+                        }],
+                        start: 5..5,
+                        end: 7..7,
+                    }],
+                    start: 3..4,
+                    end: 7..7,
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn nodes_pre_order() {
+        let doc = Document::parse("- a\n- b *c*\n");
+        let events: Vec<_> = doc
+            .nodes()
+            .map(|n| match n {
+                Node::Container { container, .. } => Start(container.clone(), Attributes::new()),
+                Node::Atom { event, .. } => event.clone(),
+            })
+            .collect();
+        assert_eq!(
+            events,
+            [
+                Start(
+                    List {
+                        kind: ListKind::Unordered(ListBulletType::Dash),
+                        tight: true,
+                    },
+                    Attributes::new(),
+                ),
+                Start(ListItem, Attributes::new()),
+                Start(Paragraph, Attributes::new()),
+                Str("a".into()),
+                Start(ListItem, Attributes::new()),
+                Start(Paragraph, Attributes::new()),
+                Str("b ".into()),
+                Start(Strong, Attributes::new()),
+                Str("c".into()),
+            ],
+        );
+        assert_eq!(doc.children[0].descendants().count(), 8);
+    }
+
+    #[test]
+    fn walk_mut_new_children() {
+        let mut doc = Document::parse("a");
+        doc.walk_mut(|n| {
+            if let Some(children) = n.children_mut() {
+                if children.is_empty() {
+                    children.push(Node::atom(Str("b".into())));
+                }
+                if let Some(Node::Atom { event: Str(s), .. }) = children.first() {
+                    if s == "a" {
+                        children
+                            .insert(0, Node::container(Emphasis, Attributes::new(), Vec::new()));
+                    }
+                }
+            }
+        });
+        assert_eq!(
+            doc.into_events().collect::<Vec<_>>(),
+            [
+                Start(Paragraph, Attributes::new()),
+                Start(Emphasis, Attributes::new()),
+                Str("b".into()),
+                End(Emphasis),
+                Str("a".into()),
+                End(Paragraph),
+            ],
+        );
+    }
+
+    #[test]
+    fn unbalanced() {
+        let doc = Document::from_offset_iter(
+            [
+                (End(Paragraph), 0..0),
+                (Start(Paragraph, Attributes::new()), 0..0),
+                (Str("a".into()), 0..1),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            doc.children,
+            [Node::Container {
+                container: Paragraph,
+                attributes: Attributes::new(),
+                children: vec![Node::Atom {
+                    event: Str("a".into()),
+                    range: 0..1,
+                }],
+                start: 0..0,
+                end: 1..1,
+            }],
+        );
+    }
+}
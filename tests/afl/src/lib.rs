@@ -10,6 +10,8 @@ pub fn parse(data: &[u8]) {
     if let Ok(s) = std::str::from_utf8(data) {
         let whitelist_whitespace = s.contains('{') && s.contains('}'); // attributes are outside events
         let mut open = Vec::new();
+        let index = jotdown::LineIndex::new(s);
+        let pos = |offset| index.position(offset, jotdown::PositionEncoding::Utf8);
         let mut last = (jotdown::Event::Str("".into()), 0..0);
         for (event, range) in jotdown::Parser::new(s).into_offset_iter() {
             // no overlap, out of order
@@ -24,9 +26,11 @@ pub fn parse(data: &[u8]) {
                     )
                     && range.end <= last.1.start
                 ),
-                "{} > {} {:?} {:?}",
+                "{} ({}) > {} ({}) {:?} {:?}",
                 last.1.end,
+                pos(last.1.end),
                 range.start,
+                pos(range.start),
                 last.0,
                 event
             );
@@ -47,7 +51,8 @@ pub fn parse(data: &[u8]) {
         // only whitespace after last event
         assert!(
             whitelist_whitespace || s[last.1.end..].chars().all(char::is_whitespace),
-            "non whitespace {:?}",
+            "non whitespace at {} {:?}",
+            pos(last.1.end),
             &s[last.1.end..],
         );
     }